@@ -0,0 +1,54 @@
+//! 策略的调用开销：直接调用、StaticContext<S>、StaticContext<enum> 和 Context (dyn)
+//! 运行：cargo bench --bench dispatch
+//! 每种形式先预热一轮，再测量 ROUNDS 轮，报告最快的一轮，减少调度和频率变化带来的噪声。
+
+use std::hint::black_box;
+
+use design_pattern::behavioral::strategy::{
+    benchmark, benchmark_report, Benchmark, BulkDiscount, Context, PercentageOff, Pricing, StaticContext, Strategy,
+};
+
+const ITERATIONS: u64 = 10_000_000;
+const ROUNDS: usize = 5;
+
+/// 输入在 0..1000 之间变化，BulkDiscount 的两个分支都会走到
+fn input(i: u64) -> f64 {
+    (i % 1000) as f64
+}
+
+/// 预热后测量 ROUNDS 轮，返回耗时最短的一轮
+fn fastest<Out>(form: &str, call: impl Fn(f64) -> Out) -> Benchmark {
+    benchmark(form, ITERATIONS / 10, input, &call);
+    (0..ROUNDS)
+        .map(|_| benchmark(form, ITERATIONS, input, &call))
+        .min_by_key(|b| b.elapsed)
+        .expect("ROUNDS > 0")
+}
+
+fn main() {
+    let strategy = PercentageOff { percent: 10.0 };
+    let generic = StaticContext::new(PercentageOff { percent: 10.0 });
+    let enumerated: StaticContext<Pricing> = StaticContext::new(PercentageOff { percent: 10.0 }.into());
+    let dynamic: Context<f64, f64> = Context::new(Box::new(PercentageOff { percent: 10.0 }));
+    // 枚举里有多个变体时编译器不能把 match 折叠掉
+    let mixed: Vec<StaticContext<Pricing>> = vec![
+        StaticContext::new(PercentageOff { percent: 10.0 }.into()),
+        StaticContext::new(BulkDiscount { threshold: 500.0, off: 15.0 }.into()),
+    ];
+    let boxed: Vec<Context<f64, f64>> = vec![
+        Context::new(Box::new(PercentageOff { percent: 10.0 })),
+        Context::new(Box::new(BulkDiscount { threshold: 500.0, off: 15.0 })),
+    ];
+    let (strategy, generic, enumerated, dynamic) = (black_box(&strategy), black_box(&generic), black_box(&enumerated), black_box(&dynamic));
+    let (mixed, boxed) = (black_box(&mixed), black_box(&boxed));
+
+    let results = vec![
+        fastest("direct call", |x| strategy.execute(x)),
+        fastest("StaticContext<S>", |x| generic.execute(x)),
+        fastest("StaticContext<enum>", |x| enumerated.execute(x)),
+        fastest("Context (dyn)", |x| dynamic.execute(x)),
+        fastest("enum, 2 variants", |x| mixed[x as usize & 1].execute(x)),
+        fastest("dyn, 2 strategies", |x| boxed[x as usize & 1].execute(x)),
+    ];
+    print!("{}", benchmark_report(&results));
+}
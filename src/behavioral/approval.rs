@@ -0,0 +1,548 @@
+//! 采购审批流程（基于责任链）
+//! 每个审批人有审批额度，申请金额超过额度时升级给下一级审批人。
+//! 审批人可以批准、驳回或暂缓；暂缓的申请超时后（时间来自可注入的 Clock）自动升级。
+//! 每一个决定都追加到审计日志中，日志只能追加，并用哈希链校验是否被篡改。
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::behavioral::clock::{Clock, ManualClock};
+
+//-----------------申请与审批人---------------------------------------------------------------------
+/// 采购申请
+#[derive(Debug, Clone)]
+pub struct PurchaseRequest {
+    pub id: u32,
+    /// 申请人
+    pub requester: String,
+    /// 金额
+    pub amount: u64,
+    pub description: String,
+}
+
+/// 审批人的决定
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalDecision {
+    /// 批准
+    Approve,
+    /// 驳回，附带原因
+    Reject(String),
+    /// 暂缓，超时后自动升级
+    Defer,
+}
+
+/// 审批处理者 trait
+pub trait ApprovalHandler: Send + Sync {
+    fn set_next(&mut self, handler: Box<dyn ApprovalHandler>);
+    /// 审批人名称
+    fn name(&self) -> &str;
+    /// 审批额度（含）
+    fn limit(&self) -> u64;
+    /// 对额度以内的申请作出决定
+    fn review(&self, request: &PurchaseRequest) -> ApprovalDecision;
+    /// 上一级审批人
+    fn next(&self) -> Option<&dyn ApprovalHandler>;
+}
+
+/// 审批人作出决定的方式
+type ReviewPolicy = Box<dyn Fn(&PurchaseRequest) -> ApprovalDecision + Send + Sync>;
+
+/// 具体审批人
+pub struct Approver {
+    name: String,
+    limit: u64,
+    policy: ReviewPolicy,
+    next_handler: Option<Box<dyn ApprovalHandler>>,
+}
+
+impl Approver {
+    /// 创建审批人，默认批准额度内的所有申请
+    pub fn new(name: &str, limit: u64) -> Self {
+        Approver {
+            name: name.to_string(),
+            limit,
+            policy: Box::new(|_| ApprovalDecision::Approve),
+            next_handler: None,
+        }
+    }
+
+    /// 设置审批人作出决定的方式
+    pub fn with_policy(mut self, policy: impl Fn(&PurchaseRequest) -> ApprovalDecision + Send + Sync + 'static) -> Self {
+        self.policy = Box::new(policy);
+        self
+    }
+}
+
+impl ApprovalHandler for Approver {
+    fn set_next(&mut self, handler: Box<dyn ApprovalHandler>) {
+        self.next_handler = Some(handler);
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    fn review(&self, request: &PurchaseRequest) -> ApprovalDecision {
+        (self.policy)(request)
+    }
+
+    fn next(&self) -> Option<&dyn ApprovalHandler> {
+        self.next_handler.as_deref()
+    }
+}
+
+//-----------------审计日志---------------------------------------------------------------------
+/// 审计日志中记录的动作
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuditAction {
+    /// 提交申请
+    Submitted { amount: u64 },
+    /// 超过额度，升级给下一级
+    Escalated { limit: u64 },
+    Approved,
+    Rejected(String),
+    /// 暂缓到 until
+    Deferred { until: Duration },
+    /// 暂缓超时
+    TimedOut,
+    /// 整条链上都没有足够额度的审批人
+    NoApprover,
+}
+
+/// 审计日志中的一条记录，创建后不能修改
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    seq: u64,
+    at: Duration,
+    request_id: u32,
+    actor: String,
+    action: AuditAction,
+    prev_hash: u64,
+    hash: u64,
+}
+
+impl AuditEntry {
+    /// 序号，从 0 开始
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// 记录时间（时钟读数）
+    pub fn at(&self) -> Duration {
+        self.at
+    }
+
+    pub fn request_id(&self) -> u32 {
+        self.request_id
+    }
+
+    /// 执行动作的人（申请人、审批人或流程本身）
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
+    pub fn action(&self) -> &AuditAction {
+        &self.action
+    }
+
+    /// 本条记录的哈希，包含上一条记录的哈希
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    fn compute_hash(&self) -> u64 {
+        // FNV-1a
+        let text = format!(
+            "{}|{}|{}|{}|{}|{:?}",
+            self.prev_hash,
+            self.seq,
+            self.at.as_nanos(),
+            self.request_id,
+            self.actor,
+            self.action
+        );
+        text.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+}
+
+/// 只能追加的审计日志
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    // 只有审批流程可以追加记录
+    fn append(&mut self, at: Duration, request_id: u32, actor: &str, action: AuditAction) {
+        let mut entry = AuditEntry {
+            seq: self.entries.len() as u64,
+            at,
+            request_id,
+            actor: actor.to_string(),
+            action,
+            prev_hash: self.entries.last().map_or(0, |last| last.hash),
+            hash: 0,
+        };
+        entry.hash = entry.compute_hash();
+        self.entries.push(entry);
+    }
+
+    /// 全部记录
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// 某个申请的记录
+    pub fn for_request(&self, request_id: u32) -> impl Iterator<Item = &AuditEntry> {
+        self.entries.iter().filter(move |entry| entry.request_id == request_id)
+    }
+
+    /// 校验哈希链，任何一条记录被改动都会失败
+    pub fn verify(&self) -> bool {
+        let mut prev_hash = 0;
+        for entry in &self.entries {
+            if entry.prev_hash != prev_hash || entry.compute_hash() != entry.hash {
+                return false;
+            }
+            prev_hash = entry.hash;
+        }
+        true
+    }
+}
+
+//-----------------审批流程---------------------------------------------------------------------
+/// 申请的当前状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalStatus {
+    Approved { by: String },
+    Rejected { by: String, reason: String },
+    /// 等待 approver 决定，until 之后自动升级
+    Pending { approver: String, until: Duration },
+}
+
+/// 暂缓中的申请
+struct PendingRequest {
+    request: PurchaseRequest,
+    /// 暂缓它的审批人在链上的位置
+    level: usize,
+    until: Duration,
+}
+
+/// 审批流程
+pub struct ApprovalWorkflow {
+    /// 审批链的第一个审批人
+    head: Box<dyn ApprovalHandler>,
+    clock: Arc<dyn Clock>,
+    /// 暂缓多久后自动升级
+    defer_timeout: Duration,
+    pending: Vec<PendingRequest>,
+    statuses: BTreeMap<u32, ApprovalStatus>,
+    audit: AuditLog,
+}
+
+/// 流程本身在审计日志中的名称
+const WORKFLOW: &str = "workflow";
+
+/// 链上第 level 个审批人
+fn handler_at(head: &dyn ApprovalHandler, level: usize) -> Option<&dyn ApprovalHandler> {
+    let mut current = Some(head);
+    for _ in 0..level {
+        current = current?.next();
+    }
+    current
+}
+
+impl ApprovalWorkflow {
+    /// 创建审批流程
+    pub fn new(head: Box<dyn ApprovalHandler>, clock: Arc<dyn Clock>, defer_timeout: Duration) -> Self {
+        ApprovalWorkflow {
+            head,
+            clock,
+            defer_timeout,
+            pending: Vec::new(),
+            statuses: BTreeMap::new(),
+            audit: AuditLog::default(),
+        }
+    }
+
+    /// 提交申请，从第一个审批人开始审批；申请编号已经提交过时返回错误
+    pub fn submit(&mut self, request: PurchaseRequest) -> Result<ApprovalStatus, String> {
+        if self.statuses.contains_key(&request.id) {
+            return Err(format!("request {} was already submitted", request.id));
+        }
+        let now = self.clock.now();
+        self.audit.append(now, request.id, &request.requester, AuditAction::Submitted { amount: request.amount });
+        Ok(self.route(request, 0))
+    }
+
+    /// 从链上第 level 个审批人开始审批
+    fn route(&mut self, request: PurchaseRequest, mut level: usize) -> ApprovalStatus {
+        let now = self.clock.now();
+        let status = loop {
+            let Some(handler) = handler_at(self.head.as_ref(), level) else {
+                self.audit.append(now, request.id, WORKFLOW, AuditAction::NoApprover);
+                break ApprovalStatus::Rejected {
+                    by: WORKFLOW.to_string(),
+                    reason: "no approver can handle the request".to_string(),
+                };
+            };
+            if request.amount > handler.limit() {
+                self.audit.append(now, request.id, handler.name(), AuditAction::Escalated { limit: handler.limit() });
+                level += 1;
+                continue;
+            }
+            let by = handler.name().to_string();
+            break match handler.review(&request) {
+                ApprovalDecision::Approve => {
+                    self.audit.append(now, request.id, &by, AuditAction::Approved);
+                    ApprovalStatus::Approved { by }
+                }
+                ApprovalDecision::Reject(reason) => {
+                    self.audit.append(now, request.id, &by, AuditAction::Rejected(reason.clone()));
+                    ApprovalStatus::Rejected { by, reason }
+                }
+                ApprovalDecision::Defer => {
+                    let until = now + self.defer_timeout;
+                    self.audit.append(now, request.id, &by, AuditAction::Deferred { until });
+                    self.pending.push(PendingRequest { request: request.clone(), level, until });
+                    ApprovalStatus::Pending { approver: by, until }
+                }
+            };
+        };
+        self.statuses.insert(request.id, status.clone());
+        status
+    }
+
+    /// 把已经超时的暂缓申请升级给下一级审批人，返回这些申请的新状态
+    pub fn process_timeouts(&mut self) -> Vec<(u32, ApprovalStatus)> {
+        let now = self.clock.now();
+        let (expired, waiting): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.pending).into_iter().partition(|pending| pending.until <= now);
+        self.pending = waiting;
+
+        let mut updated = Vec::new();
+        for pending in expired {
+            let actor = handler_at(self.head.as_ref(), pending.level).map_or(WORKFLOW, |h| h.name()).to_string();
+            self.audit.append(now, pending.request.id, &actor, AuditAction::TimedOut);
+            let id = pending.request.id;
+            let status = self.route(pending.request, pending.level + 1);
+            updated.push((id, status));
+        }
+        updated
+    }
+
+    /// 暂缓申请的审批人作出决定；再次暂缓会重新计时
+    pub fn resolve(&mut self, request_id: u32, decision: ApprovalDecision) -> Result<ApprovalStatus, String> {
+        let index = self
+            .pending
+            .iter()
+            .position(|pending| pending.request.id == request_id)
+            .ok_or_else(|| format!("request {} is not pending", request_id))?;
+        let pending = self.pending.remove(index);
+        let now = self.clock.now();
+        let by = handler_at(self.head.as_ref(), pending.level).map_or(WORKFLOW, |h| h.name()).to_string();
+
+        let status = match decision {
+            ApprovalDecision::Approve => {
+                self.audit.append(now, request_id, &by, AuditAction::Approved);
+                ApprovalStatus::Approved { by }
+            }
+            ApprovalDecision::Reject(reason) => {
+                self.audit.append(now, request_id, &by, AuditAction::Rejected(reason.clone()));
+                ApprovalStatus::Rejected { by, reason }
+            }
+            ApprovalDecision::Defer => {
+                let until = now + self.defer_timeout;
+                self.audit.append(now, request_id, &by, AuditAction::Deferred { until });
+                self.pending.push(PendingRequest { until, ..pending });
+                ApprovalStatus::Pending { approver: by, until }
+            }
+        };
+        self.statuses.insert(request_id, status.clone());
+        Ok(status)
+    }
+
+    /// 申请的当前状态
+    pub fn status(&self, request_id: u32) -> Option<&ApprovalStatus> {
+        self.statuses.get(&request_id)
+    }
+
+    /// 审计日志
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+pub fn main() {
+    // 组长 1000 以内，经理 10000 以内（大额先暂缓），总监 100000 以内
+    let mut team_lead = Approver::new("team lead", 1_000);
+    let mut manager = Approver::new("manager", 10_000).with_policy(|request| {
+        if request.amount > 5_000 {
+            ApprovalDecision::Defer
+        } else {
+            ApprovalDecision::Approve
+        }
+    });
+    let director = Approver::new("director", 100_000).with_policy(|request| {
+        if request.description.contains("yacht") {
+            ApprovalDecision::Reject("not a business expense".to_string())
+        } else {
+            ApprovalDecision::Approve
+        }
+    });
+    manager.set_next(Box::new(director));
+    team_lead.set_next(Box::new(manager));
+
+    let clock = Arc::new(ManualClock::new(Duration::ZERO));
+    let mut workflow = ApprovalWorkflow::new(Box::new(team_lead), clock.clone(), Duration::from_secs(3600));
+
+    let request = |id, amount, description: &str| PurchaseRequest {
+        id,
+        requester: "alice".to_string(),
+        amount,
+        description: description.to_string(),
+    };
+    println!("{:?}", workflow.submit(request(1, 500, "keyboard")));
+    println!("{:?}", workflow.submit(request(2, 8_000, "servers")));
+    println!("{:?}", workflow.submit(request(3, 50_000, "yacht")));
+    println!("{:?}", workflow.submit(request(4, 500_000, "office")));
+    println!("{:?}", workflow.submit(request(1, 700, "monitor"))); // Err: 编号重复
+
+    // 一小时后经理仍未处理，申请 2 自动升级给总监
+    clock.advance(Duration::from_secs(3600));
+    println!("{:?}", workflow.process_timeouts());
+
+    for entry in workflow.audit_log().entries() {
+        println!("#{} {:?} request {} {}: {:?}", entry.seq(), entry.at(), entry.request_id(), entry.actor(), entry.action());
+    }
+    println!("audit log intact: {}", workflow.audit_log().verify());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 组长 1000、经理 10000（超过 5000 暂缓）、总监 100000（拒绝游艇）
+    fn workflow() -> (ApprovalWorkflow, Arc<ManualClock>) {
+        let mut team_lead = Approver::new("team lead", 1_000);
+        let mut manager = Approver::new("manager", 10_000).with_policy(|request| {
+            if request.amount > 5_000 {
+                ApprovalDecision::Defer
+            } else {
+                ApprovalDecision::Approve
+            }
+        });
+        let director = Approver::new("director", 100_000).with_policy(|request| {
+            if request.description.contains("yacht") {
+                ApprovalDecision::Reject("not a business expense".to_string())
+            } else {
+                ApprovalDecision::Approve
+            }
+        });
+        manager.set_next(Box::new(director));
+        team_lead.set_next(Box::new(manager));
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let workflow = ApprovalWorkflow::new(Box::new(team_lead), clock.clone(), Duration::from_secs(3600));
+        (workflow, clock)
+    }
+
+    fn request(id: u32, amount: u64, description: &str) -> PurchaseRequest {
+        PurchaseRequest { id, requester: "alice".to_string(), amount, description: description.to_string() }
+    }
+
+    fn actions(workflow: &ApprovalWorkflow, id: u32) -> Vec<(String, AuditAction)> {
+        workflow.audit_log().for_request(id).map(|e| (e.actor().to_string(), e.action().clone())).collect()
+    }
+
+    #[test]
+    fn requests_escalate_until_an_approver_has_the_limit() {
+        let (mut workflow, _) = workflow();
+        assert_eq!(workflow.submit(request(1, 500, "keyboard")), Ok(ApprovalStatus::Approved { by: "team lead".to_string() }));
+        assert_eq!(
+            workflow.submit(request(3, 50_000, "yacht")),
+            Ok(ApprovalStatus::Rejected { by: "director".to_string(), reason: "not a business expense".to_string() })
+        );
+        assert_eq!(
+            actions(&workflow, 3),
+            [
+                ("alice".to_string(), AuditAction::Submitted { amount: 50_000 }),
+                ("team lead".to_string(), AuditAction::Escalated { limit: 1_000 }),
+                ("manager".to_string(), AuditAction::Escalated { limit: 10_000 }),
+                ("director".to_string(), AuditAction::Rejected("not a business expense".to_string())),
+            ]
+        );
+        let status = workflow.submit(request(4, 500_000, "office")).unwrap();
+        assert!(matches!(status, ApprovalStatus::Rejected { ref by, .. } if by == WORKFLOW));
+        assert_eq!(actions(&workflow, 4).last().unwrap().1, AuditAction::NoApprover);
+    }
+
+    #[test]
+    fn deferred_requests_escalate_after_the_timeout() {
+        let (mut workflow, clock) = workflow();
+        let until = Duration::from_secs(3600);
+        assert_eq!(
+            workflow.submit(request(2, 8_000, "servers")),
+            Ok(ApprovalStatus::Pending { approver: "manager".to_string(), until })
+        );
+        clock.advance(Duration::from_secs(3599));
+        assert!(workflow.process_timeouts().is_empty());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(workflow.process_timeouts(), [(2, ApprovalStatus::Approved { by: "director".to_string() })]);
+        assert_eq!(workflow.status(2), Some(&ApprovalStatus::Approved { by: "director".to_string() }));
+        let log = actions(&workflow, 2);
+        assert_eq!(log[log.len() - 2], ("manager".to_string(), AuditAction::TimedOut));
+        assert!(workflow.resolve(2, ApprovalDecision::Approve).is_err());
+    }
+
+    #[test]
+    fn deferring_again_restarts_the_timeout() {
+        let (mut workflow, clock) = workflow();
+        workflow.submit(request(2, 8_000, "servers")).unwrap();
+        clock.advance(Duration::from_secs(1800));
+        let again = workflow.resolve(2, ApprovalDecision::Defer).unwrap();
+        assert_eq!(again, ApprovalStatus::Pending { approver: "manager".to_string(), until: Duration::from_secs(5400) });
+        clock.advance(Duration::from_secs(1800));
+        assert!(workflow.process_timeouts().is_empty());
+        assert_eq!(
+            workflow.resolve(2, ApprovalDecision::Approve),
+            Ok(ApprovalStatus::Approved { by: "manager".to_string() })
+        );
+    }
+
+    #[test]
+    fn duplicate_request_ids_are_rejected() {
+        let (mut workflow, _) = workflow();
+        workflow.submit(request(2, 8_000, "servers")).unwrap();
+        let entries = workflow.audit_log().entries().len();
+        assert_eq!(workflow.submit(request(2, 500, "keyboard")), Err("request 2 was already submitted".to_string()));
+        assert_eq!(workflow.audit_log().entries().len(), entries);
+        assert_eq!(workflow.pending.len(), 1);
+        assert!(matches!(workflow.status(2), Some(ApprovalStatus::Pending { .. })));
+    }
+
+    #[test]
+    fn audit_hash_chain_detects_tampering() {
+        let (mut workflow, _) = workflow();
+        workflow.submit(request(1, 500, "keyboard")).unwrap();
+        workflow.submit(request(3, 50_000, "yacht")).unwrap();
+        assert!(workflow.audit_log().verify());
+        let seqs: Vec<u64> = workflow.audit_log().entries().iter().map(|e| e.seq()).collect();
+        assert_eq!(seqs, (0..seqs.len() as u64).collect::<Vec<_>>());
+
+        workflow.audit.entries[1].actor = "mallory".to_string();
+        assert!(!workflow.audit_log().verify());
+        workflow.audit.entries[1].actor = "team lead".to_string();
+        assert!(workflow.audit_log().verify());
+        // 删除一条记录会打断哈希链
+        workflow.audit.entries.remove(2);
+        assert!(!workflow.audit_log().verify());
+    }
+}
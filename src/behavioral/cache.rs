@@ -0,0 +1,783 @@
+//! 可替换淘汰策略的有界缓存
+//! 各个服务里反复手写的缓存只是淘汰规则不同，这里把淘汰规则抽成策略，缓存本身只负责存值和统计：
+//!     Lru   淘汰最久没有访问的
+//!     Lfu   淘汰访问次数最少的，次数相同时淘汰其中最久没有访问的
+//!     Fifo  淘汰最早放入的，访问不影响顺序
+//!     AdaptiveReplacement  ARC：同时维护“只访问过一次”和“访问过多次”两组，
+//!                          根据被淘汰键的再次访问自动调整两组的容量，能抵抗一次性的顺序扫描
+//! 所有策略都建立在哈希表加双向链表上，每次操作都是 O(1)。
+//! 需要知道容量的策略（ARC）由 Cache::new 通过 set_capacity 告知，与缓存的容量始终一致。
+//! simulate 把一段访问日志依次交给每种策略重放，比较命中率。
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::behavioral::retry::{RandomSource, SeededRng};
+use crate::behavioral::strategy::UnknownStrategy;
+
+//-----------------链表集合---------------------------------------------------------------------
+struct Node<K> {
+    key: K,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// 保持顺序的集合：节点放在 Vec 中用下标相连，哈希表记录每个键所在的节点，
+/// 插入、删除、移动都是 O(1)
+struct LinkedSet<K> {
+    nodes: Vec<Option<Node<K>>>,
+    free: Vec<usize>,
+    index: HashMap<K, usize>,
+    head: Option<usize>,
+    tail: Option<usize>,
+}
+
+impl<K: Clone + Eq + Hash> LinkedSet<K> {
+    fn new() -> Self {
+        LinkedSet { nodes: Vec::new(), free: Vec::new(), index: HashMap::new(), head: None, tail: None }
+    }
+
+    fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        self.index.contains_key(key)
+    }
+
+    fn front(&self) -> Option<&K> {
+        self.head.map(|i| &self.node(i).key)
+    }
+
+    fn node(&self, i: usize) -> &Node<K> {
+        self.nodes[i].as_ref().expect("linked node is live")
+    }
+
+    fn node_mut(&mut self, i: usize) -> &mut Node<K> {
+        self.nodes[i].as_mut().expect("linked node is live")
+    }
+
+    /// 把节点 i 接在 prev 和 next 之间
+    fn link(&mut self, i: usize, prev: Option<usize>, next: Option<usize>) {
+        let node = self.node_mut(i);
+        node.prev = prev;
+        node.next = next;
+        match prev {
+            Some(p) => self.node_mut(p).next = Some(i),
+            None => self.head = Some(i),
+        }
+        match next {
+            Some(n) => self.node_mut(n).prev = Some(i),
+            None => self.tail = Some(i),
+        }
+    }
+
+    fn unlink(&mut self, i: usize) {
+        let (prev, next) = {
+            let node = self.node(i);
+            (node.prev, node.next)
+        };
+        match prev {
+            Some(p) => self.node_mut(p).next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(n) => self.node_mut(n).prev = prev,
+            None => self.tail = prev,
+        }
+    }
+
+    fn alloc(&mut self, key: K) -> usize {
+        debug_assert!(!self.contains(&key));
+        let node = Node { key: key.clone(), prev: None, next: None };
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = Some(node);
+                i
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.index.insert(key, i);
+        i
+    }
+
+    fn push_back(&mut self, key: K) {
+        let i = self.alloc(key);
+        self.link(i, self.tail, None);
+    }
+
+    fn push_front(&mut self, key: K) {
+        let i = self.alloc(key);
+        self.link(i, None, self.head);
+    }
+
+    /// 插到 anchor 后面
+    fn insert_after(&mut self, anchor: &K, key: K) {
+        let a = self.index[anchor];
+        let next = self.node(a).next;
+        let i = self.alloc(key);
+        self.link(i, Some(a), next);
+    }
+
+    fn remove(&mut self, key: &K) -> bool {
+        match self.index.remove(key) {
+            Some(i) => {
+                self.unlink(i);
+                self.nodes[i] = None;
+                self.free.push(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn pop_front(&mut self) -> Option<K> {
+        let key = self.front()?.clone();
+        self.remove(&key);
+        Some(key)
+    }
+
+    fn move_to_back(&mut self, key: &K) {
+        if let Some(&i) = self.index.get(key) {
+            self.unlink(i);
+            self.link(i, self.tail, None);
+        }
+    }
+}
+
+//-----------------淘汰策略---------------------------------------------------------------------
+/// 淘汰策略只记录键的顺序，值由 Cache 保存
+pub trait EvictionPolicy<K> {
+    fn name(&self) -> &str;
+
+    /// 命中已缓存的键
+    fn on_access(&mut self, key: &K);
+
+    /// 新键放入缓存
+    fn on_insert(&mut self, key: &K);
+
+    /// 键被显式删除
+    fn on_remove(&mut self, key: &K);
+
+    /// 缓存已满、incoming 即将放入时选出被淘汰的键，策略随即忘掉它
+    fn victim(&mut self, incoming: &K) -> Option<K>;
+
+    /// 缓存的容量，Cache::new 在使用策略之前调用；默认忽略
+    fn set_capacity(&mut self, _capacity: usize) {}
+}
+
+/// 最近最少使用
+pub struct Lru<K> {
+    order: LinkedSet<K>,
+}
+
+impl<K: Clone + Eq + Hash> Default for Lru<K> {
+    fn default() -> Self {
+        Lru { order: LinkedSet::new() }
+    }
+}
+
+impl<K: Clone + Eq + Hash> EvictionPolicy<K> for Lru<K> {
+    fn name(&self) -> &str {
+        "lru"
+    }
+
+    fn on_access(&mut self, key: &K) {
+        self.order.move_to_back(key);
+    }
+
+    fn on_insert(&mut self, key: &K) {
+        self.order.push_back(key.clone());
+    }
+
+    fn on_remove(&mut self, key: &K) {
+        self.order.remove(key);
+    }
+
+    fn victim(&mut self, _: &K) -> Option<K> {
+        self.order.pop_front()
+    }
+}
+
+/// 先进先出
+pub struct Fifo<K> {
+    order: LinkedSet<K>,
+}
+
+impl<K: Clone + Eq + Hash> Default for Fifo<K> {
+    fn default() -> Self {
+        Fifo { order: LinkedSet::new() }
+    }
+}
+
+impl<K: Clone + Eq + Hash> EvictionPolicy<K> for Fifo<K> {
+    fn name(&self) -> &str {
+        "fifo"
+    }
+
+    fn on_access(&mut self, _: &K) {}
+
+    fn on_insert(&mut self, key: &K) {
+        self.order.push_back(key.clone());
+    }
+
+    fn on_remove(&mut self, key: &K) {
+        self.order.remove(key);
+    }
+
+    fn victim(&mut self, _: &K) -> Option<K> {
+        self.order.pop_front()
+    }
+}
+
+/// 最不经常使用：每个访问次数一个桶，桶按次数从小到大连成链表，
+/// 访问时把键移到下一个桶，淘汰时取第一个桶里最久没有访问的键
+pub struct Lfu<K> {
+    counts: HashMap<K, u64>,
+    buckets: HashMap<u64, LinkedSet<K>>,
+    /// 非空桶的访问次数，从小到大
+    order: LinkedSet<u64>,
+}
+
+impl<K: Clone + Eq + Hash> Default for Lfu<K> {
+    fn default() -> Self {
+        Lfu { counts: HashMap::new(), buckets: HashMap::new(), order: LinkedSet::new() }
+    }
+}
+
+impl<K: Clone + Eq + Hash> Lfu<K> {
+    /// 从 count 桶中删除 key，桶空了就一起删掉
+    fn take(&mut self, key: &K, count: u64) {
+        let bucket = self.buckets.get_mut(&count).expect("bucket of a counted key");
+        bucket.remove(key);
+        if bucket.is_empty() {
+            self.buckets.remove(&count);
+            self.order.remove(&count);
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> EvictionPolicy<K> for Lfu<K> {
+    fn name(&self) -> &str {
+        "lfu"
+    }
+
+    fn on_access(&mut self, key: &K) {
+        let Some(&count) = self.counts.get(key) else { return };
+        let next = count + 1;
+        if let Entry::Vacant(slot) = self.buckets.entry(next) {
+            slot.insert(LinkedSet::new());
+            self.order.insert_after(&count, next);
+        }
+        self.buckets.get_mut(&next).unwrap().push_back(key.clone());
+        self.counts.insert(key.clone(), next);
+        self.take(key, count);
+    }
+
+    fn on_insert(&mut self, key: &K) {
+        if let Entry::Vacant(slot) = self.buckets.entry(1) {
+            slot.insert(LinkedSet::new());
+            self.order.push_front(1);
+        }
+        self.buckets.get_mut(&1).unwrap().push_back(key.clone());
+        self.counts.insert(key.clone(), 1);
+    }
+
+    fn on_remove(&mut self, key: &K) {
+        if let Some(count) = self.counts.remove(key) {
+            self.take(key, count);
+        }
+    }
+
+    fn victim(&mut self, _: &K) -> Option<K> {
+        let count = *self.order.front()?;
+        let key = self.buckets[&count].front()?.clone();
+        self.counts.remove(&key);
+        self.take(&key, count);
+        Some(key)
+    }
+}
+
+/// 自适应替换（ARC）
+/// t1 只访问过一次的键，t2 访问过多次的键，b1、b2 分别是从 t1、t2 淘汰的键（只记键，不占缓存）；
+/// 命中 b1 说明 t1 太小，target 增大，命中 b2 则减小；淘汰时 t1 超过 target 就从 t1 淘汰，否则从 t2
+pub struct AdaptiveReplacement<K> {
+    capacity: usize,
+    /// t1 的目标大小
+    target: usize,
+    t1: LinkedSet<K>,
+    t2: LinkedSet<K>,
+    b1: LinkedSet<K>,
+    b2: LinkedSet<K>,
+    /// victim 中已经为这个键调整过 target
+    adapted: Option<K>,
+}
+
+impl<K: Clone + Eq + Hash> Default for AdaptiveReplacement<K> {
+    fn default() -> Self {
+        AdaptiveReplacement::new(1)
+    }
+}
+
+impl<K: Clone + Eq + Hash> AdaptiveReplacement<K> {
+    /// 放进 Cache 时容量会被换成缓存的容量
+    pub fn new(capacity: usize) -> Self {
+        AdaptiveReplacement {
+            capacity: capacity.max(1),
+            target: 0,
+            t1: LinkedSet::new(),
+            t2: LinkedSet::new(),
+            b1: LinkedSet::new(),
+            b2: LinkedSet::new(),
+            adapted: None,
+        }
+    }
+
+    /// t1 的目标大小
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    fn adapt(&mut self, key: &K) {
+        if self.b1.contains(key) {
+            let delta = (self.b2.len() / self.b1.len()).max(1);
+            self.target = (self.target + delta).min(self.capacity);
+        } else if self.b2.contains(key) {
+            let delta = (self.b1.len() / self.b2.len()).max(1);
+            self.target = self.target.saturating_sub(delta);
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> EvictionPolicy<K> for AdaptiveReplacement<K> {
+    fn name(&self) -> &str {
+        "arc"
+    }
+
+    fn on_access(&mut self, key: &K) {
+        if self.t1.remove(key) || self.t2.remove(key) {
+            self.t2.push_back(key.clone());
+        }
+    }
+
+    fn on_insert(&mut self, key: &K) {
+        if self.adapted.take().as_ref() != Some(key) {
+            self.adapt(key);
+        }
+        if self.b1.remove(key) || self.b2.remove(key) {
+            self.t2.push_back(key.clone());
+        } else {
+            self.t1.push_back(key.clone());
+        }
+        // 记录的键最多为 2 * capacity 个，其中 t1 + b1 最多 capacity 个
+        while self.t1.len() + self.b1.len() > self.capacity && self.b1.pop_front().is_some() {}
+        while self.t1.len() + self.t2.len() + self.b1.len() + self.b2.len() > 2 * self.capacity && self.b2.pop_front().is_some() {}
+    }
+
+    fn on_remove(&mut self, key: &K) {
+        for list in [&mut self.t1, &mut self.t2, &mut self.b1, &mut self.b2] {
+            list.remove(key);
+        }
+    }
+
+    fn victim(&mut self, incoming: &K) -> Option<K> {
+        self.adapt(incoming);
+        self.adapted = Some(incoming.clone());
+
+        let from_t1 = !self.t1.is_empty()
+            && (self.t1.len() > self.target || (self.b2.contains(incoming) && self.t1.len() == self.target));
+        if from_t1 || self.t2.is_empty() {
+            let key = self.t1.pop_front()?;
+            self.b1.push_back(key.clone());
+            Some(key)
+        } else {
+            let key = self.t2.pop_front()?;
+            self.b2.push_back(key.clone());
+            Some(key)
+        }
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.target = self.target.min(self.capacity);
+    }
+}
+
+/// 全部淘汰策略
+pub fn policies<K: Clone + Eq + Hash + 'static>() -> Vec<Box<dyn EvictionPolicy<K>>> {
+    vec![
+        Box::new(Lru::default()),
+        Box::new(Lfu::default()),
+        Box::new(Fifo::default()),
+        Box::new(AdaptiveReplacement::default()),
+    ]
+}
+
+/// 按名称选择淘汰策略；名称写错时提示最接近的一个
+pub fn policy_by_name<K: Clone + Eq + Hash + 'static>(name: &str) -> Result<Box<dyn EvictionPolicy<K>>, UnknownStrategy> {
+    let all = policies();
+    let available = all.iter().map(|p| p.name().to_string()).collect();
+    all.into_iter()
+        .find(|p| p.name() == name)
+        .ok_or(UnknownStrategy { requested: name.to_string(), available })
+}
+
+//-----------------缓存---------------------------------------------------------------------
+/// 命中、未命中和淘汰统计
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub insertions: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses, {} evictions, hit rate {:.1}%",
+            self.hits,
+            self.misses,
+            self.evictions,
+            self.hit_rate() * 100.0
+        )
+    }
+}
+
+/// 最多保存 capacity 个键的缓存
+pub struct Cache<K, V> {
+    capacity: usize,
+    entries: HashMap<K, V>,
+    policy: Box<dyn EvictionPolicy<K>>,
+    stats: CacheStats,
+}
+
+impl<K: Clone + Eq + Hash, V> Cache<K, V> {
+    /// 策略的容量设置为缓存的容量
+    pub fn new(capacity: usize, mut policy: Box<dyn EvictionPolicy<K>>) -> Self {
+        let capacity = capacity.max(1);
+        policy.set_capacity(capacity);
+        Cache { capacity, entries: HashMap::new(), policy, stats: CacheStats::default() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn policy_name(&self) -> &str {
+        self.policy.name()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// 查找并计入统计
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if self.entries.contains_key(key) {
+            self.stats.hits += 1;
+            self.policy.on_access(key);
+            self.entries.get(key)
+        } else {
+            self.stats.misses += 1;
+            None
+        }
+    }
+
+    /// 查看但不计入统计，也不影响淘汰顺序
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// 放入键值，已存在时更新值并视为一次访问；返回被淘汰的键值
+    pub fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Some(slot) = self.entries.get_mut(&key) {
+            *slot = value;
+            self.policy.on_access(&key);
+            return None;
+        }
+        let evicted = if self.entries.len() >= self.capacity {
+            self.policy.victim(&key).and_then(|victim| self.entries.remove_entry(&victim))
+        } else {
+            None
+        };
+        if evicted.is_some() {
+            self.stats.evictions += 1;
+        }
+        self.policy.on_insert(&key);
+        self.entries.insert(key, value);
+        self.stats.insertions += 1;
+        evicted
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let value = self.entries.remove(key)?;
+        self.policy.on_remove(key);
+        Some(value)
+    }
+}
+
+//-----------------访问日志模拟---------------------------------------------------------------------
+/// 一种策略的模拟结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationResult {
+    pub policy: String,
+    pub stats: CacheStats,
+}
+
+/// 解析访问日志：每行取最后一个字段作为键，忽略空行和 # 开头的注释
+pub fn parse_trace(log: &str) -> Vec<String> {
+    log.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_whitespace().last().map(str::to_string))
+        .collect()
+}
+
+/// 用每种策略重放访问序列：命中就继续，未命中就放入缓存
+pub fn simulate<K: Clone + Eq + Hash + 'static>(trace: &[K], capacity: usize) -> Vec<SimulationResult> {
+    policies()
+        .into_iter()
+        .map(|policy| {
+            let mut cache = Cache::new(capacity, policy);
+            for key in trace {
+                if cache.get(key).is_none() {
+                    cache.put(key.clone(), ());
+                }
+            }
+            SimulationResult { policy: cache.policy_name().to_string(), stats: cache.stats() }
+        })
+        .collect()
+}
+
+/// 模拟结果表格，按命中率从高到低
+pub fn simulation_report(results: &[SimulationResult]) -> String {
+    let mut sorted: Vec<&SimulationResult> = results.iter().collect();
+    sorted.sort_by(|a, b| b.stats.hit_rate().total_cmp(&a.stats.hit_rate()));
+    let mut out = format!("{:<6} {:>8} {:>8} {:>9} {:>9}\n", "policy", "hits", "misses", "evictions", "hit rate");
+    for r in sorted {
+        out.push_str(&format!(
+            "{:<6} {:>8} {:>8} {:>9} {:>8.1}%\n",
+            r.policy,
+            r.stats.hits,
+            r.stats.misses,
+            r.stats.evictions,
+            r.stats.hit_rate() * 100.0
+        ));
+    }
+    out
+}
+
+//--------------------------------------------------------------------------------------------------
+/// 容量为 2 时依次放入 a、b，访问 a，再放入 c，返回被淘汰的键
+fn evicted_by(name: &str) -> String {
+    let mut cache = Cache::new(2, policy_by_name(name).unwrap());
+    cache.put("a".to_string(), 1);
+    cache.put("b".to_string(), 2);
+    cache.get(&"a".to_string());
+    cache.put("c".to_string(), 3).unwrap().0
+}
+
+/// 访问日志：3 个热点键，中间夹着一次性的扫描
+fn hot_and_scan_log() -> String {
+    let mut log = String::from("# time method path\n");
+    for round in 0..200 {
+        for hot in [0, 1, 2, 0, 1, 2] {
+            log.push_str(&format!("{:04} GET /hot/{}\n", round, hot));
+        }
+        for scan in 0..2 {
+            log.push_str(&format!("{:04} GET /scan/{}/{}\n", round, round, scan));
+        }
+    }
+    log
+}
+
+/// 大量随机访问：80% 集中在 500 个热点键上，其余分散在 50000 个键上；
+/// 固定种子保证每次的访问序列相同
+fn skewed_trace(len: usize, seed: u64) -> Vec<u64> {
+    let mut rng = SeededRng::new(seed);
+    (0..len)
+        .map(|_| {
+            let value = rng.next_u64();
+            if value % 10 < 8 { value % 500 } else { value % 50_000 }
+        })
+        .collect()
+}
+
+pub fn main() {
+    for name in ["lru", "lfu", "fifo", "arc"] {
+        println!("{}: put a, b; get a; put c evicts {}", name, evicted_by(name));
+    }
+    println!("{}", policy_by_name::<String>("mru").err().unwrap());
+
+    let trace = parse_trace(&hot_and_scan_log());
+    print!("{}", simulation_report(&simulate(&trace, 4)));
+    print!("{}", simulation_report(&simulate(&skewed_trace(200_000, 45), 1_000)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按 Cache::put 的方式驱动 ARC，返回被淘汰的键，便于检查 target
+    fn arc_request(arc: &mut AdaptiveReplacement<&'static str>, resident: &mut Vec<&'static str>, key: &'static str) -> Option<&'static str> {
+        if resident.contains(&key) {
+            arc.on_access(&key);
+            return None;
+        }
+        let victim = if resident.len() >= arc.capacity { arc.victim(&key) } else { None };
+        resident.retain(|k| Some(*k) != victim);
+        arc.on_insert(&key);
+        resident.push(key);
+        victim
+    }
+
+    #[test]
+    fn policies_evict_their_own_victim() {
+        assert_eq!(evicted_by("lru"), "b");
+        assert_eq!(evicted_by("lfu"), "b");
+        assert_eq!(evicted_by("fifo"), "a");
+        assert_eq!(evicted_by("arc"), "b");
+        let error = policy_by_name::<String>("mru").err().unwrap();
+        assert_eq!(error.available, vec!["lru", "lfu", "fifo", "arc"]);
+        assert_eq!(error.to_string(), "unknown strategy \"mru\", did you mean \"lru\"? (available: lru, lfu, fifo, arc)");
+    }
+
+    #[test]
+    fn cache_sets_the_policy_capacity() {
+        // 策略创建时的容量与缓存不同，以缓存为准
+        let mut cache = Cache::new(3, Box::new(AdaptiveReplacement::new(100)));
+        for key in ["a", "b", "c", "d", "e"] {
+            cache.put(key, ());
+        }
+        assert_eq!(cache.len(), 3);
+        let mut arc = AdaptiveReplacement::<u32>::new(100);
+        arc.set_capacity(3);
+        assert_eq!(arc.capacity, 3);
+
+        // 创建策略时给的容量不影响重放结果
+        let trace = parse_trace(&hot_and_scan_log());
+        let replay = |policy: Box<dyn EvictionPolicy<String>>| {
+            let mut cache = Cache::new(4, policy);
+            for key in &trace {
+                if cache.get(key).is_none() {
+                    cache.put(key.clone(), ());
+                }
+            }
+            cache.stats()
+        };
+        let expected = replay(Box::new(AdaptiveReplacement::new(4)));
+        assert_eq!(replay(Box::new(AdaptiveReplacement::new(400))), expected);
+        assert_eq!(replay(policy_by_name("arc").unwrap()), expected);
+    }
+
+    #[test]
+    fn lfu_breaks_ties_by_least_recent_access() {
+        let mut cache = Cache::new(3, Box::new(Lfu::default()));
+        for key in ["x", "y", "z", "x", "y", "z", "y"] {
+            if cache.get(&key).is_none() {
+                cache.put(key, ());
+            }
+        }
+        // x 和 z 都访问了 2 次，x 更早
+        assert_eq!(cache.put("w", ()).map(|(k, _)| k), Some("x"));
+        // w 只有 1 次，比其他键都少
+        assert_eq!(cache.put("v", ()).map(|(k, _)| k), Some("w"));
+        // 都只放入过一次时按放入顺序
+        let mut fresh = Cache::new(2, Box::new(Lfu::default()));
+        fresh.put(1, ());
+        fresh.put(2, ());
+        assert_eq!(fresh.put(3, ()).map(|(k, _)| k), Some(1));
+    }
+
+    #[test]
+    fn arc_adapts_to_ghost_hits() {
+        let mut arc = AdaptiveReplacement::new(2);
+        let mut resident = Vec::new();
+        arc_request(&mut arc, &mut resident, "a");
+        arc_request(&mut arc, &mut resident, "a");
+        arc_request(&mut arc, &mut resident, "b");
+        // t1 超过 target，从 t1 淘汰 b，b 留在 b1 中
+        assert_eq!(arc_request(&mut arc, &mut resident, "c"), Some("b"));
+        assert_eq!(arc.target(), 0);
+        assert!(arc.t2.contains(&"a") && arc.b1.contains(&"b"));
+
+        // b 刚从 t1 淘汰又被访问：t1 太小，target 增大，这次从 t2 淘汰 a
+        assert_eq!(arc_request(&mut arc, &mut resident, "b"), Some("a"));
+        assert_eq!(arc.target(), 1);
+        assert!(arc.t2.contains(&"b") && arc.b2.contains(&"a"));
+
+        // a 从 t2 淘汰后又被访问：t2 太小，target 减小，从 t1 淘汰 c
+        assert_eq!(arc_request(&mut arc, &mut resident, "a"), Some("c"));
+        assert_eq!(arc.target(), 0);
+        assert_eq!(resident, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn arc_and_lfu_resist_one_off_scans() {
+        let trace = parse_trace(&hot_and_scan_log());
+        assert_eq!(trace.len(), 1600);
+        let results = simulate(&trace, 4);
+        let rate = |name: &str| results.iter().find(|r| r.policy == name).unwrap().stats.hit_rate();
+        assert!(rate("arc") > rate("lru") && rate("lfu") > rate("lru"));
+    }
+
+    #[test]
+    fn remove_frees_the_slot_and_the_policy_forgets_the_key() {
+        for name in ["lru", "lfu", "fifo", "arc"] {
+            let mut cache = Cache::new(2, policy_by_name(name).unwrap());
+            cache.put("a", 1);
+            cache.put("b", 2);
+            assert_eq!(cache.remove(&"a"), Some(1), "{}", name);
+            assert_eq!(cache.remove(&"a"), None, "{}", name);
+            assert_eq!(cache.len(), 1);
+            assert_eq!(cache.put("c", 3), None, "{}", name);
+            // 被删除的 a 不会再被选为淘汰对象
+            assert_eq!(cache.put("d", 4), Some(("b", 2)), "{}", name);
+            assert_eq!(cache.len(), 2);
+            assert!(cache.contains(&"c") && cache.contains(&"d"));
+        }
+    }
+
+    #[test]
+    fn simulation_counts_every_lookup() {
+        let trace = skewed_trace(20_000, 45);
+        for r in simulate(&trace, 1_000) {
+            assert_eq!(r.stats.hits + r.stats.misses, trace.len() as u64, "{}", r.policy);
+            assert_eq!(r.stats.insertions - r.stats.evictions, 1_000, "{}", r.policy);
+        }
+    }
+}
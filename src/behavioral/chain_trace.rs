@@ -0,0 +1,391 @@
+//! 责任链追踪与统计
+//! 请求经过一条很长的责任链时，只看输出无法知道是谁处理了它、每一步花了多久、
+//! 以及为什么最后落到了 "No handler can handle the request"。
+//! ChainTracer 代替处理者自己转发请求：逐个调用 `try_handle`，为每个请求记录一条追踪，
+//! 并按处理者在链上的位置汇总计数和耗时直方图，可以导出为文本。
+//! 统计按位置而不是名称区分，链上有同名处理者时也不会混在一起。
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::behavioral::responsibility_chain::{ConcreteHandlerA, ConcreteHandlerB, Decision, Handler, Request};
+
+//-----------------单个请求的追踪---------------------------------------------------------------------
+/// 请求在某个处理者上的一步
+#[derive(Debug, Clone)]
+pub struct TraceStep {
+    /// 处理者名称
+    pub handler: String,
+    /// 处理者的决定
+    pub decision: Decision,
+    /// 做出决定花费的时间
+    pub elapsed: Duration,
+}
+
+/// 一个请求经过责任链的完整追踪
+#[derive(Debug, Clone)]
+pub struct RequestTrace {
+    /// 请求内容
+    pub request: String,
+    /// 按访问顺序记录的每一步
+    pub steps: Vec<TraceStep>,
+    /// 整条链的总耗时
+    pub total: Duration,
+}
+
+impl RequestTrace {
+    /// 处理该请求的处理者，没有人处理时返回 None
+    pub fn handled_by(&self) -> Option<&str> {
+        self.steps
+            .iter()
+            .find(|step| step.decision == Decision::Handled)
+            .map(|step| step.handler.as_str())
+    }
+}
+
+impl fmt::Display for RequestTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.handled_by() {
+            Some(handler) => writeln!(f, "request {:?}: handled by {} in {:?}", self.request, handler, self.total)?,
+            None => writeln!(f, "request {:?}: no handler can handle the request ({:?})", self.request, self.total)?,
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            match &step.decision {
+                Decision::Handled => writeln!(f, "  {}. {} handled ({:?})", i + 1, step.handler, step.elapsed)?,
+                Decision::Pass(reason) => {
+                    writeln!(f, "  {}. {} passed: {} ({:?})", i + 1, step.handler, reason, step.elapsed)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+//-----------------耗时直方图与处理者统计---------------------------------------------------------------------
+/// 直方图各桶的上界，最后一个桶收纳所有更慢的请求
+const BUCKET_BOUNDS: [Duration; 7] = [
+    Duration::from_micros(1),
+    Duration::from_micros(10),
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+];
+
+/// 固定分桶的耗时直方图
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    counts: [u64; BUCKET_BOUNDS.len() + 1],
+    total: Duration,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    /// 记录一次耗时
+    pub fn record(&mut self, elapsed: Duration) {
+        let bucket = BUCKET_BOUNDS
+            .iter()
+            .position(|bound| elapsed <= *bound)
+            .unwrap_or(BUCKET_BOUNDS.len());
+        self.counts[bucket] += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+
+    /// 记录的次数
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// 平均耗时
+    pub fn mean(&self) -> Duration {
+        match self.count() {
+            0 => Duration::ZERO,
+            n => {
+                // 按纳秒做 u128 除法，次数超过 u32::MAX 时也不会截断
+                let nanos = self.total.as_nanos() / n as u128;
+                Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32)
+            }
+        }
+    }
+
+    /// 最大耗时
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// 按 (桶上界, 次数) 列出各桶，上界为 None 表示最后一个桶
+    pub fn buckets(&self) -> Vec<(Option<Duration>, u64)> {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (BUCKET_BOUNDS.get(i).copied(), *count))
+            .collect()
+    }
+}
+
+/// 单个处理者的汇总统计
+#[derive(Debug, Clone, Default)]
+pub struct HandlerMetrics {
+    /// 处理者名称
+    pub handler: String,
+    /// 被访问的次数
+    pub visited: u64,
+    /// 处理请求的次数
+    pub handled: u64,
+    /// 交给下一个处理者的次数
+    pub passed: u64,
+    /// 做决定的耗时分布
+    pub latency: LatencyHistogram,
+}
+
+//-----------------追踪器---------------------------------------------------------------------
+/// 责任链追踪器：负责沿链分发请求，并记录追踪和统计
+pub struct ChainTracer {
+    /// 按处理者在链上的位置（从 0 开始）汇总的统计
+    metrics: BTreeMap<usize, HandlerMetrics>,
+    /// 最近的请求追踪
+    traces: VecDeque<RequestTrace>,
+    /// 最多保留的追踪条数
+    max_traces: usize,
+    /// 分发过的请求数
+    requests: u64,
+    /// 没有处理者处理的请求数
+    unhandled: u64,
+}
+
+impl ChainTracer {
+    /// 创建追踪器，最多保留最近 max_traces 条追踪
+    pub fn new(max_traces: usize) -> Self {
+        ChainTracer {
+            metrics: BTreeMap::new(),
+            traces: VecDeque::new(),
+            max_traces,
+            requests: 0,
+            unhandled: 0,
+        }
+    }
+
+    /// 从 head 开始沿链分发请求，返回这个请求的追踪
+    pub fn dispatch(&mut self, head: &dyn Handler, request: &Request) -> RequestTrace {
+        let started = Instant::now();
+        let mut steps = Vec::new();
+        let mut current = Some(head);
+        let mut position = 0;
+        while let Some(handler) = current {
+            let start = Instant::now();
+            let decision = handler.try_handle(request);
+            let elapsed = start.elapsed();
+
+            let metrics = self.metrics.entry(position).or_insert_with(|| HandlerMetrics {
+                handler: handler.name().to_string(),
+                ..HandlerMetrics::default()
+            });
+            metrics.visited += 1;
+            metrics.latency.record(elapsed);
+            let handled = decision == Decision::Handled;
+            if handled {
+                metrics.handled += 1;
+            } else {
+                metrics.passed += 1;
+            }
+
+            steps.push(TraceStep {
+                handler: handler.name().to_string(),
+                decision,
+                elapsed,
+            });
+            current = if handled { None } else { handler.next() };
+            position += 1;
+        }
+
+        let trace = RequestTrace {
+            request: request.content.clone(),
+            steps,
+            total: started.elapsed(),
+        };
+        self.requests += 1;
+        if trace.handled_by().is_none() {
+            self.unhandled += 1;
+        }
+        if self.max_traces > 0 {
+            if self.traces.len() == self.max_traces {
+                self.traces.pop_front();
+            }
+            self.traces.push_back(trace.clone());
+        }
+        trace
+    }
+
+    /// 最近的请求追踪，从旧到新
+    pub fn traces(&self) -> impl Iterator<Item = &RequestTrace> {
+        self.traces.iter()
+    }
+
+    /// 链上第 position 个处理者（从 0 开始）的统计
+    pub fn metrics(&self, position: usize) -> Option<&HandlerMetrics> {
+        self.metrics.get(&position)
+    }
+
+    /// 把汇总统计导出为文本
+    pub fn dump(&self) -> String {
+        let mut out = format!("requests: {}, unhandled: {}\n", self.requests, self.unhandled);
+        for (position, metrics) in &self.metrics {
+            out.push_str(&format!(
+                "{}. {}: visited={} handled={} passed={} mean={:?} max={:?}\n",
+                position + 1,
+                metrics.handler,
+                metrics.visited,
+                metrics.handled,
+                metrics.passed,
+                metrics.latency.mean(),
+                metrics.latency.max()
+            ));
+            for (bound, count) in metrics.latency.buckets() {
+                if count == 0 {
+                    continue;
+                }
+                match bound {
+                    Some(bound) => out.push_str(&format!("  <= {:?}: {}\n", bound, count)),
+                    None => out.push_str(&format!("  >  {:?}: {}\n", BUCKET_BOUNDS[BUCKET_BOUNDS.len() - 1], count)),
+                }
+            }
+        }
+        out
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+pub fn main() {
+    let mut handler_a = ConcreteHandlerA { next_handler: None };
+    let handler_b = ConcreteHandlerB { next_handler: None };
+    handler_a.set_next(Box::new(handler_b));
+
+    let mut tracer = ChainTracer::new(100);
+    for content in ["Request A", "Request B", "Request C"] {
+        let request = Request { content: content.to_string() };
+        let trace = tracer.dispatch(&handler_a, &request);
+        print!("{}", trace);
+    }
+    print!("{}", tracer.dump());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 名称可以重复的处理者，内容包含 token 时处理请求
+    struct Named {
+        name: &'static str,
+        token: &'static str,
+        next: Option<Box<dyn Handler>>,
+    }
+
+    impl Handler for Named {
+        fn set_next(&mut self, handler: Box<dyn Handler>) {
+            self.next = Some(handler);
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn try_handle(&self, request: &Request) -> Decision {
+            if request.content.contains(self.token) {
+                Decision::Handled
+            } else {
+                Decision::Pass(format!("no {}", self.token))
+            }
+        }
+
+        fn next(&self) -> Option<&dyn Handler> {
+            self.next.as_deref()
+        }
+    }
+
+    fn chain() -> Named {
+        let mut head = Named { name: "filter", token: "x", next: None };
+        head.set_next(Box::new(Named { name: "filter", token: "y", next: None }));
+        head
+    }
+
+    fn request(content: &str) -> Request {
+        Request { content: content.to_string() }
+    }
+
+    #[test]
+    fn trace_records_every_step_until_a_handler_takes_the_request() {
+        let mut tracer = ChainTracer::new(2);
+        let trace = tracer.dispatch(&chain(), &request("y"));
+        assert_eq!(trace.request, "y");
+        assert_eq!(trace.handled_by(), Some("filter"));
+        assert_eq!(trace.steps.len(), 2);
+        assert_eq!(trace.steps[0].decision, Decision::Pass("no x".to_string()));
+        assert_eq!(trace.steps[1].decision, Decision::Handled);
+
+        let missed = tracer.dispatch(&chain(), &request("z"));
+        assert_eq!(missed.handled_by(), None);
+        assert!(missed.to_string().contains("no handler can handle the request"));
+        assert!(missed.to_string().contains("2. filter passed: no y"));
+
+        // 只保留最近 max_traces 条
+        tracer.dispatch(&chain(), &request("x"));
+        let kept: Vec<&str> = tracer.traces().map(|t| t.request.as_str()).collect();
+        assert_eq!(kept, ["z", "x"]);
+    }
+
+    #[test]
+    fn handlers_with_the_same_name_are_counted_by_position() {
+        let mut tracer = ChainTracer::new(0);
+        for content in ["x", "y", "z"] {
+            tracer.dispatch(&chain(), &request(content));
+        }
+        let first = tracer.metrics(0).unwrap();
+        assert_eq!((first.visited, first.handled, first.passed), (3, 1, 2));
+        let second = tracer.metrics(1).unwrap();
+        assert_eq!((second.visited, second.handled, second.passed), (2, 1, 1));
+        assert_eq!(second.handler, "filter");
+        assert!(tracer.metrics(2).is_none());
+        assert_eq!(tracer.traces().count(), 0);
+
+        let dump = tracer.dump();
+        assert!(dump.starts_with("requests: 3, unhandled: 1\n"));
+        assert!(dump.contains("1. filter: visited=3 handled=1 passed=2"));
+        assert!(dump.contains("2. filter: visited=2 handled=1 passed=1"));
+    }
+
+    #[test]
+    fn histogram_buckets_mean_and_dump() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.mean(), Duration::ZERO);
+        histogram.record(Duration::from_nanos(500));
+        histogram.record(Duration::from_micros(50));
+        histogram.record(Duration::from_micros(50));
+        histogram.record(Duration::from_secs(3));
+        assert_eq!(histogram.count(), 4);
+        assert_eq!(histogram.max(), Duration::from_secs(3));
+        assert_eq!(histogram.mean(), Duration::from_nanos(750_025_125));
+        let buckets = histogram.buckets();
+        assert_eq!(buckets[0], (Some(Duration::from_micros(1)), 1));
+        assert_eq!(buckets[2], (Some(Duration::from_micros(100)), 2));
+        assert_eq!(buckets[7], (None, 1));
+
+        let mut tracer = ChainTracer::new(0);
+        tracer.metrics.insert(0, HandlerMetrics { handler: "slow".to_string(), visited: 4, handled: 4, latency: histogram, ..HandlerMetrics::default() });
+        let dump = tracer.dump();
+        assert!(dump.contains("  <= 1µs: 1\n"));
+        assert!(dump.contains("  <= 100µs: 2\n"));
+        assert!(dump.contains("  >  1s: 1\n"));
+        assert!(!dump.contains("<= 10µs"));
+    }
+
+    #[test]
+    fn mean_does_not_truncate_large_counts() {
+        let mut histogram = LatencyHistogram::default();
+        histogram.counts[0] = u32::MAX as u64 + 1;
+        histogram.total = Duration::from_secs(u32::MAX as u64 + 1);
+        assert_eq!(histogram.mean(), Duration::from_secs(1));
+    }
+}
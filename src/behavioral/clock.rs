@@ -0,0 +1,61 @@
+//! 可注入时钟
+//! 超时、截止时间、时间戳这类依赖"当前时间"的逻辑都通过 Clock 取时间，
+//! 运行时使用 SystemClock，演示时使用可以手动拨动的 ManualClock，结果可重复；
+//! 需要等待时也调用 Clock::sleep，ManualClock 的 sleep 只拨动时钟，不会真正阻塞。
+
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 时钟接口
+pub trait Clock: Send + Sync {
+    /// 自固定起点以来经过的时间
+    fn now(&self) -> Duration;
+
+    /// 等待一段时间，默认让当前线程睡眠
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// 系统时钟，起点为 UNIX 纪元
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+}
+
+/// 手动时钟，只有调用 advance / set 时才会走
+pub struct ManualClock {
+    now: Mutex<Duration>,
+}
+
+impl ManualClock {
+    /// 创建停在 start 的手动时钟
+    pub fn new(start: Duration) -> Self {
+        ManualClock { now: Mutex::new(start) }
+    }
+
+    /// 向前拨动时钟
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += by;
+    }
+
+    /// 直接设置当前时间
+    pub fn set(&self, to: Duration) {
+        *self.now.lock().unwrap() = to;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+
+    /// 不真正等待，直接把时钟向前拨动
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
@@ -1,7 +1,7 @@
-use std::{str, sync::{Arc, Mutex}};
+//! 命令模式（Command Pattern）是一种行为设计模式，它将请求封装为对象，从而使你能够将具有不同请求的对象参数化其他对象。
+//! 这种模式允许将操作排队、记录日志或支持撤销操作等功能。
 
-/// 命令模式（Command Pattern）是一种行为设计模式，它将请求封装为对象，从而使你能够将具有不同请求的对象参数化其他对象。
-/// 这种模式允许将操作排队、记录日志或支持撤销操作等功能。
+use std::sync::{Arc, Mutex};
 
 /// 定义 Command trait
 /// 定义一个通用的 Command trait，包含执行和撤销方法
@@ -18,6 +18,7 @@ pub trait Command {
 
 /// 具体的接收者对象：灯  >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
 /// 真正执行操作的对象。
+#[derive(Default)]
 pub struct Light{
     is_on: bool,
 }
@@ -82,6 +83,7 @@ impl Command for LightOnCommand {
 //--------------------------------------------------------------------------------------------------
 /// 调用者：遥控器
 /// 不关心具体命令如何执行，只负责调用 execute() 或 undo()。
+#[derive(Default)]
 pub struct RemoteControl{
     /// 命令对象
     command : Option<Box<dyn Command>>,
@@ -99,13 +101,13 @@ impl RemoteControl  {
         self.command = Some(command);
     }
     ///按下按钮，执行命令
-    pub fn press_button(self : &Self) {
+    pub fn press_button(&self) {
         if let Some(command) = &self.command {
             command.execute();
         }
     }
     ///撤销按钮，执行撤销命令
-    pub fn undo_button(self : &Self) {
+    pub fn undo_button(&self) {
         if let Some(ref command) = &self.command {
             command.undo();
         }
@@ -113,7 +115,7 @@ impl RemoteControl  {
 
 }
 
-fn main() {
+pub fn main() {
     //创建被调用对象
     //创建灯对象
     let light = Arc::new(Mutex::new(Light::new()));
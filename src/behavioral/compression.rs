@@ -0,0 +1,762 @@
+//! 可替换的压缩策略
+//! 给策略模式一个真实的负载：CompressionContext 持有一个压缩策略，可以在游程编码（RLE）、LZ77
+//! 和范式哈夫曼编码（canonical Huffman）之间切换，全部在本 crate 中实现，不依赖外部库。
+//! 压缩结果是自描述的帧：
+//!     magic "DPZ1" | 策略编号 1 字节 | 原始长度 u32 LE | 原始数据的 FNV-1a 校验 u32 LE | 负载
+//! 解压时根据帧中的策略编号自动选择对应的策略，并校验长度和校验和；
+//! 帧头中的原始长度会传给解码器，输出超过这个长度时立即停止，损坏或恶意的帧不会让解码器无限制地分配内存。
+
+use std::fmt;
+use std::time::Instant;
+
+use crate::behavioral::retry::{RandomSource, SeededRng};
+use crate::behavioral::strategy::{StrategyInfo, UnknownStrategy};
+
+const MAGIC: &[u8; 4] = b"DPZ1";
+const HEADER_LEN: usize = 13;
+
+/// 解压失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompressionError {
+    BadMagic,
+    UnknownCodec(u8),
+    /// 数据提前结束
+    Truncated,
+    /// 负载内容不合法
+    Corrupt(String),
+    LengthMismatch { expected: usize, actual: usize },
+    /// 解码的输出超过了帧头中的原始长度
+    Overflow { expected: usize },
+    ChecksumMismatch,
+    /// 输入超过 u32 能表示的长度，无法写入帧头
+    TooLarge(usize),
+}
+
+impl fmt::Display for CompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionError::BadMagic => write!(f, "not a compressed frame"),
+            CompressionError::UnknownCodec(id) => write!(f, "unknown codec id {}", id),
+            CompressionError::Truncated => write!(f, "frame is truncated"),
+            CompressionError::Corrupt(reason) => write!(f, "corrupt payload: {}", reason),
+            CompressionError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {} bytes, decoded {}", expected, actual)
+            }
+            CompressionError::Overflow { expected } => write!(f, "decoded more than the expected {} bytes", expected),
+            CompressionError::ChecksumMismatch => write!(f, "checksum mismatch"),
+            CompressionError::TooLarge(len) => write!(f, "{} bytes do not fit in a frame", len),
+        }
+    }
+}
+
+impl std::error::Error for CompressionError {}
+
+/// 压缩策略
+pub trait CompressionStrategy {
+    /// 写入帧头的编号，各策略互不相同
+    fn id(&self) -> u8;
+    fn name(&self) -> &'static str;
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError>;
+    /// expected 为原始长度，输出超过它时返回 Overflow
+    fn decompress(&self, payload: &[u8], expected: usize) -> Result<Vec<u8>, CompressionError>;
+
+    fn metadata(&self) -> StrategyInfo {
+        StrategyInfo::new(self.name())
+    }
+}
+
+/// FNV-1a 32 位校验
+fn fnv1a(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5, |hash: u32, byte| (hash ^ *byte as u32).wrapping_mul(0x0100_0193))
+}
+
+/// 帧中用 u32 保存的长度
+fn length_u32(len: usize) -> Result<u32, CompressionError> {
+    u32::try_from(len).map_err(|_| CompressionError::TooLarge(len))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, CompressionError> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(CompressionError::Truncated)
+}
+
+//-----------------不压缩---------------------------------------------------------------------
+/// 原样保存，用于对比，也适合已经压缩过的数据
+pub struct Stored;
+
+impl CompressionStrategy for Stored {
+    fn id(&self) -> u8 {
+        0
+    }
+
+    fn name(&self) -> &'static str {
+        "stored"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        Ok(data.to_vec())
+    }
+
+    fn decompress(&self, payload: &[u8], expected: usize) -> Result<Vec<u8>, CompressionError> {
+        if payload.len() > expected {
+            return Err(CompressionError::Overflow { expected });
+        }
+        Ok(payload.to_vec())
+    }
+}
+
+//-----------------游程编码---------------------------------------------------------------------
+/// 游程编码：每一段连续相同的字节写成 (次数, 字节)，次数最多 255
+pub struct RunLength;
+
+impl CompressionStrategy for RunLength {
+    fn id(&self) -> u8 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "rle"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < data.len() {
+            let run = data[i..].iter().take(255).take_while(|b| **b == data[i]).count();
+            out.push(run as u8);
+            out.push(data[i]);
+            i += run;
+        }
+        Ok(out)
+    }
+
+    fn decompress(&self, payload: &[u8], expected: usize) -> Result<Vec<u8>, CompressionError> {
+        if !payload.len().is_multiple_of(2) {
+            return Err(CompressionError::Truncated);
+        }
+        let mut out = Vec::new();
+        for pair in payload.chunks(2) {
+            if pair[0] == 0 {
+                return Err(CompressionError::Corrupt("zero-length run".to_string()));
+            }
+            if out.len() + pair[0] as usize > expected {
+                return Err(CompressionError::Overflow { expected });
+            }
+            out.extend(std::iter::repeat_n(pair[1], pair[0] as usize));
+        }
+        Ok(out)
+    }
+
+    fn metadata(&self) -> StrategyInfo {
+        StrategyInfo::new("run-length encoding").param("max_run", "longest run per pair", 255)
+    }
+}
+
+//-----------------LZ77---------------------------------------------------------------------
+/// LZ77：在之前 window 字节中寻找最长的重复片段，写成 (距离, 长度)，找不到则写原字节
+/// 记号格式：0x00 字节 | 0x01 距离 u16 LE 长度-3 u8
+pub struct Lz77 {
+    pub window: usize,
+    /// 每个位置最多比较的候选数，越大压缩率越高、速度越慢
+    pub max_candidates: usize,
+}
+
+const LZ_MIN_MATCH: usize = 3;
+const LZ_MAX_MATCH: usize = 258;
+
+impl Default for Lz77 {
+    fn default() -> Self {
+        Lz77 { window: 4096, max_candidates: 32 }
+    }
+}
+
+impl CompressionStrategy for Lz77 {
+    fn id(&self) -> u8 {
+        2
+    }
+
+    fn name(&self) -> &'static str {
+        "lz77"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        let window = self.window.clamp(1, u16::MAX as usize);
+        // 以 3 字节前缀为键，记录出现过的位置
+        let mut chains: std::collections::HashMap<[u8; 3], Vec<usize>> = std::collections::HashMap::new();
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < data.len() {
+            let mut best = (0, 0);
+            if i + LZ_MIN_MATCH <= data.len() {
+                let key = [data[i], data[i + 1], data[i + 2]];
+                if let Some(positions) = chains.get(&key) {
+                    for &start in positions.iter().rev().take(self.max_candidates) {
+                        if i - start > window {
+                            break;
+                        }
+                        let len = data[start..]
+                            .iter()
+                            .zip(&data[i..])
+                            .take(LZ_MAX_MATCH)
+                            .take_while(|(a, b)| a == b)
+                            .count();
+                        if len > best.1 {
+                            best = (i - start, len);
+                        }
+                    }
+                }
+            }
+
+            let advance = if best.1 >= LZ_MIN_MATCH {
+                out.push(1);
+                out.extend_from_slice(&(best.0 as u16).to_le_bytes());
+                out.push((best.1 - LZ_MIN_MATCH) as u8);
+                best.1
+            } else {
+                out.push(0);
+                out.push(data[i]);
+                1
+            };
+            for p in i..i + advance {
+                if p + LZ_MIN_MATCH <= data.len() {
+                    chains.entry([data[p], data[p + 1], data[p + 2]]).or_default().push(p);
+                }
+            }
+            i += advance;
+        }
+        Ok(out)
+    }
+
+    fn decompress(&self, payload: &[u8], expected: usize) -> Result<Vec<u8>, CompressionError> {
+        let mut out: Vec<u8> = Vec::new();
+        let mut i = 0;
+        while i < payload.len() {
+            match payload[i] {
+                0 => {
+                    if out.len() == expected {
+                        return Err(CompressionError::Overflow { expected });
+                    }
+                    out.push(*payload.get(i + 1).ok_or(CompressionError::Truncated)?);
+                    i += 2;
+                }
+                1 => {
+                    let token = payload.get(i + 1..i + 4).ok_or(CompressionError::Truncated)?;
+                    let distance = u16::from_le_bytes([token[0], token[1]]) as usize;
+                    let len = token[2] as usize + LZ_MIN_MATCH;
+                    if distance == 0 || distance > out.len() {
+                        return Err(CompressionError::Corrupt(format!("match distance {} out of range", distance)));
+                    }
+                    if out.len() + len > expected {
+                        return Err(CompressionError::Overflow { expected });
+                    }
+                    // 重复片段可以与自身重叠，逐字节复制
+                    let start = out.len() - distance;
+                    for k in 0..len {
+                        out.push(out[start + k]);
+                    }
+                    i += 4;
+                }
+                tag => return Err(CompressionError::Corrupt(format!("unknown token tag {}", tag))),
+            }
+        }
+        Ok(out)
+    }
+
+    fn metadata(&self) -> StrategyInfo {
+        StrategyInfo::new("LZ77 sliding-window matches")
+            .param("window", "how far back to search", self.window)
+            .param("max_candidates", "positions compared per byte", self.max_candidates)
+    }
+}
+
+//-----------------范式哈夫曼---------------------------------------------------------------------
+/// 范式哈夫曼编码：只需保存每个符号的码长，码字按 (码长, 符号) 顺序依次分配
+/// 负载格式：符号数 u32 LE | 码长表 256 字节 | 按高位在前排列的码字
+pub struct Huffman;
+
+const MAX_CODE_LEN: u8 = 15;
+
+/// 按频率计算码长，超过 MAX_CODE_LEN 时把频率减半后重新计算
+fn code_lengths(freq: &[u64; 256]) -> [u8; 256] {
+    let mut freq = *freq;
+    loop {
+        let mut lengths = [0u8; 256];
+        // 节点：(权重, 叶子符号列表)；每次合并权重最小的两个，叶子的码长加一
+        let mut nodes: Vec<(u64, Vec<u8>)> =
+            (0..256).filter(|&s| freq[s] > 0).map(|s| (freq[s], vec![s as u8])).collect();
+        if nodes.len() == 1 {
+            lengths[nodes[0].1[0] as usize] = 1;
+            return lengths;
+        }
+        while nodes.len() > 1 {
+            nodes.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+            let (w1, s1) = nodes.pop().unwrap();
+            let (w2, s2) = nodes.pop().unwrap();
+            for &s in s1.iter().chain(&s2) {
+                lengths[s as usize] += 1;
+            }
+            nodes.push((w1 + w2, s1.into_iter().chain(s2).collect()));
+        }
+        if lengths.iter().all(|&l| l <= MAX_CODE_LEN) {
+            return lengths;
+        }
+        for f in freq.iter_mut().filter(|f| **f > 0) {
+            *f = (*f / 2).max(1);
+        }
+    }
+}
+
+/// 按码长分配范式码字
+fn canonical_codes(lengths: &[u8; 256]) -> [u16; 256] {
+    let mut symbols: Vec<usize> = (0..256).filter(|&s| lengths[s] > 0).collect();
+    symbols.sort_by_key(|&s| (lengths[s], s));
+    let mut codes = [0u16; 256];
+    let (mut code, mut prev_len) = (0u16, 0u8);
+    for s in symbols {
+        code <<= lengths[s] - prev_len;
+        codes[s] = code;
+        code += 1;
+        prev_len = lengths[s];
+    }
+    codes
+}
+
+impl CompressionStrategy for Huffman {
+    fn id(&self) -> u8 {
+        3
+    }
+
+    fn name(&self) -> &'static str {
+        "huffman"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        let mut out = length_u32(data.len())?.to_le_bytes().to_vec();
+        if data.is_empty() {
+            return Ok(out);
+        }
+        let mut freq = [0u64; 256];
+        for &b in data {
+            freq[b as usize] += 1;
+        }
+        let lengths = code_lengths(&freq);
+        let codes = canonical_codes(&lengths);
+        out.extend_from_slice(&lengths);
+
+        let (mut acc, mut bits) = (0u32, 0u32);
+        for &b in data {
+            let len = lengths[b as usize] as u32;
+            acc = (acc << len) | codes[b as usize] as u32;
+            bits += len;
+            while bits >= 8 {
+                bits -= 8;
+                out.push((acc >> bits) as u8);
+            }
+            acc &= (1 << bits) - 1;
+        }
+        if bits > 0 {
+            out.push((acc << (8 - bits)) as u8);
+        }
+        Ok(out)
+    }
+
+    fn decompress(&self, payload: &[u8], expected: usize) -> Result<Vec<u8>, CompressionError> {
+        let count = read_u32(payload, 0)? as usize;
+        if count > expected {
+            return Err(CompressionError::Overflow { expected });
+        }
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let table = payload.get(4..260).ok_or(CompressionError::Truncated)?;
+        let mut lengths = [0u8; 256];
+        lengths.copy_from_slice(table);
+        if lengths.iter().any(|&l| l > MAX_CODE_LEN) {
+            return Err(CompressionError::Corrupt("code length too long".to_string()));
+        }
+
+        // 每个码长的第一个码字、符号数和在排序符号表中的起始位置
+        let mut sorted: Vec<u8> = (0..=255u8).filter(|&s| lengths[s as usize] > 0).collect();
+        sorted.sort_by_key(|&s| (lengths[s as usize], s));
+        let mut first = [0u32; 16];
+        let mut counts = [0u32; 16];
+        let mut offset = [0usize; 16];
+        for &s in &sorted {
+            counts[lengths[s as usize] as usize] += 1;
+        }
+        let (mut code, mut index) = (0u32, 0usize);
+        for len in 1..16 {
+            code <<= 1;
+            first[len] = code;
+            offset[len] = index;
+            code += counts[len];
+            index += counts[len] as usize;
+        }
+
+        // count 来自负载，不可信；每个符号至少占 1 位，按剩余的位数限制预分配
+        let mut out = Vec::with_capacity(count.min((payload.len() - 260) * 8));
+        let (mut code, mut len) = (0u32, 0usize);
+        'bytes: for &byte in &payload[260..] {
+            for bit in (0..8).rev() {
+                code = (code << 1) | ((byte >> bit) & 1) as u32;
+                len += 1;
+                if len > MAX_CODE_LEN as usize {
+                    return Err(CompressionError::Corrupt("invalid code".to_string()));
+                }
+                if code.wrapping_sub(first[len]) < counts[len] {
+                    out.push(sorted[offset[len] + (code - first[len]) as usize]);
+                    if out.len() == count {
+                        break 'bytes;
+                    }
+                    code = 0;
+                    len = 0;
+                }
+            }
+        }
+        if out.len() < count {
+            return Err(CompressionError::Truncated);
+        }
+        Ok(out)
+    }
+
+    fn metadata(&self) -> StrategyInfo {
+        StrategyInfo::new("canonical Huffman coding").param("max_code_len", "longest code in bits", MAX_CODE_LEN)
+    }
+}
+
+//-----------------环境---------------------------------------------------------------------
+/// 全部内置的压缩策略
+pub fn codecs() -> Vec<Box<dyn CompressionStrategy>> {
+    vec![Box::new(Stored), Box::new(RunLength), Box::new(Lz77::default()), Box::new(Huffman)]
+}
+
+/// 按名称选择压缩策略
+pub fn codec_by_name(name: &str) -> Result<Box<dyn CompressionStrategy>, UnknownStrategy> {
+    let all = codecs();
+    let available = all.iter().map(|c| c.name().to_string()).collect();
+    all.into_iter()
+        .find(|c| c.name() == name)
+        .ok_or(UnknownStrategy { requested: name.to_string(), available })
+}
+
+/// 压缩环境：压缩使用当前策略，解压根据帧头自动选择策略
+pub struct CompressionContext {
+    strategy: Box<dyn CompressionStrategy>,
+}
+
+impl CompressionContext {
+    pub fn new(strategy: Box<dyn CompressionStrategy>) -> Self {
+        CompressionContext { strategy }
+    }
+
+    pub fn set_strategy(&mut self, strategy: Box<dyn CompressionStrategy>) {
+        self.strategy = strategy;
+    }
+
+    pub fn strategy(&self) -> &dyn CompressionStrategy {
+        self.strategy.as_ref()
+    }
+
+    /// 压缩并加上帧头；超过 u32 能表示的长度时返回 TooLarge
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        let mut frame = MAGIC.to_vec();
+        frame.push(self.strategy.id());
+        frame.extend_from_slice(&length_u32(data.len())?.to_le_bytes());
+        frame.extend_from_slice(&fnv1a(data).to_le_bytes());
+        frame.extend(self.strategy.compress(data)?);
+        Ok(frame)
+    }
+
+    /// 解压任意内置策略生成的帧
+    pub fn decompress(&self, frame: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        if frame.len() < HEADER_LEN {
+            return Err(if frame.starts_with(MAGIC) { CompressionError::Truncated } else { CompressionError::BadMagic });
+        }
+        if &frame[..4] != MAGIC {
+            return Err(CompressionError::BadMagic);
+        }
+        let id = frame[4];
+        let expected = read_u32(frame, 5)? as usize;
+        let checksum = read_u32(frame, 9)?;
+        let codec = codecs().into_iter().find(|c| c.id() == id).ok_or(CompressionError::UnknownCodec(id))?;
+
+        let data = codec.decompress(&frame[HEADER_LEN..], expected)?;
+        if data.len() != expected {
+            return Err(CompressionError::LengthMismatch { expected, actual: data.len() });
+        }
+        if fnv1a(&data) != checksum {
+            return Err(CompressionError::ChecksumMismatch);
+        }
+        Ok(data)
+    }
+}
+
+//-----------------对比报告---------------------------------------------------------------------
+/// 一次压缩的测量结果
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub corpus: String,
+    pub codec: String,
+    pub original: usize,
+    pub compressed: usize,
+    pub compress_mbps: f64,
+    pub decompress_mbps: f64,
+}
+
+impl Measurement {
+    /// 压缩后与原始大小之比（含帧头），越小越好
+    pub fn ratio(&self) -> f64 {
+        if self.original == 0 {
+            1.0
+        } else {
+            self.compressed as f64 / self.original as f64
+        }
+    }
+}
+
+/// 用每个策略压缩每个样本，记录压缩率和速度；round trip 失败时 panic
+pub fn compare(corpora: &[(&str, Vec<u8>)]) -> Vec<Measurement> {
+    let mut results = Vec::new();
+    for (corpus, data) in corpora {
+        for codec in codecs() {
+            let name = codec.name().to_string();
+            let context = CompressionContext::new(codec);
+            let start = Instant::now();
+            let frame = context.compress(data).expect("corpus too large for a frame");
+            let compress_time = start.elapsed().as_secs_f64();
+            let start = Instant::now();
+            let restored = context.decompress(&frame).expect("round trip failed");
+            let decompress_time = start.elapsed().as_secs_f64();
+            assert_eq!(&restored, data, "{} changed the data of {}", name, corpus);
+
+            let mbps = |secs: f64| data.len() as f64 / 1e6 / secs.max(1e-9);
+            results.push(Measurement {
+                corpus: corpus.to_string(),
+                codec: name,
+                original: data.len(),
+                compressed: frame.len(),
+                compress_mbps: mbps(compress_time),
+                decompress_mbps: mbps(decompress_time),
+            });
+        }
+    }
+    results
+}
+
+/// 格式化对比结果
+pub fn report(results: &[Measurement]) -> String {
+    let mut out = format!(
+        "{:<10} {:<8} {:>9} {:>10} {:>7} {:>10} {:>10}\n",
+        "corpus", "codec", "original", "compressed", "ratio", "comp MB/s", "dec MB/s"
+    );
+    for m in results {
+        out.push_str(&format!(
+            "{:<10} {:<8} {:>9} {:>10} {:>7.3} {:>10.1} {:>10.1}\n",
+            m.corpus, m.codec, m.original, m.compressed, m.ratio(), m.compress_mbps, m.decompress_mbps
+        ));
+    }
+    out
+}
+
+//--------------------------------------------------------------------------------------------------
+pub fn main() {
+    // 解压自动选择策略，并能发现损坏
+    let text = b"to be or not to be, that is the question".repeat(20);
+    let mut context = CompressionContext::new(codec_by_name("lz77").unwrap());
+    let frame = context.compress(&text).unwrap();
+    context.set_strategy(Box::new(RunLength));
+    println!("lz77 frame decoded by the rle context: {}", context.decompress(&frame).unwrap() == text);
+    let mut damaged = frame.clone();
+    let last = damaged.len() - 1;
+    damaged[last] ^= 0xff;
+    println!("damaged frame: {}", context.decompress(&damaged).unwrap_err());
+    println!("{}", codec_by_name("zip").err().unwrap());
+
+    let corpora = vec![
+        ("text", include_str!("strategy.rs").as_bytes().to_vec()),
+        ("runs", (0..8000u32).map(|i| (i / 400) as u8).collect()),
+        ("random", {
+            let mut rng = SeededRng::new(42);
+            (0..8000).map(|_| rng.next_u64() as u8).collect()
+        }),
+        ("empty", Vec::new()),
+    ];
+    print!("{}", report(&compare(&corpora)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn below(rng: &mut SeededRng, n: u64) -> u64 {
+        rng.next_u64() % n
+    }
+
+    /// 随机生成不同形态的数据：均匀随机、长游程、小字母表、重复片段
+    fn random_input(rng: &mut SeededRng) -> Vec<u8> {
+        let len = below(rng, 2000) as usize;
+        let mut data = Vec::with_capacity(len);
+        match below(rng, 4) {
+            0 => data.extend((0..len).map(|_| rng.next_u64() as u8)),
+            1 => {
+                while data.len() < len {
+                    let byte = rng.next_u64() as u8;
+                    let run = 1 + below(rng, 600) as usize;
+                    data.extend(std::iter::repeat_n(byte, run));
+                }
+            }
+            2 => data.extend((0..len).map(|_| b'a' + below(rng, 3) as u8)),
+            _ => {
+                let pattern: Vec<u8> = (0..1 + below(rng, 20)).map(|_| rng.next_u64() as u8).collect();
+                data.extend(pattern.iter().cycle().take(len));
+            }
+        }
+        data
+    }
+
+    /// 每个策略单独 round trip，再经过帧 round trip
+    fn assert_round_trip(data: &[u8]) {
+        for codec in codecs() {
+            let name = codec.name();
+            let payload = codec.compress(data).unwrap();
+            assert_eq!(codec.decompress(&payload, data.len()).unwrap(), data, "{} payload round trip, {} bytes", name, data.len());
+            let context = CompressionContext::new(codec);
+            let frame = context.compress(data).unwrap();
+            assert_eq!(context.decompress(&frame).unwrap(), data, "{} frame round trip, {} bytes", name, data.len());
+        }
+    }
+
+    #[test]
+    fn round_trip_generated_inputs() {
+        let mut rng = SeededRng::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..300 {
+            assert_round_trip(&random_input(&mut rng));
+        }
+    }
+
+    #[test]
+    fn round_trip_empty_input() {
+        assert_round_trip(&[]);
+    }
+
+    #[test]
+    fn round_trip_single_symbol() {
+        // 只有一种字节时哈夫曼码长为 1，游程跨过 255 的边界
+        for len in [1, 2, 3, 254, 255, 256, 511, 5000] {
+            for byte in [0u8, b'a', 0xff] {
+                assert_round_trip(&vec![byte; len]);
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_incompressible_input() {
+        let mut rng = SeededRng::new(7);
+        for len in [1, 17, 1000, 20000] {
+            let data: Vec<u8> = (0..len).map(|_| rng.next_u64() as u8).collect();
+            assert_round_trip(&data);
+        }
+        // 每个字节值都出现且频率相同
+        let all: Vec<u8> = (0..=255u8).cycle().take(4096).collect();
+        assert_round_trip(&all);
+    }
+
+    #[test]
+    fn compressible_input_shrinks() {
+        let text = b"to be or not to be, that is the question ".repeat(50);
+        let stored = CompressionContext::new(Box::new(Stored)).compress(&text).unwrap().len();
+        // LZ77 吃掉重复片段；哈夫曼只按字节频率编码，收益小一些
+        let lz77 = CompressionContext::new(codec_by_name("lz77").unwrap()).compress(&text).unwrap().len();
+        assert!(lz77 < stored / 4, "lz77 compressed to {} of {}", lz77, stored);
+        let huffman = CompressionContext::new(Box::new(Huffman)).compress(&text).unwrap().len();
+        assert!(huffman < stored * 3 / 4, "huffman compressed to {} of {}", huffman, stored);
+        let runs = vec![7u8; 10000];
+        let frame = CompressionContext::new(Box::new(RunLength)).compress(&runs).unwrap();
+        assert!(frame.len() < 100);
+    }
+
+    #[test]
+    fn decompress_picks_codec_from_header() {
+        let text = b"to be or not to be, that is the question".repeat(20);
+        let mut context = CompressionContext::new(codec_by_name("lz77").unwrap());
+        let frame = context.compress(&text).unwrap();
+        context.set_strategy(Box::new(RunLength));
+        assert_eq!(context.decompress(&frame).unwrap(), text);
+    }
+
+    #[test]
+    fn damaged_frames_are_rejected() {
+        let data = b"abracadabra abracadabra".to_vec();
+        let context = CompressionContext::new(Box::new(Stored));
+        let frame = context.compress(&data).unwrap();
+
+        let mut flipped = frame.clone();
+        let last = flipped.len() - 1;
+        flipped[last] ^= 0xff;
+        assert_eq!(context.decompress(&flipped), Err(CompressionError::ChecksumMismatch));
+        assert_eq!(
+            context.decompress(&frame[..frame.len() - 1]),
+            Err(CompressionError::LengthMismatch { expected: data.len(), actual: data.len() - 1 })
+        );
+        assert_eq!(context.decompress(&frame[..6]), Err(CompressionError::Truncated));
+        assert_eq!(context.decompress(b"nope, not a frame"), Err(CompressionError::BadMagic));
+
+        let mut unknown = frame.clone();
+        unknown[4] = 99;
+        assert_eq!(context.decompress(&unknown), Err(CompressionError::UnknownCodec(99)));
+        assert_eq!(RunLength.decompress(&[0, b'a'], 1), Err(CompressionError::Corrupt("zero-length run".to_string())));
+    }
+
+    #[test]
+    fn unknown_codec_name_lists_available() {
+        let err = codec_by_name("zip").err().unwrap();
+        assert_eq!(err.available, ["stored", "rle", "lz77", "huffman"]);
+    }
+
+    /// 帧头声明 expected 字节，codec 的负载解码后会更长
+    fn frame_with(codec: &dyn CompressionStrategy, expected: u32, payload: &[u8]) -> Vec<u8> {
+        let mut frame = MAGIC.to_vec();
+        frame.push(codec.id());
+        frame.extend_from_slice(&expected.to_le_bytes());
+        frame.extend_from_slice(&0u32.to_le_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn decoders_stop_at_the_expected_length() {
+        let context = CompressionContext::new(Box::new(Stored));
+        let overflow = Err(CompressionError::Overflow { expected: 3 });
+        // 一对 (255, 'a') 就超过 3 字节
+        assert_eq!(context.decompress(&frame_with(&RunLength, 3, &[255, b'a'])), overflow);
+        // 字面量 'a' 后面跟一个重叠的 258 字节匹配
+        assert_eq!(context.decompress(&frame_with(&Lz77::default(), 3, &[0, b'a', 1, 1, 0, 255])), overflow);
+        assert_eq!(context.decompress(&frame_with(&Stored, 3, b"abcd")), overflow);
+
+        // 哈夫曼负载自带的符号数大于帧头的长度
+        let payload = Huffman.compress(b"aaaa").unwrap();
+        assert_eq!(context.decompress(&frame_with(&Huffman, 3, &payload)), overflow);
+        assert_eq!(Huffman.decompress(&payload, 4).unwrap(), b"aaaa");
+    }
+
+    #[test]
+    fn huffman_count_does_not_drive_allocation() {
+        // 声称有 u32::MAX 个符号，但只有一个字节的码字
+        let mut payload = u32::MAX.to_le_bytes().to_vec();
+        let mut lengths = [0u8; 256];
+        lengths[b'a' as usize] = 1;
+        payload.extend_from_slice(&lengths);
+        payload.push(0);
+        assert_eq!(Huffman.decompress(&payload, usize::MAX), Err(CompressionError::Truncated));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn lengths_beyond_u32_are_rejected() {
+        let len = u32::MAX as usize + 1;
+        assert_eq!(length_u32(len), Err(CompressionError::TooLarge(len)));
+        assert_eq!(length_u32(u32::MAX as usize), Ok(u32::MAX));
+    }
+}
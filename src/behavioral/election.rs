@@ -0,0 +1,669 @@
+//! 多人投票与可插拔的计票策略
+//! behavioral::state 中的 VotingMachine 只代表一个投票人，也不记录投给了谁。
+//! 这里在它的基础上扩展成一次完整的选举：
+//!     候选人、用校验过的 VoterId 登记投票人、每人只能投一张票；
+//!     选票可以是单选（plurality）、认可投票（approval）或排序投票（ranked）；
+//!     计票策略可以替换：简单多数（first-past-the-post）、即时决选（instant-runoff）、波达计数（Borda），
+//!     每种策略都输出逐轮的计票报告。
+//! 每个投票人和整个投票活动都沿用 NotVoted/Voted/Locked 的生命周期（next_status 与 Poll）。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::behavioral::clock::ManualClock;
+use crate::behavioral::state::{next_status, Poll, VoteOperation, VoteStatus};
+
+//-----------------投票人与候选人---------------------------------------------------------------------
+/// 投票人编号：以字母开头，3 到 32 个字符，只包含字母、数字和 '-'，统一为大写
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VoterId(String);
+
+impl VoterId {
+    /// 校验并创建投票人编号
+    pub fn parse(id: &str) -> Result<Self, ElectionError> {
+        let id = id.trim();
+        let valid = (3..=32).contains(&id.len())
+            && id.starts_with(|c: char| c.is_ascii_alphabetic())
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if valid {
+            Ok(VoterId(id.to_ascii_uppercase()))
+        } else {
+            Err(ElectionError::InvalidVoterId(id.to_string()))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for VoterId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 候选人编号，即候选人在列表中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CandidateId(usize);
+
+//-----------------选票---------------------------------------------------------------------
+/// 选票类型，一次选举只接受一种
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BallotKind {
+    Plurality,
+    Approval,
+    Ranked,
+}
+
+/// 选票，不记录投票人，保证匿名
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ballot {
+    /// 只选一位候选人
+    Plurality(CandidateId),
+    /// 认可任意多位候选人
+    Approval(BTreeSet<CandidateId>),
+    /// 按偏好从高到低排序，可以只排一部分
+    Ranked(Vec<CandidateId>),
+}
+
+impl Ballot {
+    pub fn kind(&self) -> BallotKind {
+        match self {
+            Ballot::Plurality(_) => BallotKind::Plurality,
+            Ballot::Approval(_) => BallotKind::Approval,
+            Ballot::Ranked(_) => BallotKind::Ranked,
+        }
+    }
+
+    /// 按偏好顺序列出候选人；认可投票没有先后，按编号排列
+    fn preferences(&self) -> Vec<CandidateId> {
+        match self {
+            Ballot::Plurality(candidate) => vec![*candidate],
+            Ballot::Approval(approved) => approved.iter().copied().collect(),
+            Ballot::Ranked(ranking) => ranking.clone(),
+        }
+    }
+}
+
+//-----------------错误---------------------------------------------------------------------
+/// 选举中的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElectionError {
+    InvalidVoterId(String),
+    /// 候选人名称重复
+    DuplicateCandidate(String),
+    DuplicateVoter(VoterId),
+    UnknownVoter(VoterId),
+    AlreadyVoted(VoterId),
+    /// 投票已关闭
+    PollClosed,
+    /// 投票尚未关闭，不能计票
+    PollOpen,
+    /// 选票类型与选举不符
+    WrongBallotKind { expected: BallotKind, actual: BallotKind },
+    InvalidBallot(String),
+    /// 计票策略不支持这种选票
+    UnsupportedTally { strategy: String, kind: BallotKind },
+    /// 管理操作被拒绝
+    Admin(String),
+}
+
+impl fmt::Display for ElectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElectionError::InvalidVoterId(id) => write!(f, "invalid voter id {:?}", id),
+            ElectionError::DuplicateCandidate(name) => write!(f, "candidate {:?} is listed more than once", name),
+            ElectionError::DuplicateVoter(id) => write!(f, "voter {} is already registered", id),
+            ElectionError::UnknownVoter(id) => write!(f, "voter {} is not registered", id),
+            ElectionError::AlreadyVoted(id) => write!(f, "voter {} has already voted", id),
+            ElectionError::PollClosed => write!(f, "the poll is closed"),
+            ElectionError::PollOpen => write!(f, "the poll is still open"),
+            ElectionError::WrongBallotKind { expected, actual } => {
+                write!(f, "expected a {:?} ballot, got {:?}", expected, actual)
+            }
+            ElectionError::InvalidBallot(reason) => write!(f, "invalid ballot: {}", reason),
+            ElectionError::UnsupportedTally { strategy, kind } => {
+                write!(f, "{} cannot count {:?} ballots", strategy, kind)
+            }
+            ElectionError::Admin(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ElectionError {}
+
+//-----------------计票报告---------------------------------------------------------------------
+/// 一轮计票
+#[derive(Debug, Clone, PartialEq)]
+pub struct Round {
+    /// 各候选人的得票（或得分），按得票从高到低
+    pub counts: Vec<(String, u64)>,
+    /// 本轮淘汰的候选人
+    pub eliminated: Vec<String>,
+    /// 本轮没有可计入候选人的选票数
+    pub exhausted: u64,
+}
+
+/// 计票报告
+#[derive(Debug, Clone, PartialEq)]
+pub struct TallyReport {
+    pub method: String,
+    pub rounds: Vec<Round>,
+    /// 获胜者，平局时有多位
+    pub winners: Vec<String>,
+}
+
+impl fmt::Display for TallyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.method)?;
+        for (i, round) in self.rounds.iter().enumerate() {
+            let counts: Vec<String> = round.counts.iter().map(|(name, count)| format!("{} {}", name, count)).collect();
+            write!(f, "  round {}: {}", i + 1, counts.join(", "))?;
+            if round.exhausted > 0 {
+                write!(f, " (exhausted {})", round.exhausted)?;
+            }
+            if !round.eliminated.is_empty() {
+                write!(f, "; eliminated {}", round.eliminated.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "  winner(s): {}", self.winners.join(", "))
+    }
+}
+
+/// 把 (候选人, 票数) 排好序并换成名字，得票相同时按编号
+fn named_counts(candidates: &[String], counts: &BTreeMap<CandidateId, u64>) -> Vec<(String, u64)> {
+    let mut sorted: Vec<(CandidateId, u64)> = counts.iter().map(|(id, count)| (*id, *count)).collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    sorted.into_iter().map(|(id, count)| (candidates[id.0].clone(), count)).collect()
+}
+
+/// 得票最高的候选人（可能并列）
+fn leaders(candidates: &[String], counts: &BTreeMap<CandidateId, u64>) -> Vec<String> {
+    let max = counts.values().copied().max().unwrap_or(0);
+    counts.iter().filter(|(_, count)| **count == max).map(|(id, _)| candidates[id.0].clone()).collect()
+}
+
+//-----------------计票策略---------------------------------------------------------------------
+/// 计票策略
+pub trait TallyStrategy {
+    fn name(&self) -> &str;
+    /// 是否支持这种选票
+    fn accepts(&self, kind: BallotKind) -> bool;
+    /// 计票，ballots 已经按选举规则校验过
+    fn tally(&self, candidates: &[String], ballots: &[Ballot]) -> TallyReport;
+}
+
+/// 简单多数：每张选票上的每位候选人各得一票（认可投票即认可计票），得票最多者胜
+pub struct FirstPastThePost;
+
+impl TallyStrategy for FirstPastThePost {
+    fn name(&self) -> &str {
+        "first-past-the-post"
+    }
+
+    fn accepts(&self, _: BallotKind) -> bool {
+        true
+    }
+
+    fn tally(&self, candidates: &[String], ballots: &[Ballot]) -> TallyReport {
+        let mut counts: BTreeMap<CandidateId, u64> = (0..candidates.len()).map(|i| (CandidateId(i), 0)).collect();
+        for ballot in ballots {
+            let chosen = match ballot {
+                // 排序投票只看第一选择
+                Ballot::Ranked(ranking) => ranking.first().copied().into_iter().collect(),
+                other => other.preferences(),
+            };
+            for candidate in chosen {
+                *counts.entry(candidate).or_default() += 1;
+            }
+        }
+        TallyReport {
+            method: self.name().to_string(),
+            rounds: vec![Round { counts: named_counts(candidates, &counts), eliminated: Vec::new(), exhausted: 0 }],
+            winners: leaders(candidates, &counts),
+        }
+    }
+}
+
+/// 即时决选：每轮统计每张选票上排名最高且仍在竞争的候选人，过半数即胜出，
+/// 否则淘汰得票最少的候选人（并列最少时一起淘汰），继续下一轮
+pub struct InstantRunoff;
+
+impl TallyStrategy for InstantRunoff {
+    fn name(&self) -> &str {
+        "instant-runoff"
+    }
+
+    fn accepts(&self, kind: BallotKind) -> bool {
+        kind != BallotKind::Approval
+    }
+
+    fn tally(&self, candidates: &[String], ballots: &[Ballot]) -> TallyReport {
+        let mut continuing: BTreeSet<CandidateId> = (0..candidates.len()).map(CandidateId).collect();
+        let mut rounds = Vec::new();
+        loop {
+            let mut counts: BTreeMap<CandidateId, u64> = continuing.iter().map(|id| (*id, 0)).collect();
+            let mut exhausted = 0;
+            for ballot in ballots {
+                match ballot.preferences().into_iter().find(|id| continuing.contains(id)) {
+                    Some(id) => *counts.entry(id).or_default() += 1,
+                    None => exhausted += 1,
+                }
+            }
+            let active: u64 = counts.values().sum();
+            let max = counts.values().copied().max().unwrap_or(0);
+            let min = counts.values().copied().min().unwrap_or(0);
+
+            // 有人过半，或者剩下的人票数全部相同（无法再淘汰）时结束
+            if max * 2 > active || min == max {
+                let winners = leaders(candidates, &counts);
+                rounds.push(Round { counts: named_counts(candidates, &counts), eliminated: Vec::new(), exhausted });
+                return TallyReport { method: self.name().to_string(), rounds, winners };
+            }
+
+            let eliminated: Vec<CandidateId> =
+                counts.iter().filter(|(_, count)| **count == min).map(|(id, _)| *id).collect();
+            for id in &eliminated {
+                continuing.remove(id);
+            }
+            rounds.push(Round {
+                counts: named_counts(candidates, &counts),
+                eliminated: eliminated.iter().map(|id| candidates[id.0].clone()).collect(),
+                exhausted,
+            });
+        }
+    }
+}
+
+/// 波达计数：n 位候选人时，排第 1 位得 n-1 分，第 2 位得 n-2 分，依此类推；未排序的候选人不得分
+pub struct BordaCount;
+
+impl TallyStrategy for BordaCount {
+    fn name(&self) -> &str {
+        "Borda count"
+    }
+
+    fn accepts(&self, kind: BallotKind) -> bool {
+        kind != BallotKind::Approval
+    }
+
+    fn tally(&self, candidates: &[String], ballots: &[Ballot]) -> TallyReport {
+        let n = candidates.len() as u64;
+        let mut points: BTreeMap<CandidateId, u64> = (0..candidates.len()).map(|i| (CandidateId(i), 0)).collect();
+        for ballot in ballots {
+            for (rank, id) in ballot.preferences().into_iter().enumerate() {
+                *points.entry(id).or_default() += n - 1 - rank as u64;
+            }
+        }
+        TallyReport {
+            method: self.name().to_string(),
+            rounds: vec![Round { counts: named_counts(candidates, &points), eliminated: Vec::new(), exhausted: 0 }],
+            winners: leaders(candidates, &points),
+        }
+    }
+}
+
+//-----------------选举---------------------------------------------------------------------
+/// 一次选举
+pub struct Election {
+    title: String,
+    kind: BallotKind,
+    candidates: Vec<String>,
+    /// 每个投票人的生命周期状态
+    voters: BTreeMap<VoterId, VoteStatus>,
+    /// 已经投过票的投票人，重新开放投票时用来还原状态
+    voted: BTreeSet<VoterId>,
+    /// 匿名的选票箱
+    ballots: Vec<Ballot>,
+    /// 整个投票活动的生命周期：NotVoted 尚无人投票，Voted 已有选票，Locked 已关闭
+    status: VoteStatus,
+    poll: Poll,
+}
+
+impl Election {
+    /// 创建选举，poll 提供截止时间和管理员口令；候选人名称不能重复
+    pub fn new(title: &str, kind: BallotKind, candidates: &[&str], poll: Poll) -> Result<Self, ElectionError> {
+        for (i, name) in candidates.iter().enumerate() {
+            if candidates[..i].contains(name) {
+                return Err(ElectionError::DuplicateCandidate(name.to_string()));
+            }
+        }
+        Ok(Election {
+            title: title.to_string(),
+            kind,
+            candidates: candidates.iter().map(|name| name.to_string()).collect(),
+            voters: BTreeMap::new(),
+            voted: BTreeSet::new(),
+            ballots: Vec::new(),
+            status: VoteStatus::NotVoted,
+            poll,
+        })
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// 按名称查找候选人
+    pub fn candidate(&self, name: &str) -> Option<CandidateId> {
+        self.candidates.iter().position(|c| c == name).map(CandidateId)
+    }
+
+    /// 投票活动的状态
+    pub fn status(&self) -> VoteStatus {
+        self.status
+    }
+
+    /// 投票人的状态
+    pub fn voter_status(&self, voter: &VoterId) -> Option<VoteStatus> {
+        self.voters.get(voter).copied()
+    }
+
+    /// 已投出的选票数
+    pub fn turnout(&self) -> usize {
+        self.ballots.len()
+    }
+
+    /// 截止时间到期则关闭投票；返回是否已关闭
+    pub fn tick(&mut self) -> bool {
+        if self.poll.expire(self.status.state().as_ref()).is_some() {
+            self.lock_all();
+        }
+        self.status == VoteStatus::Locked
+    }
+
+    /// 关闭投票活动，同时锁定每个投票人
+    fn lock_all(&mut self) {
+        self.status = VoteStatus::Locked;
+        for status in self.voters.values_mut() {
+            *status = next_status(*status, VoteOperation::ClosePoll, false).unwrap_or(VoteStatus::Locked);
+        }
+    }
+
+    /// 登记投票人
+    pub fn register(&mut self, id: &str) -> Result<VoterId, ElectionError> {
+        let id = VoterId::parse(id)?;
+        if self.tick() {
+            return Err(ElectionError::PollClosed);
+        }
+        if self.voters.contains_key(&id) {
+            return Err(ElectionError::DuplicateVoter(id));
+        }
+        self.voters.insert(id.clone(), VoteStatus::NotVoted);
+        Ok(id)
+    }
+
+    /// 检查选票内容
+    fn validate(&self, ballot: &Ballot) -> Result<(), ElectionError> {
+        if ballot.kind() != self.kind {
+            return Err(ElectionError::WrongBallotKind { expected: self.kind, actual: ballot.kind() });
+        }
+        let preferences = ballot.preferences();
+        if preferences.is_empty() {
+            return Err(ElectionError::InvalidBallot("no candidate selected".to_string()));
+        }
+        if let Some(id) = preferences.iter().find(|id| id.0 >= self.candidates.len()) {
+            return Err(ElectionError::InvalidBallot(format!("unknown candidate #{}", id.0)));
+        }
+        let unique: BTreeSet<_> = preferences.iter().collect();
+        if unique.len() != preferences.len() {
+            return Err(ElectionError::InvalidBallot("candidate ranked more than once".to_string()));
+        }
+        Ok(())
+    }
+
+    /// 投票，每位投票人只能投一次
+    pub fn cast(&mut self, voter: &VoterId, ballot: Ballot) -> Result<(), ElectionError> {
+        if self.tick() {
+            return Err(ElectionError::PollClosed);
+        }
+        let status = *self.voters.get(voter).ok_or_else(|| ElectionError::UnknownVoter(voter.clone()))?;
+        self.validate(&ballot)?;
+        let next = next_status(status, VoteOperation::Vote, status == VoteStatus::Voted)
+            .map_err(|_| ElectionError::AlreadyVoted(voter.clone()))?;
+
+        self.voters.insert(voter.clone(), next);
+        self.voted.insert(voter.clone());
+        self.ballots.push(ballot);
+        if self.status == VoteStatus::NotVoted {
+            self.status = self.poll.vote(self.status.state().as_ref()).map_or(VoteStatus::Voted, |next| next.kind());
+        }
+        Ok(())
+    }
+
+    /// 管理员关闭投票
+    pub fn close(&mut self) -> Result<(), ElectionError> {
+        if self.tick() {
+            return Err(ElectionError::PollClosed);
+        }
+        self.poll.close(self.status.state().as_ref()).map_err(ElectionError::Admin)?;
+        self.lock_all();
+        Ok(())
+    }
+
+    /// 管理员凭口令重新开放投票；已经投过票的人仍然不能再投
+    pub fn reopen(&mut self, token: &str, deadline: Option<Duration>) -> Result<(), ElectionError> {
+        self.tick();
+        self.status = self.poll.reopen(self.status.state().as_ref(), token, deadline).map_err(ElectionError::Admin)?.kind();
+        // 投票人按是否已经投票回到 Voted 或 NotVoted
+        for (voter, status) in self.voters.iter_mut() {
+            *status = next_status(*status, VoteOperation::ReopenPoll, self.voted.contains(voter)).unwrap_or(*status);
+        }
+        Ok(())
+    }
+
+    /// 用指定的策略计票，只能在投票关闭后进行
+    pub fn tally(&mut self, strategy: &dyn TallyStrategy) -> Result<TallyReport, ElectionError> {
+        if !self.tick() {
+            return Err(ElectionError::PollOpen);
+        }
+        if !strategy.accepts(self.kind) {
+            return Err(ElectionError::UnsupportedTally { strategy: strategy.name().to_string(), kind: self.kind });
+        }
+        Ok(strategy.tally(&self.candidates, &self.ballots))
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+pub fn main() {
+    let clock = Arc::new(ManualClock::new(Duration::ZERO));
+    let poll = Poll::new(clock.clone()).with_admin_token("secret");
+    let mut election = Election::new("club president", BallotKind::Ranked, &["Alice", "Bob", "Carol"], poll).unwrap();
+
+    let (a, b, c) = (
+        election.candidate("Alice").unwrap(),
+        election.candidate("Bob").unwrap(),
+        election.candidate("Carol").unwrap(),
+    );
+    for (i, ranking) in [vec![a, b, c], vec![a, c, b], vec![b, c, a], vec![b, c, a], vec![c, b, a]].into_iter().enumerate() {
+        let voter = election.register(&format!("v-{:03}", i)).unwrap();
+        election.cast(&voter, Ballot::Ranked(ranking)).unwrap();
+    }
+
+    // 同一个人不能投两次，编号格式不对不能登记，投票关闭前不能计票
+    let voter = VoterId::parse("V-000").unwrap();
+    println!("{}", election.cast(&voter, Ballot::Ranked(vec![c])).unwrap_err());
+    println!("{}", election.register("0bad id").unwrap_err());
+    println!("{}", election.tally(&FirstPastThePost).unwrap_err());
+
+    election.close().unwrap();
+    for strategy in [&FirstPastThePost as &dyn TallyStrategy, &InstantRunoff, &BordaCount] {
+        print!("{}", election.tally(strategy).unwrap());
+    }
+
+    // 重新开放后，已投票的人仍是 Voted，新登记的人可以投票；截止时间到期自动关闭
+    election.reopen("secret", Some(Duration::from_secs(60))).unwrap();
+    let late = election.register("v-late").unwrap();
+    election.cast(&late, Ballot::Ranked(vec![c, b])).unwrap();
+    clock.advance(Duration::from_secs(61));
+    print!("{}", election.tally(&InstantRunoff).unwrap());
+
+    // 认可投票只能用简单多数计票
+    let mut approval = Election::new("lunch", BallotKind::Approval, &["pizza", "sushi", "tacos"], Poll::new(clock.clone())).unwrap();
+    for (i, picks) in [vec![0, 1], vec![1], vec![1, 2], vec![0, 2]].into_iter().enumerate() {
+        let voter = approval.register(&format!("lunch-{}", i)).unwrap();
+        approval.cast(&voter, Ballot::Approval(picks.into_iter().map(CandidateId).collect())).unwrap();
+    }
+    approval.close().unwrap();
+    println!("{}", approval.tally(&BordaCount).unwrap_err());
+    print!("{}", approval.tally(&FirstPastThePost).unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn counts(counts: &[(&str, u64)]) -> Vec<(String, u64)> {
+        counts.iter().map(|(name, count)| (name.to_string(), *count)).collect()
+    }
+
+    /// 登记一位投票人投一张票，最后关闭投票
+    fn closed_election(kind: BallotKind, candidates: &[&str], ballots: Vec<Ballot>) -> Election {
+        let mut election = Election::new("test", kind, candidates, Poll::new(Arc::new(ManualClock::new(Duration::ZERO)))).unwrap();
+        for (i, ballot) in ballots.into_iter().enumerate() {
+            let voter = election.register(&format!("v-{:03}", i)).unwrap();
+            election.cast(&voter, ballot).unwrap();
+        }
+        election.close().unwrap();
+        election
+    }
+
+    fn ranked(rankings: &[&[usize]]) -> Vec<Ballot> {
+        rankings.iter().map(|ranking| Ballot::Ranked(ranking.iter().copied().map(CandidateId).collect())).collect()
+    }
+
+    #[test]
+    fn first_past_the_post_counts_first_choices_and_reports_ties() {
+        let mut election = closed_election(BallotKind::Ranked, &["A", "B", "C"], ranked(&[&[0, 1], &[0, 2], &[1, 0], &[1], &[2]]));
+        let report = election.tally(&FirstPastThePost).unwrap();
+        assert_eq!(report.rounds[0].counts, counts(&[("A", 2), ("B", 2), ("C", 1)]));
+        assert_eq!(report.winners, names(&["A", "B"]));
+
+        let approvals = [vec![0, 1], vec![1], vec![1, 2], vec![0, 2]]
+            .into_iter()
+            .map(|picks| Ballot::Approval(picks.into_iter().map(CandidateId).collect()))
+            .collect();
+        let mut lunch = closed_election(BallotKind::Approval, &["pizza", "sushi", "tacos"], approvals);
+        let report = lunch.tally(&FirstPastThePost).unwrap();
+        assert_eq!(report.rounds[0].counts, counts(&[("sushi", 3), ("pizza", 2), ("tacos", 2)]));
+        assert_eq!(report.winners, names(&["sushi"]));
+        assert_eq!(
+            lunch.tally(&BordaCount),
+            Err(ElectionError::UnsupportedTally { strategy: "Borda count".to_string(), kind: BallotKind::Approval })
+        );
+        assert!(lunch.tally(&InstantRunoff).is_err());
+    }
+
+    #[test]
+    fn instant_runoff_transfers_votes_from_eliminated_candidates() {
+        let mut election =
+            closed_election(BallotKind::Ranked, &["A", "B", "C"], ranked(&[&[0, 1, 2], &[0, 2, 1], &[1, 2, 0], &[1, 2, 0], &[2, 1, 0]]));
+        let report = election.tally(&InstantRunoff).unwrap();
+        assert_eq!(report.rounds.len(), 2);
+        assert_eq!(report.rounds[0].counts, counts(&[("A", 2), ("B", 2), ("C", 1)]));
+        assert_eq!(report.rounds[0].eliminated, names(&["C"]));
+        assert_eq!(report.rounds[1].counts, counts(&[("B", 3), ("A", 2)]));
+        assert_eq!(report.winners, names(&["B"]));
+        assert!(report.to_string().contains("round 1: A 2, B 2, C 1; eliminated C"));
+    }
+
+    #[test]
+    fn instant_runoff_counts_exhausted_ballots_and_ends_in_a_tie() {
+        let mut election = closed_election(BallotKind::Ranked, &["A", "B", "C"], ranked(&[&[0], &[0], &[1], &[1], &[2]]));
+        let report = election.tally(&InstantRunoff).unwrap();
+        assert_eq!(report.rounds[0].eliminated, names(&["C"]));
+        // C 的选票没有其他选择，不再计入任何人
+        assert_eq!(report.rounds[1].exhausted, 1);
+        assert_eq!(report.rounds[1].counts, counts(&[("A", 2), ("B", 2)]));
+        assert_eq!(report.winners, names(&["A", "B"]));
+        assert!(report.to_string().contains("(exhausted 1)"));
+    }
+
+    #[test]
+    fn borda_count_scores_by_rank() {
+        let mut election =
+            closed_election(BallotKind::Ranked, &["A", "B", "C"], ranked(&[&[0, 1, 2], &[0, 2, 1], &[1, 2, 0], &[1, 2, 0], &[2, 1, 0]]));
+        let report = election.tally(&BordaCount).unwrap();
+        assert_eq!(report.rounds[0].counts, counts(&[("B", 6), ("C", 5), ("A", 4)]));
+        assert_eq!(report.winners, names(&["B"]));
+
+        // 只排了一部分的选票，未排序的候选人不得分
+        let mut partial = closed_election(BallotKind::Ranked, &["A", "B", "C"], ranked(&[&[2], &[1, 0]]));
+        assert_eq!(partial.tally(&BordaCount).unwrap().rounds[0].counts, counts(&[("B", 2), ("C", 2), ("A", 1)]));
+    }
+
+    #[test]
+    fn each_voter_casts_exactly_one_valid_ballot() {
+        let mut election = Election::new("test", BallotKind::Plurality, &["A", "B"], Poll::new(Arc::new(ManualClock::new(Duration::ZERO)))).unwrap();
+        let voter = election.register("v-001").unwrap();
+        assert_eq!(election.register("V-001"), Err(ElectionError::DuplicateVoter(voter.clone())));
+        assert_eq!(election.register("0bad"), Err(ElectionError::InvalidVoterId("0bad".to_string())));
+
+        let a = election.candidate("A").unwrap();
+        assert_eq!(
+            election.cast(&voter, Ballot::Ranked(vec![a])),
+            Err(ElectionError::WrongBallotKind { expected: BallotKind::Plurality, actual: BallotKind::Ranked })
+        );
+        assert!(matches!(election.cast(&voter, Ballot::Plurality(CandidateId(9))), Err(ElectionError::InvalidBallot(_))));
+        assert_eq!(election.turnout(), 0);
+
+        election.cast(&voter, Ballot::Plurality(a)).unwrap();
+        assert_eq!(election.cast(&voter, Ballot::Plurality(a)), Err(ElectionError::AlreadyVoted(voter.clone())));
+        let stranger = VoterId::parse("v-999").unwrap();
+        assert_eq!(election.cast(&stranger, Ballot::Plurality(a)), Err(ElectionError::UnknownVoter(stranger)));
+        assert_eq!(election.turnout(), 1);
+        assert_eq!(election.voter_status(&voter), Some(VoteStatus::Voted));
+    }
+
+    #[test]
+    fn ranked_ballots_cannot_repeat_candidates() {
+        let mut election = Election::new("test", BallotKind::Ranked, &["A", "B"], Poll::new(Arc::new(ManualClock::new(Duration::ZERO)))).unwrap();
+        let voter = election.register("v-001").unwrap();
+        assert_eq!(
+            election.cast(&voter, Ballot::Ranked(vec![CandidateId(0), CandidateId(0)])),
+            Err(ElectionError::InvalidBallot("candidate ranked more than once".to_string()))
+        );
+        assert_eq!(election.cast(&voter, Ballot::Ranked(Vec::new())), Err(ElectionError::InvalidBallot("no candidate selected".to_string())));
+    }
+
+    #[test]
+    fn duplicate_candidate_names_are_rejected() {
+        let poll = Poll::new(Arc::new(ManualClock::new(Duration::ZERO)));
+        let error = Election::new("test", BallotKind::Ranked, &["A", "B", "A"], poll).err();
+        assert_eq!(error, Some(ElectionError::DuplicateCandidate("A".to_string())));
+    }
+
+    #[test]
+    fn poll_lifecycle_locks_reopens_and_expires() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let poll = Poll::new(clock.clone()).with_admin_token("secret");
+        let mut election = Election::new("test", BallotKind::Plurality, &["A", "B"], poll).unwrap();
+        let voter = election.register("v-001").unwrap();
+        election.cast(&voter, Ballot::Plurality(CandidateId(0))).unwrap();
+        assert_eq!(election.tally(&FirstPastThePost), Err(ElectionError::PollOpen));
+
+        election.close().unwrap();
+        assert_eq!(election.status(), VoteStatus::Locked);
+        assert_eq!(election.voter_status(&voter), Some(VoteStatus::Locked));
+        assert_eq!(election.register("v-002"), Err(ElectionError::PollClosed));
+
+        election.reopen("secret", Some(Duration::from_secs(60))).unwrap();
+        assert_eq!(election.voter_status(&voter), Some(VoteStatus::Voted));
+        assert_eq!(election.cast(&voter, Ballot::Plurality(CandidateId(1))), Err(ElectionError::AlreadyVoted(voter.clone())));
+        let late = election.register("v-002").unwrap();
+        assert_eq!(election.voter_status(&late), Some(VoteStatus::NotVoted));
+
+        clock.advance(Duration::from_secs(61));
+        assert_eq!(election.cast(&late, Ballot::Plurality(CandidateId(1))), Err(ElectionError::PollClosed));
+        assert_eq!(election.voter_status(&late), Some(VoteStatus::Locked));
+        assert_eq!(election.tally(&FirstPastThePost).unwrap().winners, names(&["A"]));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,1110 @@
+//! ETL：抽取、转换、加载
+//! EtlJob 是一个模板方法：run 固定了整个流程 —— 读取检查点、跳过已处理的行、逐行转换并写出、
+//! 记录出错的行、定期保存检查点，具体任务只实现 extract、transform、load 三个步骤。
+//! 内置的数据源和目标：CSV 文件（支持引号、转义和字段内换行）、JSON lines 文件、内存中的 Vec。
+//! 单行出错（无法解析、转换失败、目标拒绝）不会中断任务，这一行连同原因写入死信文件；
+//! 读写文件失败则中断任务，下次运行从检查点记录的行号继续。
+//! 检查点在目标 flush 之后写入；checkpoint_every 大于 1 时，最后一个检查点之后已经写出的行
+//! 会在恢复时再写一次（至少一次）。
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::Chars;
+use std::sync::{Arc, Mutex};
+
+use crate::behavioral::escape::{csv_field, json_string};
+
+//-----------------行---------------------------------------------------------------------
+/// 字段值；CSV 中读出的都是 Text
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl Field {
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Field::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            Field::Null => "null".to_string(),
+            Field::Bool(b) => b.to_string(),
+            Field::Number(n) if n.is_finite() => self.to_string(),
+            Field::Number(_) => "null".to_string(),
+            Field::Text(text) => json_string(text),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Null => Ok(()),
+            Field::Bool(b) => write!(f, "{}", b),
+            Field::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Field::Number(n) => write!(f, "{}", n),
+            Field::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl From<&str> for Field {
+    fn from(text: &str) -> Self {
+        Field::Text(text.to_string())
+    }
+}
+
+impl From<f64> for Field {
+    fn from(n: f64) -> Self {
+        Field::Number(n)
+    }
+}
+
+/// 一行数据，字段保持原来的顺序
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Row {
+    fields: Vec<(String, Field)>,
+}
+
+impl Row {
+    pub fn new() -> Self {
+        Row::default()
+    }
+
+    pub fn with(mut self, name: &str, value: impl Into<Field>) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// 设置字段，已存在时替换并保持位置
+    pub fn set(&mut self, name: &str, value: impl Into<Field>) {
+        let value = value.into();
+        match self.fields.iter_mut().find(|(n, _)| n == name) {
+            Some((_, slot)) => *slot = value,
+            None => self.fields.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Field> {
+        let index = self.fields.iter().position(|(n, _)| n == name)?;
+        Some(self.fields.remove(index).1)
+    }
+
+    pub fn fields(&self) -> &[(String, Field)] {
+        &self.fields
+    }
+
+    pub fn to_json(&self) -> String {
+        let fields: Vec<String> = self.fields.iter().map(|(n, v)| format!("{}:{}", json_string(n), v.to_json())).collect();
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+//-----------------数据源与目标---------------------------------------------------------------------
+/// 读取失败：Malformed 只影响这一行，Io 中断任务
+#[derive(Debug)]
+pub enum SourceError {
+    Malformed { message: String, raw: String },
+    Io(io::Error),
+}
+
+impl From<io::Error> for SourceError {
+    fn from(error: io::Error) -> Self {
+        SourceError::Io(error)
+    }
+}
+
+/// 写出失败：Rejected 只影响这一行，Io 中断任务
+#[derive(Debug)]
+pub enum SinkError {
+    Rejected(String),
+    Io(io::Error),
+}
+
+impl From<io::Error> for SinkError {
+    fn from(error: io::Error) -> Self {
+        SinkError::Io(error)
+    }
+}
+
+/// 数据源，每次读取一行，读完返回 Ok(None)
+pub trait Source {
+    fn next_row(&mut self) -> Result<Option<Row>, SourceError>;
+}
+
+/// 写出目标
+pub trait Sink {
+    fn write_row(&mut self, row: &Row) -> Result<(), SinkError>;
+
+    /// 把缓冲的数据写到底层存储，保存检查点之前调用
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// 内存中的数据源
+pub struct MemorySource {
+    rows: VecDeque<Row>,
+}
+
+impl MemorySource {
+    pub fn new(rows: Vec<Row>) -> Self {
+        MemorySource { rows: rows.into() }
+    }
+}
+
+impl Source for MemorySource {
+    fn next_row(&mut self) -> Result<Option<Row>, SourceError> {
+        Ok(self.rows.pop_front())
+    }
+}
+
+/// 内存中的目标；克隆出的句柄共享同一个 Vec，任务结束后可以用来读取结果
+#[derive(Clone, Default)]
+pub struct MemorySink {
+    rows: Arc<Mutex<Vec<Row>>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        MemorySink::default()
+    }
+
+    pub fn rows(&self) -> Vec<Row> {
+        self.rows.lock().unwrap().clone()
+    }
+}
+
+impl Sink for MemorySink {
+    fn write_row(&mut self, row: &Row) -> Result<(), SinkError> {
+        self.rows.lock().unwrap().push(row.clone());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+//-----------------CSV---------------------------------------------------------------------
+/// 把一条 CSV 记录拆成字段：字段可以用双引号括起来，引号内的逗号和换行是字段内容，
+/// 两个连续的双引号表示一个双引号
+pub fn parse_csv_record(record: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut quoted = false;
+    let mut chars = record.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            ',' => {
+                fields.push(std::mem::take(&mut field));
+                quoted = false;
+            }
+            '"' if field.is_empty() && !quoted => {
+                in_quotes = true;
+                quoted = true;
+            }
+            '"' => return Err(format!("unexpected quote in field {}", fields.len() + 1)),
+            c if quoted => return Err(format!("unexpected {:?} after closing quote in field {}", c, fields.len() + 1)),
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!("unterminated quoted field {}", fields.len() + 1));
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// 记录末尾是否还在引号括起来的字段中，规则与 parse_csv_record 相同：
+/// 只有字段开头的引号才开始一个带引号的字段，其他位置的引号（例如 a,b"c）不影响记录在哪里结束
+fn ends_in_quotes(record: &str) -> bool {
+    let mut in_quotes = false;
+    let mut field_start = true;
+    // 刚刚闭合的引号后面紧跟引号是转义的 ""
+    let mut just_closed = false;
+    for c in record.chars() {
+        match c {
+            '"' if in_quotes => {
+                in_quotes = false;
+                just_closed = true;
+                continue;
+            }
+            '"' if field_start || just_closed => {
+                in_quotes = true;
+                field_start = false;
+            }
+            _ if in_quotes => {}
+            ',' => field_start = true,
+            _ => field_start = false,
+        }
+        just_closed = false;
+    }
+    in_quotes
+}
+
+/// CSV 数据源，第一条记录是表头
+pub struct CsvSource<R> {
+    reader: R,
+    header: Vec<String>,
+}
+
+impl CsvSource<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        CsvSource::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: BufRead> CsvSource<R> {
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut source = CsvSource { reader, header: Vec::new() };
+        if let Some(record) = source.read_record()? {
+            source.header = parse_csv_record(&record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("bad CSV header: {}", e)))?;
+        }
+        Ok(source)
+    }
+
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    /// 读出一条完整的记录：引号没有闭合时继续读下一行；跳过空行
+    fn read_record(&mut self) -> io::Result<Option<String>> {
+        let mut record = String::new();
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok((!record.is_empty()).then_some(record));
+            }
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if record.is_empty() && line.is_empty() {
+                continue;
+            }
+            if !record.is_empty() {
+                record.push('\n');
+            }
+            record.push_str(line);
+            if !ends_in_quotes(&record) {
+                return Ok(Some(record));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Source for CsvSource<R> {
+    fn next_row(&mut self) -> Result<Option<Row>, SourceError> {
+        let Some(record) = self.read_record()? else { return Ok(None) };
+        let malformed = |message: String| SourceError::Malformed { message, raw: record.clone() };
+        let values = parse_csv_record(&record).map_err(malformed)?;
+        if values.len() != self.header.len() {
+            return Err(malformed(format!("expected {} fields, found {}", self.header.len(), values.len())));
+        }
+        let mut row = Row::new();
+        for (name, value) in self.header.iter().zip(values) {
+            row.set(name, Field::Text(value));
+        }
+        Ok(Some(row))
+    }
+}
+
+/// CSV 目标：表头取自第一行；追加到已有文件时沿用文件中的表头。
+/// 缺少的列写为空，多出的列会被拒绝
+pub struct CsvSink {
+    writer: BufWriter<File>,
+    columns: Option<Vec<String>>,
+}
+
+impl CsvSink {
+    pub fn create(path: &Path, append: bool) -> io::Result<Self> {
+        let columns = if append && fs::metadata(path).is_ok_and(|m| m.len() > 0) {
+            Some(CsvSource::open(path)?.header().to_vec())
+        } else {
+            None
+        };
+        let file = OpenOptions::new().create(true).write(true).append(append).truncate(!append).open(path)?;
+        Ok(CsvSink { writer: BufWriter::new(file), columns })
+    }
+}
+
+impl Sink for CsvSink {
+    fn write_row(&mut self, row: &Row) -> Result<(), SinkError> {
+        let columns = match &self.columns {
+            Some(columns) => columns,
+            None => {
+                let header: Vec<String> = row.fields().iter().map(|(name, _)| name.clone()).collect();
+                let line: Vec<String> = header.iter().map(|name| csv_field(name)).collect();
+                writeln!(self.writer, "{}", line.join(","))?;
+                self.columns.insert(header)
+            }
+        };
+        if let Some((name, _)) = row.fields().iter().find(|(name, _)| !columns.contains(name)) {
+            return Err(SinkError::Rejected(format!("unexpected column {}", name)));
+        }
+        let values: Vec<String> = columns.iter().map(|name| csv_field(&row.get(name).map(Field::to_string).unwrap_or_default())).collect();
+        writeln!(self.writer, "{}", values.join(","))?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+//-----------------JSON lines---------------------------------------------------------------------
+/// 只支持一层对象的 JSON 解析，值可以是字符串、数字、布尔值和 null
+struct JsonParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl JsonParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected {:?}, found {:?}", expected, c)),
+            None => Err(format!("expected {:?}, found end of line", expected)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next().ok_or("unterminated string")? {
+                '"' => return Ok(out),
+                '\\' => match self.chars.next().ok_or("unterminated escape")? {
+                    'n' => out.push('\n'),
+                    'r' => out.push('\r'),
+                    't' => out.push('\t'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        let code = u32::from_str_radix(&hex, 16).map_err(|_| format!("bad unicode escape \\u{}", hex))?;
+                        out.push(char::from_u32(code).ok_or(format!("unsupported unicode escape \\u{}", hex))?);
+                    }
+                    c @ ('"' | '\\' | '/') => out.push(c),
+                    c => return Err(format!("unknown escape \\{}", c)),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Field, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('"') => self.string().map(Field::Text),
+            Some('{') | Some('[') => Err("nested values are not supported".to_string()),
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric() || "+-.".contains(*c)) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "null" => Ok(Field::Null),
+                    "true" => Ok(Field::Bool(true)),
+                    "false" => Ok(Field::Bool(false)),
+                    _ if is_json_number(&word) => match word.parse::<f64>() {
+                        Ok(n) if n.is_finite() => Ok(Field::Number(n)),
+                        _ => Err(format!("number out of range {:?}", word)),
+                    },
+                    _ => Err(format!("invalid value {:?}", word)),
+                }
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Row, String> {
+        let mut row = Row::new();
+        self.expect('{')?;
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_none() {
+            loop {
+                let name = self.string()?;
+                self.expect(':')?;
+                let value = self.value()?;
+                row.set(&name, value);
+                self.skip_whitespace();
+                match self.chars.next() {
+                    Some(',') => continue,
+                    Some('}') => break,
+                    _ => return Err("expected ',' or '}'".to_string()),
+                }
+            }
+        }
+        self.skip_whitespace();
+        match self.chars.next() {
+            None => Ok(row),
+            Some(c) => Err(format!("unexpected {:?} after object", c)),
+        }
+    }
+}
+
+/// 是否符合 JSON 的数字语法：-?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?
+/// f64::from_str 还接受 NaN、inf、+1、.5 这些写法，它们都不是 JSON 数字
+fn is_json_number(word: &str) -> bool {
+    fn digits(bytes: &[u8]) -> usize {
+        bytes.iter().take_while(|b| b.is_ascii_digit()).count()
+    }
+    let bytes = word.strip_prefix('-').unwrap_or(word).as_bytes();
+    let int = digits(bytes);
+    if int == 0 || (int > 1 && bytes[0] == b'0') {
+        return false;
+    }
+    let mut rest = &bytes[int..];
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let n = digits(fraction);
+        if n == 0 {
+            return false;
+        }
+        rest = &fraction[n..];
+    }
+    if let Some(exponent) = rest.strip_prefix(b"e").or_else(|| rest.strip_prefix(b"E")) {
+        let exponent = exponent.strip_prefix(b"+").or_else(|| exponent.strip_prefix(b"-")).unwrap_or(exponent);
+        let n = digits(exponent);
+        if n == 0 {
+            return false;
+        }
+        rest = &exponent[n..];
+    }
+    rest.is_empty()
+}
+
+/// 解析一行 JSON 对象
+pub fn parse_json_row(line: &str) -> Result<Row, String> {
+    JsonParser { chars: line.chars().peekable() }.object()
+}
+
+/// JSON lines 数据源，每个非空行是一个对象
+pub struct JsonLinesSource<R> {
+    reader: R,
+}
+
+impl JsonLinesSource<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(JsonLinesSource { reader: BufReader::new(File::open(path)?) })
+    }
+}
+
+impl<R: BufRead> Source for JsonLinesSource<R> {
+    fn next_row(&mut self) -> Result<Option<Row>, SourceError> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let raw = line.trim();
+            if !raw.is_empty() {
+                return parse_json_row(raw).map(Some).map_err(|message| SourceError::Malformed { message, raw: raw.to_string() });
+            }
+        }
+    }
+}
+
+/// JSON lines 目标
+pub struct JsonLinesSink {
+    writer: BufWriter<File>,
+}
+
+impl JsonLinesSink {
+    pub fn create(path: &Path, append: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).write(true).append(append).truncate(!append).open(path)?;
+        Ok(JsonLinesSink { writer: BufWriter::new(file) })
+    }
+}
+
+impl Sink for JsonLinesSink {
+    fn write_row(&mut self, row: &Row) -> Result<(), SinkError> {
+        writeln!(self.writer, "{}", row.to_json())?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+//-----------------模板---------------------------------------------------------------------
+/// 出错的阶段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Extract,
+    Transform,
+    Load,
+}
+
+/// 出错的行
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    /// 数据源中的行号，从 0 开始
+    pub offset: usize,
+    pub stage: Stage,
+    pub message: String,
+    /// 原始记录，或转换前后的行的 JSON
+    pub data: String,
+}
+
+impl RowError {
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"offset\":{},\"stage\":{},\"error\":{},\"data\":{}}}",
+            self.offset,
+            json_string(&format!("{:?}", self.stage).to_lowercase()),
+            json_string(&self.message),
+            json_string(&self.data)
+        )
+    }
+}
+
+/// 中断任务的错误
+#[derive(Debug)]
+pub enum EtlError {
+    Io(io::Error),
+    /// 检查点文件的内容不是行号
+    BadCheckpoint(String),
+}
+
+impl fmt::Display for EtlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EtlError::Io(error) => write!(f, "I/O error: {}", error),
+            EtlError::BadCheckpoint(content) => write!(f, "bad checkpoint {:?}", content),
+        }
+    }
+}
+
+impl std::error::Error for EtlError {}
+
+impl From<io::Error> for EtlError {
+    fn from(error: io::Error) -> Self {
+        EtlError::Io(error)
+    }
+}
+
+/// 运行选项
+#[derive(Debug, Clone)]
+pub struct EtlOptions {
+    dead_letter: Option<PathBuf>,
+    checkpoint: Option<PathBuf>,
+    checkpoint_every: usize,
+}
+
+impl Default for EtlOptions {
+    fn default() -> Self {
+        EtlOptions { dead_letter: None, checkpoint: None, checkpoint_every: 100 }
+    }
+}
+
+impl EtlOptions {
+    pub fn new() -> Self {
+        EtlOptions::default()
+    }
+
+    /// 出错的行以 JSON lines 追加到这个文件
+    pub fn dead_letter(mut self, path: &Path) -> Self {
+        self.dead_letter = Some(path.to_path_buf());
+        self
+    }
+
+    /// 每处理 every 行把下一行的行号写入检查点文件；文件存在时从记录的行号继续
+    pub fn checkpoint(mut self, path: &Path, every: usize) -> Self {
+        self.checkpoint = Some(path.to_path_buf());
+        self.checkpoint_every = every.max(1);
+        self
+    }
+}
+
+/// 运行结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EtlReport {
+    /// 本次从哪一行开始
+    pub resumed_from: usize,
+    /// 本次读取的行数，包括无法解析的行
+    pub read: usize,
+    pub loaded: usize,
+    /// 被 transform 过滤掉的行数
+    pub filtered: usize,
+    pub rejected: usize,
+}
+
+impl fmt::Display for EtlReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "resumed from row {}: {} read, {} loaded, {} filtered, {} rejected",
+            self.resumed_from, self.read, self.loaded, self.filtered, self.rejected
+        )
+    }
+}
+
+fn read_checkpoint(path: &Path) -> Result<usize, EtlError> {
+    match fs::read_to_string(path) {
+        Ok(content) => content.trim().parse().map_err(|_| EtlError::BadCheckpoint(content)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// 先写临时文件再改名，中断时不会留下写了一半的检查点
+fn write_checkpoint(path: &Path, offset: usize) -> io::Result<()> {
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, offset.to_string())?;
+    fs::rename(temp, path)
+}
+
+//定义 ETL 任务的抽象类
+pub trait EtlJob {
+    /// 打开数据源
+    fn extract(&mut self) -> io::Result<Box<dyn Source>>;
+
+    /// 转换一行，返回 Ok(None) 表示丢弃这一行，返回 Err 表示这一行出错
+    fn transform(&mut self, row: Row) -> Result<Option<Row>, String>;
+
+    /// 打开写出目标；append 为 true 表示从检查点恢复，应当追加而不是覆盖
+    fn load(&mut self, append: bool) -> io::Result<Box<dyn Sink>>;
+
+    /// 出错的行写入死信文件之后调用
+    fn on_reject(&mut self, _error: &RowError) {}
+
+    //模板方法
+    fn run(&mut self, options: &EtlOptions) -> Result<EtlReport, EtlError> {
+        let start = match &options.checkpoint {
+            Some(path) => read_checkpoint(path)?,
+            None => 0,
+        };
+        let mut report = EtlReport { resumed_from: start, ..EtlReport::default() };
+
+        let mut source = self.extract()?;
+        for _ in 0..start {
+            match source.next_row() {
+                Ok(None) => break,
+                Err(SourceError::Io(error)) => return Err(error.into()),
+                _ => {}
+            }
+        }
+        let mut sink = self.load(start > 0)?;
+        let mut dead_letter = match &options.dead_letter {
+            Some(path) => Some(BufWriter::new(OpenOptions::new().create(true).append(true).open(path)?)),
+            None => None,
+        };
+
+        let mut offset = start;
+        loop {
+            let rejected = match source.next_row() {
+                Ok(None) => break,
+                Err(SourceError::Io(error)) => return Err(error.into()),
+                Err(SourceError::Malformed { message, raw }) => Some(RowError { offset, stage: Stage::Extract, message, data: raw }),
+                Ok(Some(row)) => {
+                    let data = row.to_json();
+                    match self.transform(row) {
+                        Err(message) => Some(RowError { offset, stage: Stage::Transform, message, data }),
+                        Ok(None) => {
+                            report.filtered += 1;
+                            None
+                        }
+                        Ok(Some(row)) => match sink.write_row(&row) {
+                            Ok(()) => {
+                                report.loaded += 1;
+                                None
+                            }
+                            Err(SinkError::Rejected(message)) => {
+                                Some(RowError { offset, stage: Stage::Load, message, data: row.to_json() })
+                            }
+                            Err(SinkError::Io(error)) => return Err(error.into()),
+                        },
+                    }
+                }
+            };
+            report.read += 1;
+            if let Some(error) = rejected {
+                report.rejected += 1;
+                if let Some(writer) = dead_letter.as_mut() {
+                    writeln!(writer, "{}", error.to_json())?;
+                }
+                self.on_reject(&error);
+            }
+            offset += 1;
+
+            if let Some(path) = &options.checkpoint {
+                if (offset - start) % options.checkpoint_every == 0 {
+                    sink.flush()?;
+                    if let Some(writer) = dead_letter.as_mut() {
+                        writer.flush()?;
+                    }
+                    write_checkpoint(path, offset)?;
+                }
+            }
+        }
+
+        sink.flush()?;
+        if let Some(writer) = dead_letter.as_mut() {
+            writer.flush()?;
+        }
+        if let Some(path) = &options.checkpoint {
+            write_checkpoint(path, offset)?;
+        }
+        Ok(report)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// 订单导出：CSV 转成 JSON lines，金额转成数字，取消的订单丢弃
+struct OrderExport {
+    input: PathBuf,
+    output: PathBuf,
+    rejected: Vec<Stage>,
+}
+
+impl EtlJob for OrderExport {
+    fn extract(&mut self) -> io::Result<Box<dyn Source>> {
+        Ok(Box::new(CsvSource::open(&self.input)?))
+    }
+
+    fn transform(&mut self, mut row: Row) -> Result<Option<Row>, String> {
+        if row.get("status").and_then(Field::as_text) == Some("cancelled") {
+            return Ok(None);
+        }
+        let amount = row.get("amount").and_then(Field::as_text).unwrap_or("");
+        let amount: f64 = amount.trim().parse().map_err(|_| format!("invalid amount {:?}", amount))?;
+        row.set("amount", amount);
+        Ok(Some(row))
+    }
+
+    fn load(&mut self, append: bool) -> io::Result<Box<dyn Sink>> {
+        Ok(Box::new(JsonLinesSink::create(&self.output, append)?))
+    }
+
+    fn on_reject(&mut self, error: &RowError) {
+        self.rejected.push(error.stage);
+    }
+}
+
+// 写到第 fail_at 行时模拟磁盘故障
+struct FlakySink {
+    inner: Box<dyn Sink>,
+    fail_at: Option<usize>,
+    written: usize,
+}
+
+impl Sink for FlakySink {
+    fn write_row(&mut self, row: &Row) -> Result<(), SinkError> {
+        if self.fail_at == Some(self.written) {
+            return Err(SinkError::Io(io::Error::other("disk unavailable")));
+        }
+        self.written += 1;
+        self.inner.write_row(row)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// 内存中的数据写成 CSV，第一次运行在第 6 行中断
+struct Snapshot {
+    rows: Vec<Row>,
+    output: PathBuf,
+    fail_at: Option<usize>,
+}
+
+impl EtlJob for Snapshot {
+    fn extract(&mut self) -> io::Result<Box<dyn Source>> {
+        Ok(Box::new(MemorySource::new(self.rows.clone())))
+    }
+
+    fn transform(&mut self, row: Row) -> Result<Option<Row>, String> {
+        Ok(Some(row))
+    }
+
+    fn load(&mut self, append: bool) -> io::Result<Box<dyn Sink>> {
+        let inner = Box::new(CsvSink::create(&self.output, append)?);
+        Ok(Box::new(FlakySink { inner, fail_at: self.fail_at.take(), written: 0 }))
+    }
+}
+
+// 把任意数据源读进内存
+struct Collect<S> {
+    source: Option<S>,
+    sink: MemorySink,
+}
+
+impl<S: Source + 'static> EtlJob for Collect<S> {
+    fn extract(&mut self) -> io::Result<Box<dyn Source>> {
+        Ok(Box::new(self.source.take().expect("source is used once")))
+    }
+
+    fn transform(&mut self, row: Row) -> Result<Option<Row>, String> {
+        Ok(Some(row))
+    }
+
+    fn load(&mut self, _: bool) -> io::Result<Box<dyn Sink>> {
+        Ok(Box::new(self.sink.clone()))
+    }
+}
+
+// 引号中的逗号、换行和转义的引号；第 4 行引号后有多余字符，第 5 行字段数不对，第 6 行金额无效
+const ORDERS: &str = "id,customer,amount,status\r\n\
+                      1,\"Smith, Jane\",12.50,paid\r\n\
+                      2,\"Acme \"\"Tools\"\"\",7,cancelled\r\n\
+                      3,\"Line one\nline two\",3,paid\r\n\
+                      4,\"Bob\"x,1,paid\r\n\
+                      5,Eve,2\r\n\
+                      6,Mallory,lots,paid\r\n\
+                      \r\n\
+                      7,Trent,40,paid\r\n";
+
+pub fn main() {
+    let dir = std::env::temp_dir().join(format!("designPattern-etl-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let input = dir.join("orders.csv");
+    let output = dir.join("orders.jsonl");
+    let dead = dir.join("orders.dead.jsonl");
+    fs::write(&input, ORDERS).unwrap();
+
+    let mut job = OrderExport { input, output: output.clone(), rejected: Vec::new() };
+    let report = job.run(&EtlOptions::new().dead_letter(&dead)).unwrap();
+    println!("{}", report);
+    print!("{}", fs::read_to_string(&output).unwrap());
+    print!("{}", fs::read_to_string(&dead).unwrap());
+
+    // JSON lines 读回内存，字段类型保留
+    let sink = MemorySink::new();
+    let mut collect = Collect { source: Some(JsonLinesSource::open(&output).unwrap()), sink: sink.clone() };
+    collect.run(&EtlOptions::new()).unwrap();
+    for row in sink.rows() {
+        println!("{:?}", row.fields());
+    }
+
+    // 中断后从检查点继续，每行都不重复
+    let rows: Vec<Row> = (0..10).map(|i| Row::new().with("n", i as f64).with("note", if i == 3 { "a, \"b\"" } else { "" })).collect();
+    let checkpoint = dir.join("snapshot.checkpoint");
+    let options = EtlOptions::new().checkpoint(&checkpoint, 1);
+    let mut snapshot = Snapshot { rows, output: dir.join("snapshot.csv"), fail_at: Some(6) };
+    if let Err(error) = snapshot.run(&options) {
+        println!("first run: {}", error);
+    }
+    println!("second run: {}", snapshot.run(&options).unwrap());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("designPattern-etl-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read_csv(path: &Path) -> Vec<Row> {
+        let sink = MemorySink::new();
+        let mut collect = Collect { source: Some(CsvSource::open(path).unwrap()), sink: sink.clone() };
+        collect.run(&EtlOptions::new()).unwrap();
+        sink.rows()
+    }
+
+    #[test]
+    fn quotes_only_open_at_the_start_of_a_field() {
+        assert!(!ends_in_quotes(r#"1,a"b"#));
+        assert!(!ends_in_quotes(r#"1,"Bob"x,2"#));
+        assert!(!ends_in_quotes(r#"1,"Acme ""Tools""",2"#));
+        assert!(!ends_in_quotes(r#"1,"",2"#));
+        assert!(ends_in_quotes("1,\"Line one"));
+        assert!(!ends_in_quotes("1,\"say \"\"hi\"\"\""));
+        assert!(ends_in_quotes(r#"1,"a"",b"#));
+    }
+
+    #[test]
+    fn stray_quote_rejects_only_its_own_row() {
+        let csv = "id,name\n1,a\"b\n2,c\n3,\"x\ny\"\n4,d\n";
+        let mut source = CsvSource::new(Cursor::new(csv)).unwrap();
+        match source.next_row() {
+            Err(SourceError::Malformed { raw, .. }) => assert_eq!(raw, "1,a\"b"),
+            other => panic!("expected a malformed row, got {:?}", other.map(|_| ())),
+        }
+        let mut names = Vec::new();
+        while let Some(row) = source.next_row().unwrap() {
+            names.push(row.get("name").and_then(Field::as_text).unwrap().to_string());
+        }
+        assert_eq!(names, vec!["c", "x\ny", "d"]);
+    }
+
+    fn dead_letters(path: &Path) -> Vec<Row> {
+        fs::read_to_string(path).unwrap().lines().map(|line| parse_json_row(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn rejected_rows_go_to_the_dead_letter_file() {
+        let dir = temp_dir("orders");
+        let input = dir.join("orders.csv");
+        let output = dir.join("orders.jsonl");
+        let dead = dir.join("orders.dead.jsonl");
+        fs::write(&input, ORDERS).unwrap();
+
+        let mut job = OrderExport { input, output, rejected: Vec::new() };
+        let report = job.run(&EtlOptions::new().dead_letter(&dead)).unwrap();
+        assert_eq!((report.read, report.loaded, report.filtered, report.rejected), (7, 3, 1, 3));
+        assert_eq!(job.rejected, vec![Stage::Extract, Stage::Extract, Stage::Transform]);
+
+        let text = |s: &str| Field::Text(s.to_string());
+        let expected = vec![
+            Row::new()
+                .with("offset", 3.0)
+                .with("stage", "extract")
+                .with("error", "unexpected 'x' after closing quote in field 2")
+                .with("data", "4,\"Bob\"x,1,paid"),
+            Row::new().with("offset", 4.0).with("stage", "extract").with("error", "expected 4 fields, found 3").with("data", "5,Eve,2"),
+            Row::new()
+                .with("offset", 5.0)
+                .with("stage", "transform")
+                .with("error", "invalid amount \"lots\"")
+                .with("data", text(r#"{"id":"6","customer":"Mallory","amount":"lots","status":"paid"}"#)),
+        ];
+        assert_eq!(dead_letters(&dead), expected);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sink_rejections_are_reported_at_the_load_stage() {
+        let dir = temp_dir("load");
+        let output = dir.join("snapshot.csv");
+        let dead = dir.join("snapshot.dead.jsonl");
+        let rows = vec![
+            Row::new().with("a", 1.0).with("b", "x"),
+            Row::new().with("a", 2.0).with("b", "y").with("c", Field::Bool(true)),
+            Row::new().with("a", 3.0),
+        ];
+        let mut snapshot = Snapshot { rows, output: output.clone(), fail_at: None };
+        let report = snapshot.run(&EtlOptions::new().dead_letter(&dead)).unwrap();
+        assert_eq!((report.read, report.loaded, report.rejected), (3, 2, 1));
+
+        let expected = Row::new()
+            .with("offset", 1.0)
+            .with("stage", "load")
+            .with("error", "unexpected column c")
+            .with("data", r#"{"a":2,"b":"y","c":true}"#);
+        assert_eq!(dead_letters(&dead), vec![expected]);
+        // 缺少的列写为空
+        let written = read_csv(&output);
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[1].get("b"), Some(&Field::from("")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn json_lines_round_trip_keeps_field_types() {
+        let dir = temp_dir("jsonl");
+        let path = dir.join("rows.jsonl");
+        let rows = vec![
+            Row::new().with("text", "quote \" backslash \\ tab \t\nnewline").with("unicode", "日本 \u{1}").with("null", Field::Null),
+            Row::new().with("int", 42.0).with("negative", -2.5).with("small", 1e-7).with("big", 1e20).with("yes", Field::Bool(true)).with("no", Field::Bool(false)),
+            Row::new(),
+        ];
+        let mut sink = JsonLinesSink::create(&path, false).unwrap();
+        for row in &rows {
+            sink.write_row(row).unwrap();
+        }
+        sink.flush().unwrap();
+
+        let sink = MemorySink::new();
+        let mut collect = Collect { source: Some(JsonLinesSource::open(&path).unwrap()), sink: sink.clone() };
+        let report = collect.run(&EtlOptions::new()).unwrap();
+        assert_eq!(report.rejected, 0);
+        assert_eq!(sink.rows(), rows);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn numbers_follow_the_json_grammar() {
+        for accepted in ["0", "-0", "7", "-12.50", "1e3", "2E-2", "1.5e+10"] {
+            let row = parse_json_row(&format!("{{\"n\":{}}}", accepted)).unwrap();
+            assert_eq!(row.get("n"), Some(&Field::Number(accepted.parse().unwrap())), "{}", accepted);
+        }
+        for rejected in ["NaN", "inf", "-inf", "infinity", "+1", ".5", "1.", "01", "-", "1e", "1e+", "0x10", "1e400"] {
+            assert!(parse_json_row(&format!("{{\"n\":{}}}", rejected)).is_err(), "{}", rejected);
+        }
+    }
+
+    #[test]
+    fn resumes_from_the_checkpoint_without_duplicates() {
+        let dir = temp_dir("resume");
+        let rows: Vec<Row> = (0..10).map(|i| Row::new().with("n", i as f64).with("note", if i == 3 { "a, \"b\"" } else { "" })).collect();
+        let output = dir.join("snapshot.csv");
+        let checkpoint = dir.join("snapshot.checkpoint");
+        let options = EtlOptions::new().checkpoint(&checkpoint, 1);
+        let mut snapshot = Snapshot { rows, output: output.clone(), fail_at: Some(6) };
+
+        assert!(matches!(snapshot.run(&options), Err(EtlError::Io(_))));
+        assert_eq!(fs::read_to_string(&checkpoint).unwrap(), "6");
+        let report = snapshot.run(&options).unwrap();
+        assert_eq!((report.resumed_from, report.read, report.loaded), (6, 4, 4));
+        assert_eq!(fs::read_to_string(&checkpoint).unwrap(), "10");
+
+        let written = read_csv(&output);
+        let numbers: Vec<String> = written.iter().map(|row| row.get("n").unwrap().to_string()).collect();
+        assert_eq!(numbers, (0..10).map(|i| i.to_string()).collect::<Vec<_>>());
+        assert_eq!(written[3].get("note"), Some(&Field::from("a, \"b\"")));
+
+        // 已经完成的任务再运行一次不会重复写出
+        let report = snapshot.run(&options).unwrap();
+        assert_eq!((report.resumed_from, report.read), (10, 0));
+        assert_eq!(read_csv(&output).len(), 10);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sparse_checkpoints_replay_rows_after_the_last_one() {
+        let dir = temp_dir("sparse");
+        let rows: Vec<Row> = (0..10).map(|i| Row::new().with("n", i as f64)).collect();
+        let output = dir.join("snapshot.csv");
+        let checkpoint = dir.join("snapshot.checkpoint");
+        let options = EtlOptions::new().checkpoint(&checkpoint, 4);
+        let mut snapshot = Snapshot { rows, output: output.clone(), fail_at: Some(6) };
+
+        assert!(snapshot.run(&options).is_err());
+        assert_eq!(fs::read_to_string(&checkpoint).unwrap(), "4");
+        let report = snapshot.run(&options).unwrap();
+        assert_eq!((report.resumed_from, report.loaded), (4, 6));
+
+        // 至少一次：检查点之后已经写出的 4、5 会再写一次
+        let numbers: Vec<String> = read_csv(&output).iter().map(|row| row.get("n").unwrap().to_string()).collect();
+        assert_eq!(numbers, ["0", "1", "2", "3", "4", "5", "4", "5", "6", "7", "8", "9"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
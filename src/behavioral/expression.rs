@@ -0,0 +1,877 @@
+//! 表达式树上的访问者
+//! behavioral::visitor 只访问三个没有字段的状态结构体，看不出访问者模式在递归结构上的用处。
+//! 这里是公式功能的基础：一棵由数字、布尔值、变量、一元/二元运算、函数调用和 let 绑定组成的表达式树，
+//! 树本身只提供 accept，所有操作都写成访问者：
+//!     Evaluator        在给定的变量环境中求值
+//!     PrettyPrinter    输出带最少括号的公式文本
+//!     ConstantFolder   折叠常量子表达式，化简 x + 0、x * 1 等
+//!     FreeVariables    收集没有被 let 绑定的变量
+//!     TypeChecker      检查数字与布尔值是否用对了地方
+//! 增加新的操作只需要再写一个访问者，不必修改 Expr。
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+//-----------------表达式树---------------------------------------------------------------------
+/// 一元运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+/// 二元运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Lt,
+    Gt,
+    Eq,
+    And,
+    Or,
+}
+
+impl UnaryOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Not => "!",
+        }
+    }
+}
+
+impl BinaryOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Pow => "^",
+            BinaryOp::Lt => "<",
+            BinaryOp::Gt => ">",
+            BinaryOp::Eq => "==",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+
+    /// 优先级，数字越大结合越紧
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Eq => 3,
+            BinaryOp::Add | BinaryOp::Sub => 4,
+            BinaryOp::Mul | BinaryOp::Div => 5,
+            BinaryOp::Pow => 7,
+        }
+    }
+}
+
+/// 一元运算的优先级：-x^2 表示 -(x^2)
+const UNARY_PRECEDENCE: u8 = 6;
+/// 数字、变量、函数调用等不需要括号的表达式
+const ATOM_PRECEDENCE: u8 = 8;
+
+/// 表达式
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Bool(bool),
+    Variable(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    /// let name = value in body
+    Let(String, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn num(value: f64) -> Expr {
+        Expr::Number(value)
+    }
+
+    pub fn var(name: &str) -> Expr {
+        Expr::Variable(name.to_string())
+    }
+
+    pub fn unary(op: UnaryOp, operand: Expr) -> Expr {
+        Expr::Unary(op, Box::new(operand))
+    }
+
+    pub fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+        Expr::Binary(op, Box::new(left), Box::new(right))
+    }
+
+    pub fn call(name: &str, args: Vec<Expr>) -> Expr {
+        Expr::Call(name.to_string(), args)
+    }
+
+    pub fn let_in(name: &str, value: Expr, body: Expr) -> Expr {
+        Expr::Let(name.to_string(), Box::new(value), Box::new(body))
+    }
+
+    /// 把自己交给访问者对应的 visit 方法
+    pub fn accept<V: ExprVisitor>(&self, visitor: &mut V) -> V::Output {
+        match self {
+            Expr::Number(value) => visitor.visit_number(*value),
+            Expr::Bool(value) => visitor.visit_bool(*value),
+            Expr::Variable(name) => visitor.visit_variable(name),
+            Expr::Unary(op, operand) => visitor.visit_unary(*op, operand),
+            Expr::Binary(op, left, right) => visitor.visit_binary(*op, left, right),
+            Expr::Call(name, args) => visitor.visit_call(name, args),
+            Expr::Let(name, value, body) => visitor.visit_let(name, value, body),
+        }
+    }
+
+    /// 作为子表达式时的优先级
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Number(value) if *value < 0.0 => UNARY_PRECEDENCE,
+            Expr::Unary(..) => UNARY_PRECEDENCE,
+            Expr::Binary(op, ..) => op.precedence(),
+            Expr::Let(..) => 0,
+            _ => ATOM_PRECEDENCE,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.accept(&mut PrettyPrinter))
+    }
+}
+
+/// 表达式访问者，子表达式由访问者自己决定是否以及如何递归访问
+pub trait ExprVisitor {
+    type Output;
+
+    fn visit_number(&mut self, value: f64) -> Self::Output;
+    fn visit_bool(&mut self, value: bool) -> Self::Output;
+    fn visit_variable(&mut self, name: &str) -> Self::Output;
+    fn visit_unary(&mut self, op: UnaryOp, operand: &Expr) -> Self::Output;
+    fn visit_binary(&mut self, op: BinaryOp, left: &Expr, right: &Expr) -> Self::Output;
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> Self::Output;
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> Self::Output;
+}
+
+//-----------------值、类型与内置函数---------------------------------------------------------------------
+/// 求值结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Bool(bool),
+}
+
+/// 表达式的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Number,
+    Bool,
+}
+
+impl Value {
+    pub fn type_of(&self) -> Type {
+        match self {
+            Value::Number(_) => Type::Number,
+            Value::Bool(_) => Type::Bool,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// 内置函数的签名：参数类型和返回类型
+fn signature(name: &str) -> Option<(&'static [Type], Type)> {
+    use Type::*;
+    match name {
+        "abs" | "sqrt" => Some((&[Number], Number)),
+        "min" | "max" => Some((&[Number, Number], Number)),
+        "if" => Some((&[Bool, Number, Number], Number)),
+        _ => None,
+    }
+}
+
+/// 运算符的操作数类型和结果类型
+fn unary_type(op: UnaryOp) -> Type {
+    match op {
+        UnaryOp::Neg => Type::Number,
+        UnaryOp::Not => Type::Bool,
+    }
+}
+
+fn binary_types(op: BinaryOp) -> (Type, Type) {
+    match op {
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Pow => (Type::Number, Type::Number),
+        BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Eq => (Type::Number, Type::Bool),
+        BinaryOp::And | BinaryOp::Or => (Type::Bool, Type::Bool),
+    }
+}
+
+/// 求值或类型检查中的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprError {
+    UnboundVariable(String),
+    UnknownFunction(String),
+    Arity { function: String, expected: usize, actual: usize },
+    /// where_ 描述出错的位置，例如运算符或函数参数
+    TypeMismatch { where_: String, expected: Type, found: Type },
+    DivisionByZero,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            ExprError::UnknownFunction(name) => write!(f, "unknown function {}", name),
+            ExprError::Arity { function, expected, actual } => {
+                write!(f, "{} expects {} argument(s), got {}", function, expected, actual)
+            }
+            ExprError::TypeMismatch { where_, expected, found } => {
+                write!(f, "{}: expected {:?}, found {:?}", where_, expected, found)
+            }
+            ExprError::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+/// 检查函数是否存在、参数个数是否正确，返回签名
+fn check_call(name: &str, args: usize) -> Result<(&'static [Type], Type), ExprError> {
+    let (params, result) = signature(name).ok_or_else(|| ExprError::UnknownFunction(name.to_string()))?;
+    if params.len() != args {
+        return Err(ExprError::Arity { function: name.to_string(), expected: params.len(), actual: args });
+    }
+    Ok((params, result))
+}
+
+fn expect(where_: &str, expected: Type, found: Type) -> Result<(), ExprError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(ExprError::TypeMismatch { where_: where_.to_string(), expected, found })
+    }
+}
+
+//-----------------求值---------------------------------------------------------------------
+/// 在变量环境中求值；let 绑定的变量遮蔽环境中的同名变量
+pub struct Evaluator<'a> {
+    env: &'a HashMap<String, Value>,
+    /// let 绑定的局部变量，越靠后越内层
+    locals: Vec<(String, Value)>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(env: &'a HashMap<String, Value>) -> Self {
+        Evaluator { env, locals: Vec::new() }
+    }
+
+    fn number(&mut self, where_: &str, expr: &Expr) -> Result<f64, ExprError> {
+        match expr.accept(self)? {
+            Value::Number(value) => Ok(value),
+            other => Err(ExprError::TypeMismatch { where_: where_.to_string(), expected: Type::Number, found: other.type_of() }),
+        }
+    }
+
+    fn boolean(&mut self, where_: &str, expr: &Expr) -> Result<bool, ExprError> {
+        match expr.accept(self)? {
+            Value::Bool(value) => Ok(value),
+            other => Err(ExprError::TypeMismatch { where_: where_.to_string(), expected: Type::Bool, found: other.type_of() }),
+        }
+    }
+}
+
+impl ExprVisitor for Evaluator<'_> {
+    type Output = Result<Value, ExprError>;
+
+    fn visit_number(&mut self, value: f64) -> Self::Output {
+        Ok(Value::Number(value))
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Self::Output {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_variable(&mut self, name: &str) -> Self::Output {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(local, _)| local == name)
+            .map(|(_, value)| *value)
+            .or_else(|| self.env.get(name).copied())
+            .ok_or_else(|| ExprError::UnboundVariable(name.to_string()))
+    }
+
+    fn visit_unary(&mut self, op: UnaryOp, operand: &Expr) -> Self::Output {
+        let where_ = format!("operand of {}", op.symbol());
+        match op {
+            UnaryOp::Neg => Ok(Value::Number(-self.number(&where_, operand)?)),
+            UnaryOp::Not => Ok(Value::Bool(!self.boolean(&where_, operand)?)),
+        }
+    }
+
+    fn visit_binary(&mut self, op: BinaryOp, left: &Expr, right: &Expr) -> Self::Output {
+        let where_ = format!("operand of {}", op.symbol());
+        // 逻辑运算短路
+        if let BinaryOp::And | BinaryOp::Or = op {
+            let l = self.boolean(&where_, left)?;
+            if l == (op == BinaryOp::Or) {
+                return Ok(Value::Bool(l));
+            }
+            return Ok(Value::Bool(self.boolean(&where_, right)?));
+        }
+        let (l, r) = (self.number(&where_, left)?, self.number(&where_, right)?);
+        Ok(match op {
+            BinaryOp::Add => Value::Number(l + r),
+            BinaryOp::Sub => Value::Number(l - r),
+            BinaryOp::Mul => Value::Number(l * r),
+            BinaryOp::Div if r == 0.0 => return Err(ExprError::DivisionByZero),
+            BinaryOp::Div => Value::Number(l / r),
+            BinaryOp::Pow => Value::Number(l.powf(r)),
+            BinaryOp::Lt => Value::Bool(l < r),
+            BinaryOp::Gt => Value::Bool(l > r),
+            BinaryOp::Eq => Value::Bool(l == r),
+            BinaryOp::And | BinaryOp::Or => unreachable!(),
+        })
+    }
+
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> Self::Output {
+        check_call(name, args.len())?;
+        let where_ = format!("argument of {}", name);
+        // if 只对选中的分支求值
+        if name == "if" {
+            let branch = if self.boolean(&where_, &args[0])? { &args[1] } else { &args[2] };
+            return Ok(Value::Number(self.number(&where_, branch)?));
+        }
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(self.number(&where_, arg)?);
+        }
+        Ok(Value::Number(match name {
+            "abs" => values[0].abs(),
+            "sqrt" => values[0].sqrt(),
+            "min" => values[0].min(values[1]),
+            "max" => values[0].max(values[1]),
+            _ => unreachable!(),
+        }))
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> Self::Output {
+        let value = value.accept(self)?;
+        self.locals.push((name.to_string(), value));
+        let result = body.accept(self);
+        self.locals.pop();
+        result
+    }
+}
+
+//-----------------格式化---------------------------------------------------------------------
+/// 输出公式文本，只在改变含义时加括号：
+/// 加减乘除左结合，右侧同级需要括号；乘方右结合，左侧同级需要括号；比较不能连写，两侧同级都需要括号
+pub struct PrettyPrinter;
+
+impl PrettyPrinter {
+    fn child(&mut self, expr: &Expr, parenthesize: bool) -> String {
+        let text = expr.accept(self);
+        if parenthesize {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+}
+
+impl ExprVisitor for PrettyPrinter {
+    type Output = String;
+
+    fn visit_number(&mut self, value: f64) -> String {
+        format!("{}", value)
+    }
+
+    fn visit_bool(&mut self, value: bool) -> String {
+        value.to_string()
+    }
+
+    fn visit_variable(&mut self, name: &str) -> String {
+        name.to_string()
+    }
+
+    fn visit_unary(&mut self, op: UnaryOp, operand: &Expr) -> String {
+        // 负数字面量也要加括号，避免 --1
+        let parenthesize = operand.precedence() < UNARY_PRECEDENCE || matches!(operand, Expr::Number(v) if *v < 0.0);
+        format!("{}{}", op.symbol(), self.child(operand, parenthesize))
+    }
+
+    fn visit_binary(&mut self, op: BinaryOp, left: &Expr, right: &Expr) -> String {
+        let p = op.precedence();
+        let (left_paren, right_paren) = match op {
+            BinaryOp::Pow => (left.precedence() <= p, right.precedence() < p),
+            BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Eq => (left.precedence() <= p, right.precedence() <= p),
+            _ => (left.precedence() < p, right.precedence() <= p),
+        };
+        format!("{} {} {}", self.child(left, left_paren), op.symbol(), self.child(right, right_paren))
+    }
+
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| arg.accept(self)).collect();
+        format!("{}({})", name, args.join(", "))
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> String {
+        format!("let {} = {} in {}", name, value.accept(self), body.accept(self))
+    }
+}
+
+//-----------------常量折叠---------------------------------------------------------------------
+/// 折叠常量子表达式并做简单的代数化简，返回新的表达式，折叠前后求值结果（包括错误）相同：
+/// 求值会出错的常量表达式（例如 1 / 0）原样保留，留到运行时报告；
+/// x + 0、x * 1 这样的化简和去掉未使用的 let 绑定只在 x 一定能求出对应类型的值时进行。
+/// env 声明自由变量的类型，折叠结果只在求值环境与声明一致时与原表达式等价；不做假设时传入空表
+pub struct ConstantFolder<'a> {
+    env: &'a HashMap<String, Type>,
+    /// let 绑定的局部变量，None 表示绑定的值可能求值出错或类型未知
+    locals: Vec<(String, Option<Type>)>,
+}
+
+impl<'a> ConstantFolder<'a> {
+    pub fn new(env: &'a HashMap<String, Type>) -> Self {
+        ConstantFolder { env, locals: Vec::new() }
+    }
+
+    /// 子表达式都是常量时求值
+    fn try_evaluate(expr: Expr) -> Expr {
+        let constant = match &expr {
+            Expr::Unary(_, operand) => is_constant(operand),
+            Expr::Binary(_, left, right) => is_constant(left) && is_constant(right),
+            Expr::Call(_, args) => args.iter().all(is_constant),
+            _ => false,
+        };
+        if !constant {
+            return expr;
+        }
+        match expr.accept(&mut Evaluator::new(&HashMap::new())) {
+            Ok(Value::Number(value)) => Expr::Number(value),
+            Ok(Value::Bool(value)) => Expr::Bool(value),
+            Err(_) => expr,
+        }
+    }
+
+    /// 表达式一定能求值成功时返回结果的类型；可能出错（未绑定的变量、类型不符、除以零）时返回 None
+    fn safe_type(&mut self, expr: &Expr) -> Option<Type> {
+        let ty = match expr {
+            Expr::Number(_) => Type::Number,
+            Expr::Bool(_) => Type::Bool,
+            Expr::Variable(name) => match self.locals.iter().rev().find(|(local, _)| local == name) {
+                Some((_, ty)) => return *ty,
+                None => return self.env.get(name).copied(),
+            },
+            Expr::Unary(op, operand) => {
+                let ty = unary_type(*op);
+                (self.safe_type(operand)? == ty).then_some(ty)?
+            }
+            Expr::Binary(op, left, right) => {
+                let (operand, result) = binary_types(*op);
+                // 除数只有是非零常量时才不会出错
+                if *op == BinaryOp::Div && !matches!(**right, Expr::Number(r) if r != 0.0) {
+                    return None;
+                }
+                (self.safe_type(left)? == operand && self.safe_type(right)? == operand).then_some(result)?
+            }
+            Expr::Call(name, args) => {
+                let (params, result) = check_call(name, args.len()).ok()?;
+                for (param, arg) in params.iter().zip(args) {
+                    if self.safe_type(arg)? != *param {
+                        return None;
+                    }
+                }
+                result
+            }
+            Expr::Let(name, value, body) => {
+                let ty = self.safe_type(value)?;
+                self.locals.push((name.clone(), Some(ty)));
+                let result = self.safe_type(body);
+                self.locals.pop();
+                return result;
+            }
+        };
+        Some(ty)
+    }
+
+    fn is_safe(&mut self, expr: &Expr, ty: Type) -> bool {
+        self.safe_type(expr) == Some(ty)
+    }
+}
+
+fn is_constant(expr: &Expr) -> bool {
+    matches!(expr, Expr::Number(_) | Expr::Bool(_))
+}
+
+impl ExprVisitor for ConstantFolder<'_> {
+    type Output = Expr;
+
+    fn visit_number(&mut self, value: f64) -> Expr {
+        Expr::Number(value)
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Expr {
+        Expr::Bool(value)
+    }
+
+    fn visit_variable(&mut self, name: &str) -> Expr {
+        Expr::var(name)
+    }
+
+    fn visit_unary(&mut self, op: UnaryOp, operand: &Expr) -> Expr {
+        ConstantFolder::try_evaluate(Expr::unary(op, operand.accept(self)))
+    }
+
+    fn visit_binary(&mut self, op: BinaryOp, left: &Expr, right: &Expr) -> Expr {
+        let (left, right) = (left.accept(self), right.accept(self));
+        let n = |expr: &Expr, v: f64| matches!(expr, Expr::Number(x) if *x == v);
+        let b = |expr: &Expr, v: bool| matches!(expr, Expr::Bool(x) if *x == v);
+        match op {
+            // 化简掉的运算符本来会检查另一侧的类型，所以另一侧必须一定是数字或布尔值
+            BinaryOp::Add if n(&left, 0.0) && self.is_safe(&right, Type::Number) => right,
+            BinaryOp::Add | BinaryOp::Sub if n(&right, 0.0) && self.is_safe(&left, Type::Number) => left,
+            BinaryOp::Mul if n(&left, 1.0) && self.is_safe(&right, Type::Number) => right,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Pow if n(&right, 1.0) && self.is_safe(&left, Type::Number) => left,
+            BinaryOp::And if b(&left, true) && self.is_safe(&right, Type::Bool) => right,
+            BinaryOp::Or if b(&left, false) && self.is_safe(&right, Type::Bool) => right,
+            // 短路：右侧不会被求值，可以直接丢弃
+            BinaryOp::And if b(&left, false) => left,
+            BinaryOp::Or if b(&left, true) => left,
+            _ => ConstantFolder::try_evaluate(Expr::binary(op, left, right)),
+        }
+    }
+
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> Expr {
+        let args: Vec<Expr> = args.iter().map(|arg| arg.accept(self)).collect();
+        // 条件已知的 if 直接取对应分支，分支必须一定是数字，否则 if 本来会报类型错误
+        if let (true, Some(Expr::Bool(condition))) = (name == "if" && args.len() == 3, args.first()) {
+            let branch = &args[if *condition { 1 } else { 2 }];
+            if self.is_safe(branch, Type::Number) {
+                return branch.clone();
+            }
+        }
+        ConstantFolder::try_evaluate(Expr::call(name, args))
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> Expr {
+        let value = value.accept(self);
+        let ty = self.safe_type(&value);
+        self.locals.push((name.to_string(), ty));
+        let body = body.accept(self);
+        self.locals.pop();
+        // 变量没有被使用、绑定的值也不会出错时去掉绑定
+        if ty.is_some() && !free_variables(&body).contains(name) {
+            return body;
+        }
+        Expr::let_in(name, value, body)
+    }
+}
+
+//-----------------自由变量---------------------------------------------------------------------
+/// 收集没有被 let 绑定的变量，结果累积在 names 中
+#[derive(Default)]
+pub struct FreeVariables {
+    pub names: BTreeSet<String>,
+    bound: Vec<String>,
+}
+
+impl ExprVisitor for FreeVariables {
+    type Output = ();
+
+    fn visit_number(&mut self, _: f64) {}
+
+    fn visit_bool(&mut self, _: bool) {}
+
+    fn visit_variable(&mut self, name: &str) {
+        if !self.bound.iter().any(|bound| bound == name) {
+            self.names.insert(name.to_string());
+        }
+    }
+
+    fn visit_unary(&mut self, _: UnaryOp, operand: &Expr) {
+        operand.accept(self);
+    }
+
+    fn visit_binary(&mut self, _: BinaryOp, left: &Expr, right: &Expr) {
+        left.accept(self);
+        right.accept(self);
+    }
+
+    fn visit_call(&mut self, _: &str, args: &[Expr]) {
+        for arg in args {
+            arg.accept(self);
+        }
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) {
+        // 绑定的变量只在 body 中可见
+        value.accept(self);
+        self.bound.push(name.to_string());
+        body.accept(self);
+        self.bound.pop();
+    }
+}
+
+/// 表达式中的自由变量
+pub fn free_variables(expr: &Expr) -> BTreeSet<String> {
+    let mut visitor = FreeVariables::default();
+    expr.accept(&mut visitor);
+    visitor.names
+}
+
+//-----------------类型检查---------------------------------------------------------------------
+/// 根据变量的类型声明检查表达式，返回表达式的类型
+pub struct TypeChecker<'a> {
+    env: &'a HashMap<String, Type>,
+    locals: Vec<(String, Type)>,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn new(env: &'a HashMap<String, Type>) -> Self {
+        TypeChecker { env, locals: Vec::new() }
+    }
+}
+
+impl ExprVisitor for TypeChecker<'_> {
+    type Output = Result<Type, ExprError>;
+
+    fn visit_number(&mut self, _: f64) -> Self::Output {
+        Ok(Type::Number)
+    }
+
+    fn visit_bool(&mut self, _: bool) -> Self::Output {
+        Ok(Type::Bool)
+    }
+
+    fn visit_variable(&mut self, name: &str) -> Self::Output {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(local, _)| local == name)
+            .map(|(_, ty)| *ty)
+            .or_else(|| self.env.get(name).copied())
+            .ok_or_else(|| ExprError::UnboundVariable(name.to_string()))
+    }
+
+    fn visit_unary(&mut self, op: UnaryOp, operand: &Expr) -> Self::Output {
+        let ty = unary_type(op);
+        expect(&format!("operand of {}", op.symbol()), ty, operand.accept(self)?)?;
+        Ok(ty)
+    }
+
+    fn visit_binary(&mut self, op: BinaryOp, left: &Expr, right: &Expr) -> Self::Output {
+        let (operand, result) = binary_types(op);
+        let where_ = format!("operand of {}", op.symbol());
+        expect(&where_, operand, left.accept(self)?)?;
+        expect(&where_, operand, right.accept(self)?)?;
+        Ok(result)
+    }
+
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> Self::Output {
+        let (params, result) = check_call(name, args.len())?;
+        for (i, (param, arg)) in params.iter().zip(args).enumerate() {
+            expect(&format!("argument {} of {}", i + 1, name), *param, arg.accept(self)?)?;
+        }
+        Ok(result)
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> Self::Output {
+        let ty = value.accept(self)?;
+        self.locals.push((name.to_string(), ty));
+        let result = body.accept(self);
+        self.locals.pop();
+        result
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+pub fn main() {
+    use BinaryOp::*;
+
+    // let rate = 0.1 + 0.05 in if(price > 100, price * (1 - rate), price) + shipping * 1
+    let expr = Expr::let_in(
+        "rate",
+        Expr::binary(Add, Expr::num(0.1), Expr::num(0.05)),
+        Expr::binary(
+            Add,
+            Expr::call(
+                "if",
+                vec![
+                    Expr::binary(Gt, Expr::var("price"), Expr::num(100.0)),
+                    Expr::binary(Mul, Expr::var("price"), Expr::binary(Sub, Expr::num(1.0), Expr::var("rate"))),
+                    Expr::var("price"),
+                ],
+            ),
+            Expr::binary(Mul, Expr::var("shipping"), Expr::num(1.0)),
+        ),
+    );
+    println!("{}", expr);
+    assert_eq!(expr.to_string(), "let rate = 0.1 + 0.05 in if(price > 100, price * (1 - rate), price) + shipping * 1");
+
+    // 自由变量：rate 被 let 绑定，不算
+    let free = free_variables(&expr);
+    println!("free variables: {:?}", free);
+    assert_eq!(free.into_iter().collect::<Vec<_>>(), vec!["price", "shipping"]);
+
+    // 类型检查
+    let types: HashMap<String, Type> = [("price".to_string(), Type::Number), ("shipping".to_string(), Type::Number)].into();
+    assert_eq!(expr.accept(&mut TypeChecker::new(&types)), Ok(Type::Number));
+    let bad = Expr::binary(Add, Expr::var("price"), Expr::binary(Lt, Expr::num(1.0), Expr::num(2.0)));
+    println!("{}: {}", bad, bad.accept(&mut TypeChecker::new(&types)).unwrap_err());
+
+    // 求值
+    let env: HashMap<String, Value> = [("price".to_string(), Value::Number(200.0)), ("shipping".to_string(), Value::Number(5.0))].into();
+    let value = expr.accept(&mut Evaluator::new(&env)).unwrap();
+    println!("= {}", value);
+    assert_eq!(value, Value::Number(175.0));
+    let div = Expr::binary(Div, Expr::var("price"), Expr::binary(Sub, Expr::num(2.0), Expr::num(2.0)));
+    assert_eq!(div.accept(&mut Evaluator::new(&env)), Err(ExprError::DivisionByZero));
+
+    // 常量折叠
+    let folded = expr.accept(&mut ConstantFolder::new(&types));
+    println!("folded: {}", folded);
+    assert_eq!(folded.to_string(), "let rate = 0.15000000000000002 in if(price > 100, price * (1 - rate), price) + shipping");
+    assert_eq!(folded.accept(&mut Evaluator::new(&env)), Ok(value));
+    let dead = Expr::let_in("unused", Expr::num(1.0), Expr::call("max", vec![Expr::num(2.0), Expr::num(3.0)]));
+    assert_eq!(dead.accept(&mut ConstantFolder::new(&HashMap::new())), Expr::num(3.0));
+
+    // 最少括号：结构不同的树输出不同的文本
+    let cases = [
+        (Expr::binary(Sub, Expr::var("a"), Expr::binary(Sub, Expr::var("b"), Expr::var("c"))), "a - (b - c)"),
+        (Expr::binary(Sub, Expr::binary(Sub, Expr::var("a"), Expr::var("b")), Expr::var("c")), "a - b - c"),
+        (Expr::binary(Pow, Expr::var("a"), Expr::binary(Pow, Expr::var("b"), Expr::var("c"))), "a ^ b ^ c"),
+        (Expr::binary(Pow, Expr::binary(Pow, Expr::var("a"), Expr::var("b")), Expr::var("c")), "(a ^ b) ^ c"),
+        (Expr::unary(UnaryOp::Neg, Expr::binary(Pow, Expr::var("x"), Expr::num(2.0))), "-x ^ 2"),
+        (Expr::binary(Pow, Expr::unary(UnaryOp::Neg, Expr::var("x")), Expr::num(2.0)), "(-x) ^ 2"),
+        (Expr::binary(Pow, Expr::num(-2.0), Expr::num(2.0)), "(-2) ^ 2"),
+        (Expr::binary(Or, Expr::var("p"), Expr::binary(And, Expr::var("q"), Expr::var("r"))), "p || q && r"),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(expr.to_string(), expected);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use BinaryOp::*;
+
+    fn eval(expr: &Expr, env: &HashMap<String, Value>) -> Result<Value, ExprError> {
+        expr.accept(&mut Evaluator::new(env))
+    }
+
+    /// 折叠前后求值结果相同，NaN 视为相等
+    fn assert_fold_preserves(expr: &Expr, types: &HashMap<String, Type>, env: &HashMap<String, Value>) {
+        let folded = expr.accept(&mut ConstantFolder::new(types));
+        let (before, after) = (eval(expr, env), eval(&folded, env));
+        let both_nan = matches!((&before, &after), (Ok(Value::Number(a)), Ok(Value::Number(b))) if a.is_nan() && b.is_nan());
+        assert!(both_nan || before == after, "{} => {}: {:?} != {:?}", expr, folded, before, after);
+    }
+
+    fn env() -> (HashMap<String, Type>, HashMap<String, Value>) {
+        let values: HashMap<String, Value> = [("x".to_string(), Value::Number(3.0)), ("p".to_string(), Value::Bool(true))].into();
+        let types = values.iter().map(|(name, value)| (name.clone(), value.type_of())).collect();
+        (types, values)
+    }
+
+    #[test]
+    fn unused_bindings_that_fail_are_kept() {
+        let (_, values) = env();
+        let none = HashMap::new();
+        let cases = [
+            Expr::let_in("x", Expr::binary(Div, Expr::num(1.0), Expr::num(0.0)), Expr::num(3.0)),
+            Expr::let_in("x", Expr::var("y"), Expr::num(3.0)),
+            Expr::let_in("x", Expr::binary(Add, Expr::Bool(true), Expr::num(1.0)), Expr::num(3.0)),
+        ];
+        for expr in cases {
+            assert_eq!(expr.accept(&mut ConstantFolder::new(&none)), expr);
+            assert_fold_preserves(&expr, &none, &values);
+        }
+        let unused = Expr::let_in("x", Expr::binary(Div, Expr::num(1.0), Expr::num(2.0)), Expr::num(3.0));
+        assert_eq!(unused.accept(&mut ConstantFolder::new(&none)), Expr::num(3.0));
+    }
+
+    #[test]
+    fn identities_check_the_operand_type() {
+        let (types, values) = env();
+        let none = HashMap::new();
+        let cases = [
+            Expr::binary(Add, Expr::Bool(true), Expr::num(0.0)),
+            Expr::binary(Add, Expr::num(0.0), Expr::var("p")),
+            Expr::binary(Mul, Expr::num(1.0), Expr::var("p")),
+            Expr::binary(Div, Expr::var("p"), Expr::num(1.0)),
+            Expr::binary(And, Expr::Bool(true), Expr::num(1.0)),
+            Expr::call("if", vec![Expr::Bool(true), Expr::var("p"), Expr::num(0.0)]),
+        ];
+        for expr in &cases {
+            assert_fold_preserves(expr, &types, &values);
+            assert_fold_preserves(expr, &none, &values);
+        }
+        // 类型未知的变量不化简，已声明为数字的变量照常化简
+        let x_times_one = Expr::binary(Mul, Expr::var("x"), Expr::num(1.0));
+        assert_eq!(x_times_one.accept(&mut ConstantFolder::new(&none)), x_times_one);
+        assert_eq!(x_times_one.accept(&mut ConstantFolder::new(&types)), Expr::var("x"));
+        let local = Expr::let_in("r", Expr::num(2.0), Expr::binary(Add, Expr::num(0.0), Expr::var("r")));
+        assert_eq!(local.accept(&mut ConstantFolder::new(&none)).to_string(), "let r = 2 in r");
+    }
+
+    /// 固定种子的 xorshift 随机数
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    /// 随机生成不一定类型正确的表达式，常量偏向 0、1 以触发化简和除以零
+    fn random_expr(rng: &mut XorShift, depth: u32) -> Expr {
+        const BINARY: [BinaryOp; 10] = [Add, Sub, Mul, Div, Pow, Lt, Gt, Eq, And, Or];
+        const VARIABLES: [&str; 4] = ["x", "p", "y", "v"];
+        const FUNCTIONS: [(&str, usize); 5] = [("abs", 1), ("min", 2), ("max", 2), ("if", 3), ("nope", 1)];
+        let leaf = depth == 0 || rng.below(4) == 0;
+        match if leaf { rng.below(3) } else { 3 + rng.below(4) } {
+            0 => Expr::num([0.0, 1.0, 2.0, -1.5][rng.below(4) as usize]),
+            1 => Expr::Bool(rng.below(2) == 0),
+            2 => Expr::var(VARIABLES[rng.below(4) as usize]),
+            3 => Expr::unary(if rng.below(2) == 0 { UnaryOp::Neg } else { UnaryOp::Not }, random_expr(rng, depth - 1)),
+            4 => {
+                let (function, arity) = FUNCTIONS[rng.below(5) as usize];
+                Expr::call(function, (0..arity).map(|_| random_expr(rng, depth - 1)).collect())
+            }
+            5 => Expr::let_in(VARIABLES[rng.below(4) as usize], random_expr(rng, depth - 1), random_expr(rng, depth - 1)),
+            _ => Expr::binary(BINARY[rng.below(10) as usize], random_expr(rng, depth - 1), random_expr(rng, depth - 1)),
+        }
+    }
+
+    #[test]
+    fn folding_preserves_evaluation_including_errors() {
+        let (types, values) = env();
+        let none = HashMap::new();
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+        for _ in 0..5000 {
+            let expr = random_expr(&mut rng, 4);
+            assert_fold_preserves(&expr, &types, &values);
+            assert_fold_preserves(&expr, &none, &values);
+        }
+    }
+}
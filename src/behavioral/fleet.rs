@@ -0,0 +1,285 @@
+//! 投票机集群的统计访问者
+//! 运维需要一个看板：每个状态下有多少台投票机、每台在各状态停留了多久、哪些机器出了异常。
+//! FleetVisitor 在 StateVisitor 的基础上增加了按机器访问和合并两个步骤，
+//! visit_fleet 把投票机分给多个线程，每个线程用自己的访问者统计一段，最后按分段顺序合并；
+//! 统计结果只依赖机器编号，与线程数和线程调度无关。
+//! state::VotingState 要求 Send + Sync，投票机可以直接在线程之间共享引用。
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::behavioral::clock::{Clock, ManualClock};
+use crate::behavioral::state::{Poll, VoteStatus};
+use crate::behavioral::visitor::{Locked, NotVoted, StateVisitor, Voted, VotingMachine};
+
+/// 跨投票机集群运行的访问者
+pub trait FleetVisitor: StateVisitor<Output = ()> + Send + Sized {
+    /// 访问编号为 id 的投票机，默认只让当前状态接受访问
+    fn visit_machine(&mut self, id: usize, machine: &VotingMachine) {
+        let _ = id;
+        machine.accept(self);
+    }
+
+    /// 合并另一个访问者的结果；other 统计的机器编号都大于 self 统计过的
+    fn merge(&mut self, other: Self);
+}
+
+/// 把投票机平均分给 threads 个线程访问，每个线程用 factory 创建访问者，结果按机器顺序合并
+pub fn visit_fleet<V, F>(machines: &[VotingMachine], threads: usize, factory: F) -> V
+where
+    V: FleetVisitor,
+    F: Fn() -> V + Sync,
+{
+    let chunk = machines.len().div_ceil(threads.max(1)).max(1);
+    let factory = &factory;
+    let partials: Vec<V> = thread::scope(|scope| {
+        let handles: Vec<_> = machines
+            .chunks(chunk)
+            .enumerate()
+            .map(|(n, part)| {
+                scope.spawn(move || {
+                    let mut visitor = factory();
+                    for (i, machine) in part.iter().enumerate() {
+                        visitor.visit_machine(n * chunk + i, machine);
+                    }
+                    visitor
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().expect("fleet visitor panicked")).collect()
+    });
+
+    let mut result = factory();
+    for partial in partials {
+        result.merge(partial);
+    }
+    result
+}
+
+//-----------------统计访问者---------------------------------------------------------------------
+/// 异常
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    /// 截止时间已过，但投票机还没有锁定（通常是没有调用 tick）
+    MissedDeadline { machine: usize, overdue: Duration },
+    /// 长时间停留在 NotVoted
+    Idle { machine: usize, idle: Duration },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::MissedDeadline { machine, overdue } => {
+                write!(f, "machine #{} missed its deadline by {:?}", machine, overdue)
+            }
+            Anomaly::Idle { machine, idle } => write!(f, "machine #{} idle for {:?}", machine, idle),
+        }
+    }
+}
+
+/// 单台投票机在各状态停留的时间
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StateTimes {
+    pub not_voted: Duration,
+    pub voted: Duration,
+    pub locked: Duration,
+}
+
+/// 集群统计
+pub struct FleetStats {
+    /// 停留在 NotVoted 超过这个时间视为异常
+    idle_threshold: Duration,
+    clock: Arc<dyn Clock>,
+    /// 各状态的机器数
+    pub not_voted: usize,
+    pub voted: usize,
+    pub locked: usize,
+    /// 按机器编号排列的停留时间
+    pub times: BTreeMap<usize, StateTimes>,
+    /// 按机器编号排列的异常
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl FleetStats {
+    /// clock 用来判断截止时间，应与投票机使用同一个时钟
+    pub fn new(clock: Arc<dyn Clock>, idle_threshold: Duration) -> Self {
+        FleetStats {
+            idle_threshold,
+            clock,
+            not_voted: 0,
+            voted: 0,
+            locked: 0,
+            times: BTreeMap::new(),
+            anomalies: Vec::new(),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.not_voted + self.voted + self.locked
+    }
+
+    /// 全部机器在 status 状态停留时间的总和
+    pub fn total_time(&self, status: VoteStatus) -> Duration {
+        self.times
+            .values()
+            .map(|t| match status {
+                VoteStatus::NotVoted => t.not_voted,
+                VoteStatus::Voted => t.voted,
+                VoteStatus::Locked => t.locked,
+            })
+            .sum()
+    }
+}
+
+impl StateVisitor for FleetStats {
+    type Output = ();
+
+    fn visit_not_voted(&mut self, _: &NotVoted) {
+        self.not_voted += 1;
+    }
+
+    fn visit_voted(&mut self, _: &Voted) {
+        self.voted += 1;
+    }
+
+    fn visit_locked(&mut self, _: &Locked) {
+        self.locked += 1;
+    }
+}
+
+impl FleetVisitor for FleetStats {
+    fn visit_machine(&mut self, id: usize, machine: &VotingMachine) {
+        machine.accept(self);
+
+        let times = StateTimes {
+            not_voted: machine.time_in(VoteStatus::NotVoted),
+            voted: machine.time_in(VoteStatus::Voted),
+            locked: machine.time_in(VoteStatus::Locked),
+        };
+        self.times.insert(id, times);
+
+        let now = self.clock.now();
+        match machine.poll().deadline() {
+            Some(deadline) if machine.state() != VoteStatus::Locked && now >= deadline => {
+                self.anomalies.push(Anomaly::MissedDeadline { machine: id, overdue: now - deadline });
+            }
+            _ => {}
+        }
+        if machine.state() == VoteStatus::NotVoted && times.not_voted > self.idle_threshold {
+            self.anomalies.push(Anomaly::Idle { machine: id, idle: times.not_voted });
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.not_voted += other.not_voted;
+        self.voted += other.voted;
+        self.locked += other.locked;
+        self.times.extend(other.times);
+        self.anomalies.extend(other.anomalies);
+    }
+}
+
+impl fmt::Display for FleetStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "machines: {}", self.total())?;
+        for (name, count, status) in [
+            ("NotVoted", self.not_voted, VoteStatus::NotVoted),
+            ("Voted", self.voted, VoteStatus::Voted),
+            ("Locked", self.locked, VoteStatus::Locked),
+        ] {
+            writeln!(f, "  {:<8} {:>4} machines, {:>6}s total", name, count, self.total_time(status).as_secs())?;
+        }
+        for anomaly in &self.anomalies {
+            writeln!(f, "  ! {}", anomaly)?;
+        }
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+/// 演示用的 10 台投票机：前 6 台在第 10 秒投票，其中前 3 台在第 20 秒关闭；
+/// 第 9 台设置了 30 秒的截止时间，时钟停在第 40 秒
+fn demo_fleet(clock: &Arc<ManualClock>) -> Vec<VotingMachine> {
+    let mut machines: Vec<VotingMachine> = (0..10).map(|_| VotingMachine::with_poll(Poll::new(clock.clone()))).collect();
+    clock.advance(Duration::from_secs(10));
+    for machine in &mut machines[..6] {
+        machine.vote();
+    }
+    clock.advance(Duration::from_secs(10));
+    for machine in &mut machines[..3] {
+        machine.close_poll().unwrap();
+    }
+    machines[9].open_poll(Some(Duration::from_secs(30))).unwrap();
+    clock.advance(Duration::from_secs(20));
+    machines
+}
+
+pub fn main() {
+    let clock = Arc::new(ManualClock::new(Duration::ZERO));
+    let machines = demo_fleet(&clock);
+    let stats = visit_fleet(&machines, 4, || FleetStats::new(clock.clone(), Duration::from_secs(35)));
+    print!("{}", stats);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(threads: usize) -> FleetStats {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let machines = demo_fleet(&clock);
+        visit_fleet(&machines, threads, || FleetStats::new(clock.clone(), Duration::from_secs(35)))
+    }
+
+    #[test]
+    fn counts_times_and_anomalies() {
+        let stats = stats(4);
+        assert_eq!((stats.not_voted, stats.voted, stats.locked), (4, 3, 3));
+        assert_eq!(stats.total(), 10);
+        assert_eq!(
+            stats.times[&0],
+            StateTimes { not_voted: Duration::from_secs(10), voted: Duration::from_secs(10), locked: Duration::from_secs(20) }
+        );
+        assert_eq!(
+            stats.times[&5],
+            StateTimes { not_voted: Duration::from_secs(10), voted: Duration::from_secs(30), locked: Duration::ZERO }
+        );
+        assert_eq!(stats.total_time(VoteStatus::NotVoted), Duration::from_secs(6 * 10 + 4 * 40));
+        assert_eq!(stats.total_time(VoteStatus::Voted), Duration::from_secs(3 * 10 + 3 * 30));
+        assert_eq!(stats.total_time(VoteStatus::Locked), Duration::from_secs(3 * 20));
+        assert_eq!(
+            stats.anomalies,
+            vec![
+                Anomaly::Idle { machine: 6, idle: Duration::from_secs(40) },
+                Anomaly::Idle { machine: 7, idle: Duration::from_secs(40) },
+                Anomaly::Idle { machine: 8, idle: Duration::from_secs(40) },
+                Anomaly::MissedDeadline { machine: 9, overdue: Duration::from_secs(10) },
+                Anomaly::Idle { machine: 9, idle: Duration::from_secs(40) },
+            ]
+        );
+    }
+
+    #[test]
+    fn result_does_not_depend_on_thread_count() {
+        let expected = stats(1);
+        assert_eq!(expected.times.keys().copied().collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+        // 0 按 1 个线程处理；超过机器数时每个线程最多一台
+        for threads in [0, 3, 16, 25] {
+            let other = stats(threads);
+            assert_eq!((other.not_voted, other.voted, other.locked), (expected.not_voted, expected.voted, expected.locked));
+            assert_eq!(other.times, expected.times, "threads = {}", threads);
+            assert_eq!(other.anomalies, expected.anomalies, "threads = {}", threads);
+        }
+    }
+
+    #[test]
+    fn empty_fleet() {
+        let clock: Arc<dyn Clock> = Arc::new(ManualClock::new(Duration::ZERO));
+        let stats = visit_fleet(&[], 4, || FleetStats::new(clock.clone(), Duration::from_secs(1)));
+        assert_eq!(stats.total(), 0);
+        assert!(stats.times.is_empty() && stats.anomalies.is_empty());
+    }
+}
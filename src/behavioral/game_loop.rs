@@ -0,0 +1,809 @@
+//! 固定步长的游戏循环
+//! Game 是一个模板方法：run 固定了循环的骨架，具体游戏实现 init、handle_input、update、render、shutdown 几个钩子。
+//! 模拟按固定步长 dt 推进，与渲染频率无关：每一帧把经过的时间累积起来，够一个 dt 就更新一次，
+//! 剩余的不足一个 dt 的部分作为插值系数交给 render。
+//! 输入按模拟的 tick 而不是帧来分发，所以录下的输入在任何帧率下回放都会得到相同的状态；
+//! 每个 tick 之后记录状态哈希，两次运行的哈希序列可以直接比较。
+//! 状态哈希由游戏自己把状态按固定顺序写成小端字节再计算，不依赖 #[derive(Hash)]，
+//! 因为标准库不保证 Hash 的输出在不同平台和 Rust 版本之间保持一致。
+//! TextRenderer 把每一帧画成字符网格，不需要窗口，可以在 CI 中运行。
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::behavioral::clock::{Clock, ManualClock};
+use crate::behavioral::retry::{RandomSource, SeededRng};
+
+//-----------------状态哈希---------------------------------------------------------------------
+/// FNV-1a 64 位状态哈希
+/// 整数一律按小端字节写入，不提供 usize 等随平台变化的类型，所以结果可以写进录像文件，在任何平台上回放
+pub struct StateHasher(u64);
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        StateHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        StateHasher::default()
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        self
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn i64(&mut self, value: i64) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+//-----------------输入---------------------------------------------------------------------
+/// 输入与录像文件中一行文本之间的转换
+pub trait InputCodec: Sized {
+    fn encode(&self) -> String;
+    fn decode(text: &str) -> Option<Self>;
+}
+
+/// 输入来源：返回第 tick 个模拟步骤之前到达的输入
+pub trait InputSource<I> {
+    fn poll(&mut self, tick: u64) -> Vec<I>;
+}
+
+/// 闭包也可以作为输入来源
+impl<I, F: FnMut(u64) -> Vec<I>> InputSource<I> for F {
+    fn poll(&mut self, tick: u64) -> Vec<I> {
+        self(tick)
+    }
+}
+
+/// 录制经过的输入
+pub struct Recorder<S, I> {
+    inner: S,
+    inputs: Vec<(u64, I)>,
+}
+
+impl<S: InputSource<I>, I: Clone> Recorder<S, I> {
+    pub fn new(inner: S) -> Self {
+        Recorder { inner, inputs: Vec::new() }
+    }
+
+    /// 结合运行结果生成录像
+    pub fn finish(self, summary: &RunSummary) -> Recording<I> {
+        Recording { dt: summary.dt, ticks: summary.ticks, inputs: self.inputs, final_hash: summary.final_hash() }
+    }
+}
+
+impl<S: InputSource<I>, I: Clone> InputSource<I> for Recorder<S, I> {
+    fn poll(&mut self, tick: u64) -> Vec<I> {
+        let inputs = self.inner.poll(tick);
+        self.inputs.extend(inputs.iter().map(|input| (tick, input.clone())));
+        inputs
+    }
+}
+
+/// 按录像回放输入
+pub struct Replay<I> {
+    inputs: VecDeque<(u64, I)>,
+}
+
+impl<I> InputSource<I> for Replay<I> {
+    fn poll(&mut self, tick: u64) -> Vec<I> {
+        let mut inputs = Vec::new();
+        while self.inputs.front().is_some_and(|(t, _)| *t <= tick) {
+            inputs.push(self.inputs.pop_front().unwrap().1);
+        }
+        inputs
+    }
+}
+
+//-----------------录像---------------------------------------------------------------------
+/// 读取录像失败
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(error) => write!(f, "I/O error: {}", error),
+            RecordingError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl From<io::Error> for RecordingError {
+    fn from(error: io::Error) -> Self {
+        RecordingError::Io(error)
+    }
+}
+
+/// 回放结果与录像不一致
+#[derive(Debug, Clone, PartialEq)]
+pub struct Desync {
+    pub expected_dt: Duration,
+    pub actual_dt: Duration,
+    pub expected_ticks: u64,
+    pub actual_ticks: u64,
+    pub expected_hash: u64,
+    pub actual_hash: u64,
+}
+
+impl fmt::Display for Desync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.expected_dt != self.actual_dt {
+            return write!(f, "replay desynced: recorded with dt {:?}, replayed with dt {:?}", self.expected_dt, self.actual_dt);
+        }
+        write!(
+            f,
+            "replay desynced: expected {:016x} after {} ticks, got {:016x} after {} ticks",
+            self.expected_hash, self.expected_ticks, self.actual_hash, self.actual_ticks
+        )
+    }
+}
+
+impl std::error::Error for Desync {}
+
+/// 一次运行的输入录像
+/// 文件格式：第一行 `dt <微秒>`，每个输入一行 `<tick> <输入>`（tick 不能减小），最后一行 `end <tick 数> <最终哈希>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording<I> {
+    pub dt: Duration,
+    pub ticks: u64,
+    pub inputs: Vec<(u64, I)>,
+    pub final_hash: u64,
+}
+
+impl<I: InputCodec + Clone> Recording<I> {
+    pub fn to_text(&self) -> String {
+        let mut out = format!("dt {}\n", self.dt.as_micros());
+        for (tick, input) in &self.inputs {
+            out.push_str(&format!("{} {}\n", tick, input.encode()));
+        }
+        out.push_str(&format!("end {} {:016x}\n", self.ticks, self.final_hash));
+        out
+    }
+
+    pub fn parse(text: &str) -> Result<Self, RecordingError> {
+        let mut dt = None;
+        let mut end = None;
+        let mut inputs = Vec::new();
+        for (index, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let error = |message: &str| RecordingError::Parse { line: index + 1, message: message.to_string() };
+            let (head, rest) = line.split_once(' ').ok_or_else(|| error("expected two fields"))?;
+            match head {
+                "dt" => match rest.parse() {
+                    Ok(micros) if micros > 0 => dt = Some(Duration::from_micros(micros)),
+                    _ => return Err(error("invalid dt")),
+                },
+                "end" => {
+                    let (ticks, hash) = rest.split_once(' ').ok_or_else(|| error("expected tick count and hash"))?;
+                    let ticks = ticks.parse().map_err(|_| error("invalid tick count"))?;
+                    let hash = u64::from_str_radix(hash, 16).map_err(|_| error("invalid hash"))?;
+                    end = Some((ticks, hash));
+                }
+                tick => {
+                    let tick = tick.parse().map_err(|_| error("invalid tick"))?;
+                    // 回放按顺序消费输入，乱序的输入会被推迟到更晚的 tick
+                    if inputs.last().is_some_and(|(last, _)| tick < *last) {
+                        return Err(error("tick goes backwards"));
+                    }
+                    let input = I::decode(rest).ok_or_else(|| error("unknown input"))?;
+                    inputs.push((tick, input));
+                }
+            }
+        }
+        let dt = dt.ok_or(RecordingError::Parse { line: 1, message: "missing dt".to_string() })?;
+        let (ticks, final_hash) = end.ok_or(RecordingError::Parse { line: 0, message: "missing end".to_string() })?;
+        Ok(Recording { dt, ticks, inputs, final_hash })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, RecordingError> {
+        Recording::parse(&fs::read_to_string(path)?)
+    }
+
+    /// 回放用的输入来源
+    pub fn replay(&self) -> Replay<I> {
+        Replay { inputs: self.inputs.iter().cloned().collect() }
+    }
+
+    /// 检查回放结果与录像是否一致；步长不同时即使哈希碰巧相同也算失步
+    pub fn verify(&self, summary: &RunSummary) -> Result<(), Desync> {
+        if summary.dt == self.dt && summary.ticks == self.ticks && summary.final_hash() == self.final_hash {
+            return Ok(());
+        }
+        Err(Desync {
+            expected_dt: self.dt,
+            actual_dt: summary.dt,
+            expected_ticks: self.ticks,
+            actual_ticks: summary.ticks,
+            expected_hash: self.final_hash,
+            actual_hash: summary.final_hash(),
+        })
+    }
+}
+
+//-----------------渲染---------------------------------------------------------------------
+/// 渲染接口
+pub trait Renderer {
+    fn begin_frame(&mut self);
+    fn draw_text(&mut self, x: i32, y: i32, text: &str);
+    fn end_frame(&mut self);
+}
+
+/// 无窗口的文本渲染器：每一帧画成 width × height 的字符网格，超出范围的字符被裁掉
+pub struct TextRenderer {
+    width: usize,
+    height: usize,
+    grid: Vec<Vec<char>>,
+    frames: Vec<String>,
+    /// 只保留最近的这么多帧
+    keep: usize,
+}
+
+impl TextRenderer {
+    pub fn new(width: usize, height: usize, keep: usize) -> Self {
+        TextRenderer { width, height, grid: Vec::new(), frames: Vec::new(), keep: keep.max(1) }
+    }
+
+    pub fn frames(&self) -> &[String] {
+        &self.frames
+    }
+
+    pub fn last_frame(&self) -> Option<&str> {
+        self.frames.last().map(String::as_str)
+    }
+}
+
+impl Renderer for TextRenderer {
+    fn begin_frame(&mut self) {
+        self.grid = vec![vec![' '; self.width]; self.height];
+    }
+
+    fn draw_text(&mut self, x: i32, y: i32, text: &str) {
+        if y < 0 || y as usize >= self.height {
+            return;
+        }
+        for (i, c) in text.chars().enumerate() {
+            let column = x + i as i32;
+            if column >= 0 && (column as usize) < self.width {
+                self.grid[y as usize][column as usize] = c;
+            }
+        }
+    }
+
+    fn end_frame(&mut self) {
+        let lines: Vec<String> = self.grid.iter().map(|row| row.iter().collect::<String>().trim_end().to_string()).collect();
+        self.frames.push(lines.join("\n"));
+        if self.frames.len() > self.keep {
+            self.frames.remove(0);
+        }
+    }
+}
+
+//-----------------循环---------------------------------------------------------------------
+/// 循环参数
+#[derive(Clone)]
+pub struct LoopConfig {
+    clock: Arc<dyn Clock>,
+    dt: Duration,
+    frame_interval: Duration,
+    max_frame: Duration,
+    max_ticks: u64,
+}
+
+impl LoopConfig {
+    /// 以 dt 为模拟步长，默认每 1/60 秒渲染一帧
+    /// dt 为 0 时 panic：累积的时间永远不小于 0，循环会一直更新而不渲染
+    pub fn new(clock: Arc<dyn Clock>, dt: Duration) -> Self {
+        assert!(!dt.is_zero(), "fixed timestep must be positive");
+        LoopConfig { clock, dt, frame_interval: Duration::from_micros(16_667), max_frame: Duration::from_millis(250), max_ticks: u64::MAX }
+    }
+
+    /// 两帧之间等待的时间
+    pub fn frame_interval(mut self, interval: Duration) -> Self {
+        self.frame_interval = interval;
+        self
+    }
+
+    /// 单帧最多计入的时间，卡顿之后不会一次补上太多步
+    pub fn max_frame(mut self, max_frame: Duration) -> Self {
+        self.max_frame = max_frame;
+        self
+    }
+
+    /// 模拟到这么多步后停止
+    pub fn max_ticks(mut self, ticks: u64) -> Self {
+        self.max_ticks = ticks;
+        self
+    }
+
+    pub fn dt(&self) -> Duration {
+        self.dt
+    }
+}
+
+/// 一次运行的结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunSummary {
+    /// 模拟步长
+    pub dt: Duration,
+    pub ticks: u64,
+    pub frames: u64,
+    /// 每个 tick 之后的状态哈希
+    pub hashes: Vec<u64>,
+}
+
+impl RunSummary {
+    /// 最后的状态哈希，没有模拟过时为 0
+    pub fn final_hash(&self) -> u64 {
+        self.hashes.last().copied().unwrap_or(0)
+    }
+}
+
+//定义游戏循环的抽象类
+pub trait Game {
+    type Input;
+
+    /// 循环开始前调用一次
+    fn init(&mut self) {}
+
+    /// 处理一个输入，在同一 tick 的 update 之前调用
+    fn handle_input(&mut self, input: &Self::Input);
+
+    /// 推进一个固定步长
+    fn update(&mut self, dt: Duration);
+
+    /// 画出当前状态；alpha 在 [0, 1) 之间，是距离下一个 tick 的比例，可以用来插值
+    fn render(&self, alpha: f64, renderer: &mut dyn Renderer);
+
+    /// 循环结束后调用一次
+    fn shutdown(&mut self) {}
+
+    /// 状态哈希，相同的输入序列必须得到相同的哈希
+    fn state_hash(&self) -> u64;
+
+    /// 返回 true 时循环在当前 tick 之后结束
+    fn is_finished(&self) -> bool {
+        false
+    }
+
+    //模板方法
+    fn run(&mut self, config: &LoopConfig, input: &mut dyn InputSource<Self::Input>, renderer: &mut dyn Renderer) -> RunSummary {
+        let dt = config.dt;
+        let mut summary = RunSummary { dt, ..RunSummary::default() };
+        self.init();
+        let mut previous = config.clock.now();
+        let mut accumulator = Duration::ZERO;
+        while summary.ticks < config.max_ticks && !self.is_finished() {
+            let now = config.clock.now();
+            accumulator += now.saturating_sub(previous).min(config.max_frame);
+            previous = now;
+
+            while accumulator >= dt && summary.ticks < config.max_ticks && !self.is_finished() {
+                for event in input.poll(summary.ticks) {
+                    self.handle_input(&event);
+                }
+                self.update(dt);
+                summary.ticks += 1;
+                summary.hashes.push(self.state_hash());
+                accumulator -= dt;
+            }
+
+            renderer.begin_frame();
+            self.render(accumulator.as_secs_f64() / dt.as_secs_f64(), renderer);
+            renderer.end_frame();
+            summary.frames += 1;
+            config.clock.sleep(config.frame_interval);
+        }
+        self.shutdown();
+        summary
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// 单人弹球：球在场地里反弹，底部的挡板左右移动接球。坐标用整数的千分之一格，结果与平台无关
+const WIDTH: i64 = 24;
+const HEIGHT: i64 = 8;
+const PADDLE: i64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Left,
+    Right,
+}
+
+impl InputCodec for Command {
+    fn encode(&self) -> String {
+        match self {
+            Command::Left => "left".to_string(),
+            Command::Right => "right".to_string(),
+        }
+    }
+
+    fn decode(text: &str) -> Option<Self> {
+        match text {
+            "left" => Some(Command::Left),
+            "right" => Some(Command::Right),
+            _ => None,
+        }
+    }
+}
+
+struct Ball {
+    x: i64,
+    y: i64,
+    /// 每秒移动的千分之一格
+    vx: i64,
+    vy: i64,
+}
+
+struct Pong {
+    ball: Ball,
+    previous: (i64, i64),
+    paddle: i64,
+    caught: u32,
+    missed: u32,
+    lives: u32,
+}
+
+impl Pong {
+    fn new(lives: u32) -> Self {
+        Pong { ball: Ball { x: 2_000, y: 1_000, vx: 9_000, vy: 6_000 }, previous: (2_000, 1_000), paddle: 8, caught: 0, missed: 0, lives }
+    }
+}
+
+impl Game for Pong {
+    type Input = Command;
+
+    fn handle_input(&mut self, input: &Command) {
+        self.paddle = match input {
+            Command::Left => (self.paddle - 1).max(0),
+            Command::Right => (self.paddle + 1).min(WIDTH - PADDLE),
+        };
+    }
+
+    fn update(&mut self, dt: Duration) {
+        let micros = dt.as_micros() as i64;
+        let ball = &mut self.ball;
+        self.previous = (ball.x, ball.y);
+        ball.x += ball.vx * micros / 1_000_000;
+        ball.y += ball.vy * micros / 1_000_000;
+        if ball.x < 0 || ball.x >= WIDTH * 1_000 {
+            ball.vx = -ball.vx;
+            ball.x = ball.x.clamp(0, WIDTH * 1_000 - 1);
+        }
+        if ball.y < 0 {
+            ball.vy = -ball.vy;
+            ball.y = 0;
+        }
+        if ball.y >= (HEIGHT - 1) * 1_000 {
+            let column = ball.x / 1_000;
+            if (self.paddle..self.paddle + PADDLE).contains(&column) {
+                self.caught += 1;
+            } else {
+                self.missed += 1;
+            }
+            ball.vy = -ball.vy;
+            ball.y = (HEIGHT - 1) * 1_000 - 1;
+        }
+    }
+
+    fn render(&self, alpha: f64, renderer: &mut dyn Renderer) {
+        let lerp = |from: i64, to: i64| (from as f64 + (to - from) as f64 * alpha) as i64 / 1_000;
+        renderer.draw_text(lerp(self.previous.0, self.ball.x) as i32, lerp(self.previous.1, self.ball.y) as i32, "o");
+        renderer.draw_text(self.paddle as i32, HEIGHT as i32, &"=".repeat(PADDLE as usize));
+        renderer.draw_text(0, HEIGHT as i32 + 1, &format!("caught {} missed {}", self.caught, self.missed));
+    }
+
+    fn state_hash(&self) -> u64 {
+        let ball = &self.ball;
+        StateHasher::new()
+            .i64(ball.x)
+            .i64(ball.y)
+            .i64(ball.vx)
+            .i64(ball.vy)
+            .i64(self.previous.0)
+            .i64(self.previous.1)
+            .i64(self.paddle)
+            .u32(self.caught)
+            .u32(self.missed)
+            .u32(self.lives)
+            .finish()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.missed >= self.lives
+    }
+}
+
+/// 每次 sleep 都多走一段随机时间的时钟，模拟不稳定的帧率
+struct JitterClock {
+    clock: ManualClock,
+    rng: Mutex<SeededRng>,
+    max_jitter: Duration,
+}
+
+impl Clock for JitterClock {
+    fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let jitter = self.rng.lock().unwrap().between(Duration::ZERO, self.max_jitter);
+        self.clock.advance(duration + jitter);
+    }
+}
+
+const DT: Duration = Duration::from_micros(10_000);
+
+/// 录制一局：30 帧每秒、帧间隔有抖动；“玩家”每 50 毫秒随机按一次左右
+fn record_session(ticks: u64) -> (RunSummary, Recording<Command>, TextRenderer) {
+    let clock = Arc::new(JitterClock { clock: ManualClock::new(Duration::ZERO), rng: Mutex::new(SeededRng::new(49)), max_jitter: Duration::from_millis(20) });
+    let config = LoopConfig::new(clock, DT).frame_interval(Duration::from_micros(33_333)).max_ticks(ticks);
+    let mut rng = SeededRng::new(7);
+    let player = move |tick: u64| match (tick % 5, rng.next_u64() % 3) {
+        (0, 0) => vec![Command::Left],
+        (0, 1) => vec![Command::Right],
+        _ => vec![],
+    };
+    let mut recorder = Recorder::new(player);
+    let mut renderer = TextRenderer::new(WIDTH as usize, HEIGHT as usize + 2, 3);
+    let summary = Pong::new(u32::MAX).run(&config, &mut recorder, &mut renderer);
+    let recording = recorder.finish(&summary);
+    (summary, recording, renderer)
+}
+
+/// 按录像回放，frame_interval 为回放时的帧间隔
+fn replay(recording: &Recording<Command>, frame_interval: Duration) -> RunSummary {
+    let clock = Arc::new(ManualClock::new(Duration::from_secs(1_000)));
+    let config = LoopConfig::new(clock, recording.dt).frame_interval(frame_interval).max_ticks(recording.ticks);
+    let mut renderer = TextRenderer::new(WIDTH as usize, HEIGHT as usize + 2, 1);
+    Pong::new(u32::MAX).run(&config, &mut recording.replay(), &mut renderer)
+}
+
+pub fn main() {
+    let (summary, recording, renderer) = record_session(1_500);
+    println!("recorded {} ticks in {} frames, {} inputs", summary.ticks, summary.frames, recording.inputs.len());
+    println!("{}", renderer.last_frame().unwrap());
+
+    // 录像写入文件再读回
+    let path = std::env::temp_dir().join(format!("designPattern-pong-{}.rec", std::process::id()));
+    recording.save(&path).unwrap();
+    let loaded: Recording<Command> = Recording::load(&path).unwrap();
+    let _ = fs::remove_file(&path);
+
+    // 回放：换成 144 帧每秒或 10 帧每秒，结果都与录制时相同
+    for interval in [Duration::from_micros(6_944), Duration::from_millis(100)] {
+        let result = replay(&loaded, interval);
+        println!("replayed at {:?} per frame: {} frames, {:?}", interval, result.frames, loaded.verify(&result));
+    }
+
+    // 输入不同就会失步
+    let mut altered = loaded.clone();
+    altered.inputs.retain(|(_, command)| *command == Command::Right);
+    println!("{}", loaded.verify(&replay(&altered, DT)).unwrap_err());
+
+    // 换了步长的回放直接判为失步
+    let mut halved = loaded.clone();
+    halved.dt = DT * 2;
+    println!("{}", loaded.verify(&replay(&halved, DT)).unwrap_err());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_hasher_is_fnv1a_over_little_endian_bytes() {
+        // FNV-1a 64 的标准测试向量
+        assert_eq!(StateHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(StateHasher::new().bytes(b"a").finish(), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(StateHasher::new().u32(0x6463_6261).finish(), StateHasher::new().bytes(b"abcd").finish());
+        assert_eq!(StateHasher::new().i64(-1).finish(), StateHasher::new().bytes(&[0xff; 8]).finish());
+    }
+
+    #[test]
+    fn pong_hash_is_pinned() {
+        // 录像文件里的哈希要在任何平台上都能复现，编码改变时这里会失败
+        let mut game = Pong::new(3);
+        let expected = StateHasher::new()
+            .i64(2_000)
+            .i64(1_000)
+            .i64(9_000)
+            .i64(6_000)
+            .i64(2_000)
+            .i64(1_000)
+            .i64(8)
+            .u32(0)
+            .u32(0)
+            .u32(3)
+            .finish();
+        assert_eq!(game.state_hash(), expected);
+        game.handle_input(&Command::Left);
+        assert_ne!(game.state_hash(), expected);
+    }
+
+    #[test]
+    fn replay_matches_recording_at_any_frame_rate() {
+        let (summary, recording, _) = record_session(600);
+        assert_eq!(summary.ticks, 600);
+        assert_eq!(summary.dt, DT);
+        assert!(!recording.inputs.is_empty());
+        for interval in [Duration::from_micros(6_944), DT, Duration::from_millis(100)] {
+            let result = replay(&recording, interval);
+            assert_eq!(result.hashes, summary.hashes, "replay at {:?} per frame", interval);
+            assert_eq!(recording.verify(&result), Ok(()));
+        }
+    }
+
+    #[test]
+    fn recording_survives_save_and_load() {
+        let (_, recording, _) = record_session(300);
+        assert_eq!(Recording::parse(&recording.to_text()).unwrap(), recording);
+
+        let path = std::env::temp_dir().join(format!("designPattern-pong-test-{}.rec", std::process::id()));
+        recording.save(&path).unwrap();
+        let loaded: Result<Recording<Command>, _> = Recording::load(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.unwrap(), recording);
+    }
+
+    #[test]
+    fn altered_inputs_desync() {
+        let (summary, recording, _) = record_session(600);
+        let mut altered = recording.clone();
+        altered.inputs.retain(|(_, command)| *command == Command::Right);
+        let result = replay(&altered, DT);
+        let desync = recording.verify(&result).unwrap_err();
+        assert_eq!(desync.expected_hash, summary.final_hash());
+        assert_ne!(desync.actual_hash, desync.expected_hash);
+        assert_eq!((desync.expected_ticks, desync.actual_ticks), (600, 600));
+    }
+
+    #[test]
+    fn replay_with_other_dt_desyncs() {
+        let (_, recording, _) = record_session(300);
+        let mut other = recording.clone();
+        other.dt = DT * 2;
+        let desync = recording.verify(&replay(&other, DT)).unwrap_err();
+        assert_eq!((desync.expected_dt, desync.actual_dt), (DT, DT * 2));
+        assert!(desync.to_string().contains("recorded with dt 10ms, replayed with dt 20ms"));
+    }
+
+    #[test]
+    fn parse_rejects_bad_recordings() {
+        let parse = |text: &str| Recording::<Command>::parse(text).unwrap_err().to_string();
+        assert_eq!(parse("dt 0\nend 0 0\n"), "line 1: invalid dt");
+        assert_eq!(parse("dt 10000\n3 jump\nend 5 0\n"), "line 2: unknown input");
+        assert_eq!(parse("dt 10000\n"), "line 0: missing end");
+        assert_eq!(parse("3 left\nend 5 0\n"), "line 1: missing dt");
+        assert_eq!(parse("dt 10000\n3 left\n3 right\n2 left\nend 5 0\n"), "line 4: tick goes backwards");
+    }
+
+    #[test]
+    #[should_panic(expected = "fixed timestep must be positive")]
+    fn zero_timestep_is_rejected() {
+        LoopConfig::new(Arc::new(ManualClock::new(Duration::ZERO)), Duration::ZERO);
+    }
+
+    #[test]
+    fn slow_frames_catch_up_with_several_ticks() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let config = LoopConfig::new(clock, DT).frame_interval(Duration::from_millis(35)).max_ticks(35);
+        let mut renderer = TextRenderer::new(1, 1, 1);
+        let summary = Pong::new(u32::MAX).run(&config, &mut |_| Vec::<Command>::new(), &mut renderer);
+        // 第一帧没有经过时间，之后每帧 35 毫秒，累积 3 或 4 个 tick
+        assert_eq!(summary.ticks, 35);
+        assert_eq!(summary.frames, 11);
+    }
+
+    /// 每三次 sleep 就往回拨 5 毫秒的时钟，例如系统时间被校正
+    struct SteppingBack {
+        clock: ManualClock,
+        sleeps: Mutex<u32>,
+    }
+
+    impl Clock for SteppingBack {
+        fn now(&self) -> Duration {
+            self.clock.now()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            let mut sleeps = self.sleeps.lock().unwrap();
+            *sleeps += 1;
+            if sleeps.is_multiple_of(3) {
+                self.clock.set(self.clock.now().saturating_sub(Duration::from_millis(5)));
+            } else {
+                self.clock.advance(duration);
+            }
+        }
+    }
+
+    #[test]
+    fn clock_going_backwards_adds_no_time() {
+        let clock = Arc::new(SteppingBack { clock: ManualClock::new(Duration::from_secs(1)), sleeps: Mutex::new(0) });
+        let config = LoopConfig::new(clock, DT).frame_interval(Duration::from_millis(20)).max_ticks(20);
+        let summary = Pong::new(u32::MAX).run(&config, &mut |_| Vec::<Command>::new(), &mut TextRenderer::new(1, 1, 1));
+        // 第一帧和往回拨之后的一帧不累积，其余每帧累积 2 个 tick
+        assert_eq!(summary.ticks, 20);
+        assert_eq!(summary.frames, 15);
+    }
+
+    #[test]
+    fn long_stall_is_capped_by_max_frame() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let config = LoopConfig::new(clock, DT)
+            .frame_interval(Duration::from_secs(10))
+            .max_frame(Duration::from_millis(50))
+            .max_ticks(20);
+        let summary = Pong::new(u32::MAX).run(&config, &mut |_| Vec::<Command>::new(), &mut TextRenderer::new(1, 1, 1));
+        // 每帧最多补 5 个 tick，而不是 1000 个
+        assert_eq!(summary.frames, 5);
+    }
+
+    #[test]
+    fn text_renderer_draws_the_last_frames() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let config = LoopConfig::new(clock, DT).frame_interval(DT).max_ticks(3);
+        let mut renderer = TextRenderer::new(WIDTH as usize, HEIGHT as usize + 2, 2);
+        Pong::new(u32::MAX).run(&config, &mut |_| Vec::<Command>::new(), &mut renderer);
+        assert_eq!(renderer.frames().len(), 2);
+        let frame = renderer.last_frame().unwrap();
+        let lines: Vec<&str> = frame.lines().collect();
+        assert_eq!(lines.len(), HEIGHT as usize + 2);
+        assert_eq!(lines[1], "  o");
+        assert_eq!(lines[HEIGHT as usize], "        =====");
+        assert_eq!(lines[HEIGHT as usize + 1], "caught 0 missed 0");
+    }
+
+    #[test]
+    fn text_renderer_clips_out_of_range_text() {
+        let mut renderer = TextRenderer::new(4, 2, 1);
+        renderer.begin_frame();
+        renderer.draw_text(-2, 0, "abcdef");
+        renderer.draw_text(3, 1, "xyz");
+        renderer.draw_text(0, 5, "hidden");
+        renderer.end_frame();
+        assert_eq!(renderer.last_frame(), Some("cdef\n   x"));
+    }
+
+    #[test]
+    fn game_ends_when_finished() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let mut game = Pong::new(1);
+        let summary = game.run(&LoopConfig::new(clock, DT), &mut |_| Vec::<Command>::new(), &mut TextRenderer::new(1, 1, 1));
+        assert_eq!(game.missed, 1);
+        assert_eq!(summary.hashes.len() as u64, summary.ticks);
+        assert!(summary.ticks < 1_500);
+    }
+}
@@ -0,0 +1,451 @@
+//! 图形上的访问者
+//! creational::prototype::Circle、structural::adapter::LegacyRectangle 和 structural::bridge::Circle
+//! 是三个互不相关的类型，没有任何共同的操作。
+//! 这里把它们转换成统一的 Shape（圆、矩形、多边形），Shape 只提供 accept，
+//! 面积与周长、包围盒、点击测试、SVG 和 JSON 导出都写成访问者，
+//! 增加新的操作不需要修改任何图形类型。
+//! 坐标系与 SVG 一致：x 向右，y 向下，矩形的 (x, y) 为左上角。
+//! 宽或高为负的矩形从 (x, y) 向反方向延伸，面积、包围盒、点击测试和 SVG 都按规范化后的矩形计算。
+
+use std::fmt;
+
+use crate::behavioral::escape::markup;
+use crate::creational::prototype;
+use crate::structural::adapter::LegacyRectangle;
+use crate::structural::bridge::{self, OpenGLRenderer};
+
+//-----------------图形---------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+
+    fn distance(&self, other: &Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rectangle {
+    pub origin: Point,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rectangle {
+    /// 宽高为非负数的同一个矩形
+    pub fn normalized(&self) -> Rectangle {
+        Rectangle {
+            origin: Point::new(self.origin.x.min(self.origin.x + self.width), self.origin.y.min(self.origin.y + self.height)),
+            width: self.width.abs(),
+            height: self.height.abs(),
+        }
+    }
+}
+
+/// 多边形，顶点按顺序首尾相连
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub points: Vec<Point>,
+}
+
+/// 图形
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Circle(Circle),
+    Rectangle(Rectangle),
+    Polygon(Polygon),
+}
+
+impl Shape {
+    pub fn circle(x: f64, y: f64, radius: f64) -> Shape {
+        Shape::Circle(Circle { center: Point::new(x, y), radius })
+    }
+
+    pub fn rectangle(x: f64, y: f64, width: f64, height: f64) -> Shape {
+        Shape::Rectangle(Rectangle { origin: Point::new(x, y), width, height })
+    }
+
+    pub fn polygon(points: &[(f64, f64)]) -> Shape {
+        Shape::Polygon(Polygon { points: points.iter().map(|&(x, y)| Point::new(x, y)).collect() })
+    }
+
+    /// 把自己交给访问者对应的 visit 方法
+    pub fn accept<V: ShapeVisitor>(&self, visitor: &mut V) -> V::Output {
+        match self {
+            Shape::Circle(circle) => visitor.visit_circle(circle),
+            Shape::Rectangle(rectangle) => visitor.visit_rectangle(rectangle),
+            Shape::Polygon(polygon) => visitor.visit_polygon(polygon),
+        }
+    }
+}
+
+/// 图形访问者
+pub trait ShapeVisitor {
+    type Output;
+
+    fn visit_circle(&mut self, circle: &Circle) -> Self::Output;
+    fn visit_rectangle(&mut self, rectangle: &Rectangle) -> Self::Output;
+    fn visit_polygon(&mut self, polygon: &Polygon) -> Self::Output;
+}
+
+//-----------------从已有类型转换---------------------------------------------------------------------
+impl From<&prototype::Circle> for Shape {
+    fn from(circle: &prototype::Circle) -> Self {
+        Shape::circle(circle.x as f64, circle.y as f64, circle.radius as f64)
+    }
+}
+
+impl From<&LegacyRectangle> for Shape {
+    fn from(rect: &LegacyRectangle) -> Self {
+        Shape::rectangle(rect.x as f64, rect.y as f64, rect.width as f64, rect.height as f64)
+    }
+}
+
+/// bridge::Circle 没有位置信息，放在原点
+impl From<&bridge::Circle> for Shape {
+    fn from(circle: &bridge::Circle) -> Self {
+        Shape::circle(0.0, 0.0, circle.radius())
+    }
+}
+
+//-----------------面积与周长---------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurements {
+    pub area: f64,
+    pub perimeter: f64,
+}
+
+/// 计算面积与周长；多边形的面积用鞋带公式
+pub struct Measure;
+
+impl ShapeVisitor for Measure {
+    type Output = Measurements;
+
+    fn visit_circle(&mut self, circle: &Circle) -> Measurements {
+        Measurements {
+            area: std::f64::consts::PI * circle.radius * circle.radius,
+            perimeter: 2.0 * std::f64::consts::PI * circle.radius,
+        }
+    }
+
+    fn visit_rectangle(&mut self, rect: &Rectangle) -> Measurements {
+        let rect = rect.normalized();
+        Measurements { area: rect.width * rect.height, perimeter: 2.0 * (rect.width + rect.height) }
+    }
+
+    fn visit_polygon(&mut self, polygon: &Polygon) -> Measurements {
+        let edges = polygon.points.iter().zip(polygon.points.iter().cycle().skip(1));
+        let (mut twice_area, mut perimeter) = (0.0, 0.0);
+        for (a, b) in edges {
+            twice_area += a.x * b.y - b.x * a.y;
+            perimeter += a.distance(b);
+        }
+        Measurements { area: twice_area.abs() / 2.0, perimeter }
+    }
+}
+
+//-----------------包围盒---------------------------------------------------------------------
+/// 轴对齐包围盒
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    /// 同时包含两个包围盒的最小包围盒
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min: Point::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+}
+
+/// 计算包围盒；空多边形返回 None
+pub struct BoundingBox;
+
+impl ShapeVisitor for BoundingBox {
+    type Output = Option<Bounds>;
+
+    fn visit_circle(&mut self, c: &Circle) -> Option<Bounds> {
+        Some(Bounds {
+            min: Point::new(c.center.x - c.radius, c.center.y - c.radius),
+            max: Point::new(c.center.x + c.radius, c.center.y + c.radius),
+        })
+    }
+
+    fn visit_rectangle(&mut self, r: &Rectangle) -> Option<Bounds> {
+        let r = r.normalized();
+        Some(Bounds { min: r.origin, max: Point::new(r.origin.x + r.width, r.origin.y + r.height) })
+    }
+
+    fn visit_polygon(&mut self, polygon: &Polygon) -> Option<Bounds> {
+        polygon
+            .points
+            .iter()
+            .map(|p| Bounds { min: *p, max: *p })
+            .reduce(|a, b| a.union(&b))
+    }
+}
+
+//-----------------点击测试---------------------------------------------------------------------
+/// 判断点是否落在图形内（含边界）
+pub struct HitTest {
+    pub point: Point,
+}
+
+impl ShapeVisitor for HitTest {
+    type Output = bool;
+
+    fn visit_circle(&mut self, circle: &Circle) -> bool {
+        self.point.distance(&circle.center) <= circle.radius
+    }
+
+    fn visit_rectangle(&mut self, r: &Rectangle) -> bool {
+        let (p, r) = (self.point, r.normalized());
+        p.x >= r.origin.x && p.x <= r.origin.x + r.width && p.y >= r.origin.y && p.y <= r.origin.y + r.height
+    }
+
+    /// 射线法：从点向右的射线与边相交奇数次则在内部
+    fn visit_polygon(&mut self, polygon: &Polygon) -> bool {
+        let p = self.point;
+        let mut inside = false;
+        for (a, b) in polygon.points.iter().zip(polygon.points.iter().cycle().skip(1)) {
+            if (a.y > p.y) != (b.y > p.y) {
+                let x = a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y);
+                if p.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+}
+
+//-----------------SVG 与 JSON---------------------------------------------------------------------
+/// 每个图形输出一个 SVG 元素；fill 原样保存，输出时转义
+pub struct SvgWriter {
+    pub fill: String,
+}
+
+impl ShapeVisitor for SvgWriter {
+    type Output = String;
+
+    fn visit_circle(&mut self, c: &Circle) -> String {
+        format!(r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#, c.center.x, c.center.y, c.radius, markup(&self.fill))
+    }
+
+    fn visit_rectangle(&mut self, r: &Rectangle) -> String {
+        let r = r.normalized();
+        format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            r.origin.x, r.origin.y, r.width, r.height, markup(&self.fill)
+        )
+    }
+
+    fn visit_polygon(&mut self, polygon: &Polygon) -> String {
+        let points: Vec<String> = polygon.points.iter().map(|p| format!("{},{}", p.x, p.y)).collect();
+        format!(r#"<polygon points="{}" fill="{}"/>"#, points.join(" "), markup(&self.fill))
+    }
+}
+
+/// 输出完整的 SVG 文档，viewBox 取所有图形的包围盒
+pub fn to_svg(shapes: &[Shape], fill: &str) -> String {
+    let bounds = shapes
+        .iter()
+        .filter_map(|shape| shape.accept(&mut BoundingBox))
+        .reduce(|a, b| a.union(&b))
+        .unwrap_or(Bounds { min: Point::new(0.0, 0.0), max: Point::new(0.0, 0.0) });
+    let mut writer = SvgWriter { fill: fill.to_string() };
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        bounds.min.x,
+        bounds.min.y,
+        bounds.width(),
+        bounds.height()
+    );
+    for shape in shapes {
+        out.push_str(&format!("  {}\n", shape.accept(&mut writer)));
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// JSON 数字；NaN 和无穷大在 JSON 中没有表示，写为 null
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// 每个图形输出一个 JSON 对象
+pub struct JsonWriter;
+
+impl ShapeVisitor for JsonWriter {
+    type Output = String;
+
+    fn visit_circle(&mut self, c: &Circle) -> String {
+        format!(
+            r#"{{"type":"circle","cx":{},"cy":{},"r":{}}}"#,
+            json_number(c.center.x),
+            json_number(c.center.y),
+            json_number(c.radius)
+        )
+    }
+
+    fn visit_rectangle(&mut self, r: &Rectangle) -> String {
+        format!(
+            r#"{{"type":"rectangle","x":{},"y":{},"width":{},"height":{}}}"#,
+            json_number(r.origin.x),
+            json_number(r.origin.y),
+            json_number(r.width),
+            json_number(r.height)
+        )
+    }
+
+    fn visit_polygon(&mut self, polygon: &Polygon) -> String {
+        let points: Vec<String> = polygon.points.iter().map(|p| format!("[{},{}]", json_number(p.x), json_number(p.y))).collect();
+        format!(r#"{{"type":"polygon","points":[{}]}}"#, points.join(","))
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.accept(&mut JsonWriter))
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+pub fn main() {
+    let shapes = vec![
+        Shape::from(&prototype::Circle { x: 10, y: 20, radius: 5 }),
+        Shape::from(&LegacyRectangle { x: 10, y: 20, width: 100, height: 50 }),
+        Shape::from(&bridge::Circle::new(Box::new(OpenGLRenderer), 2.0)),
+        Shape::polygon(&[(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)]),
+    ];
+
+    for shape in &shapes {
+        let m = shape.accept(&mut Measure);
+        println!("{} area={:.2} perimeter={:.2}", shape, m.area, m.perimeter);
+    }
+    println!("bounds of {}: {:?}", shapes[0], shapes[0].accept(&mut BoundingBox));
+
+    // 点击测试：找出包含 (12, 22) 的图形
+    let mut hit = HitTest { point: Point::new(12.0, 22.0) };
+    let hits: Vec<usize> = (0..shapes.len()).filter(|&i| shapes[i].accept(&mut hit)).collect();
+    println!("shapes containing (12, 22): {:?}", hits);
+
+    print!("{}", to_svg(&shapes, "none"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg_fill_is_escaped() {
+        let mut writer = SvgWriter { fill: r#"red" onload="alert('x')"#.to_string() };
+        let svg = Shape::circle(1.0, 2.0, 3.0).accept(&mut writer);
+        assert_eq!(svg, r#"<circle cx="1" cy="2" r="3" fill="red&quot; onload=&quot;alert(&#39;x&#39;)"/>"#);
+        let document = to_svg(&[Shape::rectangle(0.0, 0.0, 1.0, 1.0), Shape::polygon(&[(0.0, 0.0), (1.0, 1.0)])], "<&>");
+        assert_eq!(document.matches(r#"fill="&lt;&amp;&gt;""#).count(), 2);
+    }
+
+    #[test]
+    fn json_writes_non_finite_numbers_as_null() {
+        assert_eq!(Shape::circle(f64::NAN, 1.5, f64::INFINITY).to_string(), r#"{"type":"circle","cx":null,"cy":1.5,"r":null}"#);
+        assert_eq!(
+            Shape::rectangle(0.0, f64::NEG_INFINITY, 2.0, 3.0).to_string(),
+            r#"{"type":"rectangle","x":0,"y":null,"width":2,"height":3}"#
+        );
+        assert_eq!(Shape::polygon(&[(0.0, f64::NAN)]).to_string(), r#"{"type":"polygon","points":[[0,null]]}"#);
+    }
+
+    #[test]
+    fn measure_circle_rectangle_and_polygon() {
+        let circle = Shape::circle(0.0, 0.0, 2.0).accept(&mut Measure);
+        assert!((circle.area - 4.0 * std::f64::consts::PI).abs() < 1e-9);
+        assert!((circle.perimeter - 4.0 * std::f64::consts::PI).abs() < 1e-9);
+        assert_eq!(Shape::rectangle(1.0, 1.0, 4.0, 3.0).accept(&mut Measure), Measurements { area: 12.0, perimeter: 14.0 });
+        // 顶点顺序不影响面积
+        let triangle = Measurements { area: 6.0, perimeter: 12.0 };
+        assert_eq!(Shape::polygon(&[(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)]).accept(&mut Measure), triangle);
+        assert_eq!(Shape::polygon(&[(0.0, 3.0), (4.0, 0.0), (0.0, 0.0)]).accept(&mut Measure), triangle);
+        assert_eq!(Shape::polygon(&[]).accept(&mut Measure), Measurements { area: 0.0, perimeter: 0.0 });
+    }
+
+    #[test]
+    fn bounding_boxes() {
+        let bounds = |shape: Shape| shape.accept(&mut BoundingBox);
+        assert_eq!(bounds(Shape::circle(10.0, 20.0, 5.0)), Some(Bounds { min: Point::new(5.0, 15.0), max: Point::new(15.0, 25.0) }));
+        assert_eq!(bounds(Shape::rectangle(1.0, 2.0, 3.0, 4.0)), Some(Bounds { min: Point::new(1.0, 2.0), max: Point::new(4.0, 6.0) }));
+        assert_eq!(
+            bounds(Shape::polygon(&[(2.0, -1.0), (-3.0, 4.0), (0.0, 0.0)])),
+            Some(Bounds { min: Point::new(-3.0, -1.0), max: Point::new(2.0, 4.0) })
+        );
+        assert_eq!(bounds(Shape::polygon(&[])), None);
+    }
+
+    #[test]
+    fn hit_test_includes_edges() {
+        let mut hit = HitTest { point: Point::new(3.0, 0.0) };
+        assert!(Shape::circle(0.0, 0.0, 3.0).accept(&mut hit));
+        assert!(Shape::rectangle(1.0, 0.0, 2.0, 1.0).accept(&mut hit));
+        hit.point = Point::new(3.1, 0.0);
+        assert!(!Shape::circle(0.0, 0.0, 3.0).accept(&mut hit));
+        assert!(!Shape::rectangle(1.0, 0.0, 2.0, 1.0).accept(&mut hit));
+
+        let triangle = Shape::polygon(&[(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)]);
+        hit.point = Point::new(1.0, 1.0);
+        assert!(triangle.accept(&mut hit));
+        hit.point = Point::new(3.0, 2.0);
+        assert!(!triangle.accept(&mut hit));
+        hit.point = Point::new(-1.0, 1.0);
+        assert!(!triangle.accept(&mut hit));
+    }
+
+    #[test]
+    fn negative_size_rectangles_are_normalized() {
+        // 从 (4, 6) 向左上延伸，与 (1, 2) 3x4 的矩形是同一个
+        let negative = Shape::rectangle(4.0, 6.0, -3.0, -4.0);
+        let positive = Shape::rectangle(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(negative.accept(&mut Measure), Measurements { area: 12.0, perimeter: 14.0 });
+        assert_eq!(negative.accept(&mut BoundingBox), positive.accept(&mut BoundingBox));
+
+        let mut hit = HitTest { point: Point::new(2.0, 3.0) };
+        assert!(negative.accept(&mut hit));
+        hit.point = Point::new(5.0, 3.0);
+        assert!(!negative.accept(&mut hit));
+
+        // 只有一个方向为负
+        let flipped = Shape::rectangle(4.0, 2.0, -3.0, 4.0);
+        assert_eq!(flipped.accept(&mut BoundingBox), positive.accept(&mut BoundingBox));
+
+        let mut writer = SvgWriter { fill: "none".to_string() };
+        assert_eq!(negative.accept(&mut writer), r#"<rect x="1" y="2" width="3" height="4" fill="none"/>"#);
+    }
+}
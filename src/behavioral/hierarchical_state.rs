@@ -0,0 +1,520 @@
+//! 分层状态机（UML 风格的嵌套状态）
+//! 状态就是 behavioral::state 的通用状态 trait State 的对象，由状态机持有 `Box<dyn State<E, C>>`；
+//! 投票机的 NotVoted、Voted、Locked 也实现了 State，可以直接放进分层状态机（见 voting_machine）。
+//! 与 state 不同的是状态可以嵌套：
+//!     子状态不处理的事件交给父状态处理；
+//!     跨层级迁移时，先从当前叶子状态逐层退出到公共祖先，再逐层进入目标状态，进入/退出动作按这个顺序执行；
+//!     复合状态可以带浅历史或深历史，重新进入时回到上次离开时的直接子状态或最深的叶子状态。
+//! 示例：媒体播放器，Playing/Paused 嵌套在 PoweredOn 中，关机再开机后回到关机前的状态。
+
+use std::fmt;
+
+use crate::behavioral::state::{Locked, NotVoted, VoteOperation, Voted};
+pub use crate::behavioral::state::{Response, State};
+
+/// 复合状态的历史类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum History {
+    /// 没有历史，总是进入初始子状态
+    None,
+    /// 浅历史：回到上次活动的直接子状态
+    Shallow,
+    /// 深历史：回到上次活动的叶子状态
+    Deep,
+}
+
+/// 状态树中的一个节点
+struct Node<E, C> {
+    state: Box<dyn State<E, C>>,
+    parent: Option<usize>,
+    /// 初始子状态，叶子状态为 None
+    initial: Option<usize>,
+    history: History,
+    /// 上次离开时活动的直接子状态
+    last_child: Option<usize>,
+    /// 上次离开时活动的叶子状态
+    last_leaf: Option<usize>,
+}
+
+/// 构建分层状态机时的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum HsmError {
+    /// 引用了不存在的状态
+    UnknownState(String),
+    /// 状态名称重复
+    DuplicateState(String),
+    /// 复合状态没有指定初始子状态
+    MissingInitial(String),
+}
+
+impl fmt::Display for HsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HsmError::UnknownState(name) => write!(f, "unknown state {:?}", name),
+            HsmError::DuplicateState(name) => write!(f, "duplicate state {:?}", name),
+            HsmError::MissingInitial(name) => write!(f, "composite state {:?} has no initial substate", name),
+        }
+    }
+}
+
+impl std::error::Error for HsmError {}
+
+//-----------------构建器---------------------------------------------------------------------
+/// 待添加的状态及其父状态名称
+type StateEntry<E, C> = (Box<dyn State<E, C>>, Option<&'static str>);
+
+/// 分层状态机构建器
+pub struct HsmBuilder<E, C> {
+    states: Vec<StateEntry<E, C>>,
+    initials: Vec<(&'static str, &'static str)>,
+    histories: Vec<(&'static str, History)>,
+}
+
+impl<E, C> Default for HsmBuilder<E, C> {
+    fn default() -> Self {
+        HsmBuilder { states: Vec::new(), initials: Vec::new(), histories: Vec::new() }
+    }
+}
+
+impl<E, C> HsmBuilder<E, C> {
+    pub fn new() -> Self {
+        HsmBuilder::default()
+    }
+
+    /// 添加状态，parent 为 None 表示顶层状态
+    pub fn state(mut self, state: Box<dyn State<E, C>>, parent: Option<&'static str>) -> Self {
+        self.states.push((state, parent));
+        self
+    }
+
+    /// 指定复合状态的初始子状态
+    pub fn initial(mut self, parent: &'static str, child: &'static str) -> Self {
+        self.initials.push((parent, child));
+        self
+    }
+
+    /// 指定复合状态的历史类型
+    pub fn history(mut self, state: &'static str, history: History) -> Self {
+        self.histories.push((state, history));
+        self
+    }
+
+    /// 构建状态机并进入 initial 状态
+    pub fn build(self, initial: &'static str, context: C) -> Result<HierarchicalMachine<E, C>, HsmError> {
+        let names: Vec<&'static str> = self.states.iter().map(|(state, _)| state.name()).collect();
+        let index_of = |name: &str| {
+            names.iter().position(|n| *n == name).ok_or_else(|| HsmError::UnknownState(name.to_string()))
+        };
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(HsmError::DuplicateState(name.to_string()));
+            }
+        }
+
+        let mut nodes = Vec::new();
+        for (state, parent) in self.states {
+            let parent = parent.map(index_of).transpose()?;
+            nodes.push(Node { state, parent, initial: None, history: History::None, last_child: None, last_leaf: None });
+        }
+        for (parent, child) in self.initials {
+            let (parent, child) = (index_of(parent)?, index_of(child)?);
+            nodes[parent].initial = Some(child);
+        }
+        for (state, history) in self.histories {
+            nodes[index_of(state)?].history = history;
+        }
+        for (i, node) in nodes.iter().enumerate() {
+            let has_children = nodes.iter().any(|n| n.parent == Some(i));
+            if has_children && node.initial.is_none() {
+                return Err(HsmError::MissingInitial(names[i].to_string()));
+            }
+        }
+
+        let target = index_of(initial)?;
+        let mut machine = HierarchicalMachine { nodes, active: target, context };
+        // 从根逐层进入初始状态，再进入它的默认子状态
+        let mut path = machine.ancestors(target);
+        path.reverse();
+        for node in path {
+            machine.nodes[node].state.on_entry(&mut machine.context);
+        }
+        machine.descend(target);
+        Ok(machine)
+    }
+}
+
+//-----------------状态机---------------------------------------------------------------------
+/// 分层状态机
+pub struct HierarchicalMachine<E, C> {
+    nodes: Vec<Node<E, C>>,
+    /// 当前活动的叶子状态
+    active: usize,
+    context: C,
+}
+
+impl<E, C> HierarchicalMachine<E, C> {
+    /// node 及其所有祖先，从 node 到根
+    fn ancestors(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        let mut current = node;
+        while let Some(parent) = self.nodes[current].parent {
+            path.push(parent);
+            current = parent;
+        }
+        path
+    }
+
+    /// 当前活动的叶子状态名称
+    pub fn state(&self) -> &'static str {
+        self.nodes[self.active].state.name()
+    }
+
+    /// 当前活动的全部状态，从根到叶子
+    pub fn configuration(&self) -> Vec<&'static str> {
+        self.ancestors(self.active).into_iter().rev().map(|node| self.nodes[node].state.name()).collect()
+    }
+
+    /// 某个状态当前是否活动（包括作为祖先活动）
+    pub fn is_in(&self, name: &str) -> bool {
+        self.configuration().contains(&name)
+    }
+
+    pub fn context(&self) -> &C {
+        &self.context
+    }
+
+    /// 分发事件：从叶子状态开始，子状态忽略的事件逐层交给父状态；返回事件是否被处理
+    pub fn dispatch(&mut self, event: &E) -> Result<bool, HsmError> {
+        let mut current = Some(self.active);
+        while let Some(node) = current {
+            match self.nodes[node].state.handle(event, &mut self.context) {
+                Response::Handled => return Ok(true),
+                Response::Transition(target) => {
+                    let target = self
+                        .nodes
+                        .iter()
+                        .position(|n| n.state.name() == target)
+                        .ok_or_else(|| HsmError::UnknownState(target.to_string()))?;
+                    self.transition(node, target);
+                    return Ok(true);
+                }
+                Response::Ignored => current = self.nodes[node].parent,
+            }
+        }
+        Ok(false)
+    }
+
+    /// 从 source 迁移到 target
+    fn transition(&mut self, source: usize, target: usize) {
+        let source_path = self.ancestors(source);
+        let target_path = self.ancestors(target);
+        // 迁移到自身或祖先时需要退出并重新进入目标，公共祖先取目标的父状态；
+        // 迁移到后代时不退出 source
+        let lca = if source_path.contains(&target) {
+            self.nodes[target].parent
+        } else {
+            target_path.iter().copied().find(|node| source_path.contains(node))
+        };
+
+        // 从叶子逐层退出到公共祖先，沿途记录历史
+        let leaf = self.active;
+        let mut current = Some(leaf);
+        while current != lca {
+            let Some(node) = current else { break };
+            if node != leaf {
+                self.nodes[node].last_leaf = Some(leaf);
+            }
+            if let Some(parent) = self.nodes[node].parent {
+                self.nodes[parent].last_child = Some(node);
+            }
+            self.nodes[node].state.on_exit(&mut self.context);
+            current = self.nodes[node].parent;
+        }
+
+        // 从公共祖先逐层进入目标
+        let enter: Vec<usize> = target_path.into_iter().take_while(|node| Some(*node) != lca).collect();
+        for node in enter.into_iter().rev() {
+            self.nodes[node].state.on_entry(&mut self.context);
+        }
+        self.descend(target);
+    }
+
+    /// 进入 node 之后，按历史或初始子状态逐层进入直到叶子
+    fn descend(&mut self, node: usize) {
+        let mut current = node;
+        loop {
+            let next = match (self.nodes[current].history, self.nodes[current].last_child, self.nodes[current].last_leaf) {
+                (History::Deep, _, Some(leaf)) => {
+                    // 深历史：直接进入从 current 到上次叶子之间的所有状态
+                    let path: Vec<usize> = self.ancestors(leaf).into_iter().take_while(|n| *n != current).collect();
+                    for n in path.into_iter().rev() {
+                        self.nodes[n].state.on_entry(&mut self.context);
+                    }
+                    self.active = leaf;
+                    return;
+                }
+                (History::Shallow, Some(child), _) | (History::Deep, Some(child), None) => Some(child),
+                _ => self.nodes[current].initial,
+            };
+            match next {
+                Some(child) => {
+                    self.nodes[child].state.on_entry(&mut self.context);
+                    current = child;
+                }
+                None => {
+                    self.active = current;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+//-----------------示例：媒体播放器---------------------------------------------------------------------
+/// 播放器事件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerEvent {
+    Power,
+    Play,
+    Pause,
+    Stop,
+    FastForward,
+}
+
+/// 播放器上下文：记录进入/退出顺序
+#[derive(Debug, Default)]
+pub struct PlayerContext {
+    pub trace: Vec<String>,
+}
+
+/// 播放器的状态：事件到目标状态的表，外加进入/退出日志
+struct PlayerState {
+    name: &'static str,
+    on: Vec<(PlayerEvent, &'static str)>,
+}
+
+impl PlayerState {
+    fn boxed(name: &'static str, on: Vec<(PlayerEvent, &'static str)>) -> Box<Self> {
+        Box::new(PlayerState { name, on })
+    }
+}
+
+impl State<PlayerEvent, PlayerContext> for PlayerState {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn on_entry(&mut self, context: &mut PlayerContext) {
+        context.trace.push(format!("enter {}", self.name));
+    }
+
+    fn on_exit(&mut self, context: &mut PlayerContext) {
+        context.trace.push(format!("exit {}", self.name));
+    }
+
+    fn handle(&mut self, event: &PlayerEvent, _: &mut PlayerContext) -> Response {
+        match self.on.iter().find(|(e, _)| e == event) {
+            Some((_, target)) => Response::Transition(target),
+            None => Response::Ignored,
+        }
+    }
+}
+
+/// 媒体播放器
+/// PoweredOff
+/// PoweredOn（深历史）
+///     Stopped
+///     Active
+///         Playing
+///         Paused
+///         FastForwarding
+pub fn media_player() -> HierarchicalMachine<PlayerEvent, PlayerContext> {
+    media_player_with(History::Deep)
+}
+
+fn media_player_with(history: History) -> HierarchicalMachine<PlayerEvent, PlayerContext> {
+    use PlayerEvent::*;
+    HsmBuilder::new()
+        .state(PlayerState::boxed("PoweredOff", vec![(Power, "PoweredOn")]), None)
+        // 不管处在哪个子状态，关机都由 PoweredOn 处理
+        .state(PlayerState::boxed("PoweredOn", vec![(Power, "PoweredOff")]), None)
+        .state(PlayerState::boxed("Stopped", vec![(Play, "Playing")]), Some("PoweredOn"))
+        .state(PlayerState::boxed("Active", vec![(Stop, "Stopped")]), Some("PoweredOn"))
+        .state(PlayerState::boxed("Playing", vec![(Pause, "Paused"), (FastForward, "FastForwarding")]), Some("Active"))
+        .state(PlayerState::boxed("Paused", vec![(Play, "Playing")]), Some("Active"))
+        .state(PlayerState::boxed("FastForwarding", vec![(Play, "Playing")]), Some("Active"))
+        .initial("PoweredOn", "Stopped")
+        .initial("Active", "Playing")
+        .history("PoweredOn", history)
+        .build("PoweredOff", PlayerContext::default())
+        .expect("media player definition is valid")
+}
+
+//-----------------示例：分层投票机---------------------------------------------------------------------
+/// 投票进行中的复合状态，本身不处理任何操作
+struct Open;
+
+impl State<VoteOperation, bool> for Open {
+    fn name(&self) -> &'static str {
+        "Open"
+    }
+
+    fn handle(&mut self, _: &VoteOperation, _: &mut bool) -> Response {
+        Response::Ignored
+    }
+}
+
+/// 投票机的分层版本，状态对象直接来自 behavioral::state，上下文记录是否已经投过票
+/// Open
+///     NotVoted
+///     Voted
+/// Locked
+pub fn voting_machine() -> HierarchicalMachine<VoteOperation, bool> {
+    HsmBuilder::new()
+        .state(Box::new(Open), None)
+        .state(Box::new(NotVoted), Some("Open"))
+        .state(Box::new(Voted), Some("Open"))
+        .state(Box::new(Locked), None)
+        .initial("Open", "NotVoted")
+        .build("Open", false)
+        .expect("voting machine definition is valid")
+}
+
+//--------------------------------------------------------------------------------------------------
+pub fn main() {
+    let mut player = media_player();
+    for event in [PlayerEvent::Power, PlayerEvent::Play, PlayerEvent::Pause] {
+        player.dispatch(&event).unwrap();
+        println!("{:?} -> {:?}", event, player.configuration());
+    }
+
+    // Paused 不处理 Power，交给 PoweredOn：依次退出 Paused、Active、PoweredOn
+    player.dispatch(&PlayerEvent::Power).unwrap();
+    println!("Power -> {:?}", player.configuration());
+
+    // 再次开机，深历史回到 Paused
+    player.dispatch(&PlayerEvent::Power).unwrap();
+    println!("Power -> {:?}", player.configuration());
+
+    // Stopped 中不处理 Pause，一路冒泡到顶层也没人处理
+    player.dispatch(&PlayerEvent::Stop).unwrap();
+    println!("Pause handled: {}", player.dispatch(&PlayerEvent::Pause).unwrap());
+    println!("{:#?}", player.context().trace);
+
+    // 投票状态对象组成的分层状态机
+    let mut machine = voting_machine();
+    for operation in [VoteOperation::Vote, VoteOperation::Vote, VoteOperation::ClosePoll, VoteOperation::ReopenPoll] {
+        let handled = machine.dispatch(&operation).unwrap();
+        println!("{:?} handled: {} -> {:?}", operation, handled, machine.configuration());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavioral::state::{next_status, VoteStatus};
+
+    /// 派发事件，返回这次派发追加的进入/退出记录
+    fn step(player: &mut HierarchicalMachine<PlayerEvent, PlayerContext>, event: PlayerEvent) -> Vec<String> {
+        let before = player.context().trace.len();
+        assert!(player.dispatch(&event).unwrap(), "{:?} was not handled", event);
+        player.context().trace[before..].to_vec()
+    }
+
+    #[test]
+    fn transitions_exit_up_to_the_common_ancestor_and_enter_down_to_a_leaf() {
+        let mut player = media_player();
+        assert_eq!(player.context().trace, ["enter PoweredOff"]);
+        assert_eq!(step(&mut player, PlayerEvent::Power), ["exit PoweredOff", "enter PoweredOn", "enter Stopped"]);
+        // Stopped 与 Playing 的公共祖先是 PoweredOn，进入 Active 后再进入 Playing
+        assert_eq!(step(&mut player, PlayerEvent::Play), ["exit Stopped", "enter Active", "enter Playing"]);
+        assert_eq!(player.configuration(), ["PoweredOn", "Active", "Playing"]);
+        // 兄弟状态之间只退出和进入叶子
+        assert_eq!(step(&mut player, PlayerEvent::Pause), ["exit Playing", "enter Paused"]);
+        // Paused 不处理 Stop，由 Active 处理：从叶子退出到 Active 之外
+        assert_eq!(step(&mut player, PlayerEvent::Stop), ["exit Paused", "exit Active", "enter Stopped"]);
+        assert!(player.is_in("PoweredOn"));
+        assert!(!player.is_in("Active"));
+    }
+
+    #[test]
+    fn parents_handle_events_their_children_ignore() {
+        let mut player = media_player();
+        step(&mut player, PlayerEvent::Power);
+        step(&mut player, PlayerEvent::Play);
+        step(&mut player, PlayerEvent::FastForward);
+        assert_eq!(
+            step(&mut player, PlayerEvent::Power),
+            ["exit FastForwarding", "exit Active", "exit PoweredOn", "enter PoweredOff"]
+        );
+        // 没有任何状态处理的事件返回 false，状态不变
+        assert!(!player.dispatch(&PlayerEvent::Pause).unwrap());
+        assert_eq!(player.state(), "PoweredOff");
+    }
+
+    #[test]
+    fn deep_history_returns_to_the_last_leaf() {
+        let mut player = media_player();
+        step(&mut player, PlayerEvent::Power);
+        step(&mut player, PlayerEvent::Play);
+        step(&mut player, PlayerEvent::Pause);
+        step(&mut player, PlayerEvent::Power);
+        assert_eq!(step(&mut player, PlayerEvent::Power), ["exit PoweredOff", "enter PoweredOn", "enter Active", "enter Paused"]);
+        assert_eq!(player.state(), "Paused");
+    }
+
+    #[test]
+    fn shallow_history_returns_to_the_last_child_and_its_initial_state() {
+        let mut player = media_player_with(History::Shallow);
+        step(&mut player, PlayerEvent::Power);
+        step(&mut player, PlayerEvent::Play);
+        step(&mut player, PlayerEvent::Pause);
+        step(&mut player, PlayerEvent::Power);
+        // 回到 Active，但 Active 没有历史，进入它的初始子状态 Playing
+        assert_eq!(step(&mut player, PlayerEvent::Power), ["exit PoweredOff", "enter PoweredOn", "enter Active", "enter Playing"]);
+
+        let mut player = media_player_with(History::None);
+        step(&mut player, PlayerEvent::Power);
+        step(&mut player, PlayerEvent::Play);
+        step(&mut player, PlayerEvent::Power);
+        assert_eq!(step(&mut player, PlayerEvent::Power), ["exit PoweredOff", "enter PoweredOn", "enter Stopped"]);
+    }
+
+    #[test]
+    fn builder_rejects_invalid_definitions() {
+        let state = |name| PlayerState::boxed(name, Vec::new());
+        let built = HsmBuilder::new().state(state("A"), None).state(state("A"), None).build("A", PlayerContext::default());
+        assert_eq!(built.err(), Some(HsmError::DuplicateState("A".to_string())));
+        let built = HsmBuilder::new().state(state("A"), None).state(state("B"), Some("A")).build("A", PlayerContext::default());
+        assert_eq!(built.err(), Some(HsmError::MissingInitial("A".to_string())));
+        let built = HsmBuilder::new().state(state("A"), Some("Z")).build("A", PlayerContext::default());
+        assert_eq!(built.err(), Some(HsmError::UnknownState("Z".to_string())));
+
+        let mut machine = HsmBuilder::new()
+            .state(PlayerState::boxed("A", vec![(PlayerEvent::Play, "Nowhere")]), None)
+            .build("A", PlayerContext::default())
+            .unwrap();
+        assert_eq!(machine.dispatch(&PlayerEvent::Play), Err(HsmError::UnknownState("Nowhere".to_string())));
+    }
+
+    #[test]
+    fn voting_states_follow_the_shared_transition_model() {
+        use VoteOperation::*;
+        let mut machine = voting_machine();
+        assert_eq!(machine.configuration(), ["Open", "NotVoted"]);
+        let mut expected = VoteStatus::NotVoted;
+        let mut has_voted = false;
+        for operation in [Vote, Vote, ReopenPoll, ClosePoll, Vote, ReopenPoll, DeadlinePassed, DeadlinePassed] {
+            let next = next_status(expected, operation, has_voted);
+            // 状态对象不允许的操作冒泡到 Open，也没人处理
+            assert_eq!(machine.dispatch(&operation).unwrap(), next.is_ok(), "{:?} in {:?}", operation, expected);
+            if let Ok(next) = next {
+                has_voted |= operation == Vote;
+                expected = next;
+            }
+            assert_eq!(machine.state(), format!("{:?}", expected));
+            assert_eq!(*machine.context(), has_voted);
+        }
+        assert_eq!(machine.configuration(), ["Locked"]);
+    }
+}
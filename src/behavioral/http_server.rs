@@ -0,0 +1,875 @@
+//! 基于责任链的 HTTP/1.1 服务器
+//! 服务器只负责收发：在 std::net::TcpListener 上接收连接，交给有界线程池处理，支持 keep-alive。
+//! 日志、鉴权、路由、静态文件都是链上的处理者，每个处理者要么返回响应，要么交给下一个处理者；
+//! 整条链都没有处理的请求返回 404。
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// 请求行和单个请求头的最大长度
+const MAX_LINE: usize = 8 * 1024;
+/// 请求头的最大个数
+const MAX_HEADERS: usize = 100;
+/// 请求体的最大长度
+const MAX_BODY: usize = 1024 * 1024;
+
+//-----------------请求与响应---------------------------------------------------------------------
+/// HTTP 请求
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    /// 路径，不含查询字符串
+    pub path: String,
+    /// 查询字符串，不含 '?'
+    pub query: Option<String>,
+    /// 例如 "HTTP/1.1"
+    pub version: String,
+    /// 请求头，名称统一为小写
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// 获取请求头，名称不区分大小写
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers.iter().find(|(key, _)| *key == name).map(|(_, value)| value.as_str())
+    }
+
+    /// 处理完这个请求后是否保持连接
+    pub fn keep_alive(&self) -> bool {
+        let connection = self.header("connection").map(|value| value.to_ascii_lowercase());
+        match connection.as_deref() {
+            Some("close") => false,
+            Some("keep-alive") => true,
+            _ => self.version == "HTTP/1.1",
+        }
+    }
+}
+
+/// HTTP 响应
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// 创建指定状态码的空响应
+    pub fn new(status: u16) -> Self {
+        HttpResponse { status, headers: Vec::new(), body: Vec::new() }
+    }
+
+    /// 纯文本响应
+    pub fn text(status: u16, body: &str) -> Self {
+        HttpResponse::new(status)
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+            .with_body(body.as_bytes().to_vec())
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// 状态码对应的原因短语
+    pub fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            500 => "Internal Server Error",
+            501 => "Not Implemented",
+            503 => "Service Unavailable",
+            _ => "Unknown",
+        }
+    }
+
+    /// 序列化为报文，Content-Length 和 Connection 由服务器填写
+    fn to_bytes(&self, keep_alive: bool) -> Vec<u8> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, self.reason());
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        head.push_str(if keep_alive { "Connection: keep-alive\r\n\r\n" } else { "Connection: close\r\n\r\n" });
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+//-----------------处理者---------------------------------------------------------------------
+/// HTTP 处理者 trait
+/// 返回 Some 表示处理了请求；返回 None 表示交给下一个处理者也没人处理
+pub trait HttpHandler: Send + Sync {
+    fn set_next(&mut self, handler: Box<dyn HttpHandler>);
+    fn handle(&self, request: &HttpRequest) -> Option<HttpResponse>;
+}
+
+/// 把请求交给下一个处理者
+fn forward(next: &Option<Box<dyn HttpHandler>>, request: &HttpRequest) -> Option<HttpResponse> {
+    next.as_ref().and_then(|handler| handler.handle(request))
+}
+
+/// 日志处理者：记录每个请求的方法、路径和最终状态码
+pub struct LoggingHandler {
+    lines: Arc<Mutex<Vec<String>>>,
+    /// 每条日志另外写到这里，默认丢弃
+    writer: Mutex<Box<dyn Write + Send>>,
+    next_handler: Option<Box<dyn HttpHandler>>,
+}
+
+impl Default for LoggingHandler {
+    fn default() -> Self {
+        LoggingHandler {
+            lines: Arc::default(),
+            writer: Mutex::new(Box::new(io::sink())),
+            next_handler: None,
+        }
+    }
+}
+
+impl LoggingHandler {
+    pub fn new() -> Self {
+        LoggingHandler::default()
+    }
+
+    /// 把每条日志写到 writer，例如 io::stdout() 或文件
+    pub fn with_writer(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.writer = Mutex::new(Box::new(writer));
+        self
+    }
+
+    /// 共享的日志行，服务器运行期间也可以读取
+    pub fn lines(&self) -> Arc<Mutex<Vec<String>>> {
+        self.lines.clone()
+    }
+}
+
+impl HttpHandler for LoggingHandler {
+    fn set_next(&mut self, handler: Box<dyn HttpHandler>) {
+        self.next_handler = Some(handler);
+    }
+
+    fn handle(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        let response = forward(&self.next_handler, request);
+        let status = response.as_ref().map_or(404, |response| response.status);
+        let line = format!("{} {} -> {}", request.method, request.path, status);
+        // 日志写失败不影响响应
+        let _ = writeln!(self.writer.lock().unwrap(), "[http] {}", line);
+        self.lines.lock().unwrap().push(line);
+        response
+    }
+}
+
+/// 鉴权处理者：访问 prefix 下的路径需要携带 Bearer token
+pub struct AuthHandler {
+    prefix: String,
+    token: String,
+    next_handler: Option<Box<dyn HttpHandler>>,
+}
+
+impl AuthHandler {
+    pub fn new(prefix: &str, token: &str) -> Self {
+        AuthHandler {
+            prefix: prefix.to_string(),
+            token: token.to_string(),
+            next_handler: None,
+        }
+    }
+}
+
+impl HttpHandler for AuthHandler {
+    fn set_next(&mut self, handler: Box<dyn HttpHandler>) {
+        self.next_handler = Some(handler);
+    }
+
+    fn handle(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        if request.path.starts_with(&self.prefix) {
+            let expected = format!("Bearer {}", self.token);
+            if request.header("authorization") != Some(expected.as_str()) {
+                return Some(
+                    HttpResponse::text(401, "unauthorized\n").with_header("WWW-Authenticate", "Bearer"),
+                );
+            }
+        }
+        forward(&self.next_handler, request)
+    }
+}
+
+/// 路由函数
+type Route = Box<dyn Fn(&HttpRequest) -> HttpResponse + Send + Sync>;
+
+/// 路由处理者：按方法和路径精确匹配
+#[derive(Default)]
+pub struct Router {
+    routes: HashMap<(String, String), Route>,
+    next_handler: Option<Box<dyn HttpHandler>>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Router::default()
+    }
+
+    /// 注册路由
+    pub fn route(
+        mut self,
+        method: &str,
+        path: &str,
+        handler: impl Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    ) -> Self {
+        self.routes.insert((method.to_string(), path.to_string()), Box::new(handler));
+        self
+    }
+}
+
+impl HttpHandler for Router {
+    fn set_next(&mut self, handler: Box<dyn HttpHandler>) {
+        self.next_handler = Some(handler);
+    }
+
+    fn handle(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        match self.routes.get(&(request.method.clone(), request.path.clone())) {
+            Some(route) => Some(route(request)),
+            None => forward(&self.next_handler, request),
+        }
+    }
+}
+
+/// 静态文件处理者：把 prefix 下的 GET 请求映射到 root 目录中的文件
+pub struct StaticFiles {
+    prefix: String,
+    root: PathBuf,
+    next_handler: Option<Box<dyn HttpHandler>>,
+}
+
+impl StaticFiles {
+    pub fn new(prefix: &str, root: impl Into<PathBuf>) -> Self {
+        StaticFiles {
+            prefix: prefix.to_string(),
+            root: root.into(),
+            next_handler: None,
+        }
+    }
+
+    /// 请求路径对应的文件，拒绝 ".." 等跳出根目录的路径
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let relative = Path::new(path.strip_prefix(&self.prefix)?);
+        if relative.components().all(|component| matches!(component, Component::Normal(_))) {
+            Some(self.root.join(relative))
+        } else {
+            None
+        }
+    }
+}
+
+/// 按扩展名推断 Content-Type
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "application/javascript",
+        Some("json") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("png") => "image/png",
+        _ => "application/octet-stream",
+    }
+}
+
+impl HttpHandler for StaticFiles {
+    fn set_next(&mut self, handler: Box<dyn HttpHandler>) {
+        self.next_handler = Some(handler);
+    }
+
+    fn handle(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        if request.method == "GET" {
+            if let Some(file) = self.resolve(&request.path) {
+                if let Ok(body) = fs::read(&file) {
+                    return Some(HttpResponse::new(200).with_header("Content-Type", content_type(&file)).with_body(body));
+                }
+            }
+        }
+        forward(&self.next_handler, request)
+    }
+}
+
+//-----------------请求解析---------------------------------------------------------------------
+/// 读取一行（去掉 CRLF），超过 MAX_LINE 视为错误；连接关闭时返回 None
+fn read_line(reader: &mut BufReader<TcpStream>) -> io::Result<Option<String>> {
+    let mut line = Vec::new();
+    let read = reader.by_ref().take(MAX_LINE as u64 + 1).read_until(b'\n', &mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if line.len() > MAX_LINE || !line.ends_with(b"\n") {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    while line.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line).map(Some).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid utf-8"))
+}
+
+/// 从连接中读取一个请求；对端关闭连接时返回 Ok(None)
+fn read_request(reader: &mut BufReader<TcpStream>) -> io::Result<Option<HttpRequest>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    // 跳过请求之间多余的空行
+    let request_line = loop {
+        match read_line(reader)? {
+            None => return Ok(None),
+            Some(line) if line.is_empty() => continue,
+            Some(line) => break line,
+        }
+    };
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target), Some(version), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid("malformed request line"));
+    };
+    if version != "HTTP/1.1" && version != "HTTP/1.0" {
+        return Err(invalid("unsupported version"));
+    }
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), Some(query.to_string())),
+        None => (target.to_string(), None),
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?.ok_or_else(|| invalid("unexpected end of headers"))?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        let (name, value) = line.split_once(':').ok_or_else(|| invalid("malformed header"))?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    let mut request = HttpRequest {
+        method: method.to_string(),
+        path,
+        query,
+        version: version.to_string(),
+        headers,
+        body: Vec::new(),
+    };
+    // 不支持分块等传输编码：如果忽略这个头按 Content-Length 读取，前后两台服务器对请求边界的理解
+    // 可能不同，造成请求走私，所以直接拒绝
+    if request.header("transfer-encoding").is_some() {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "transfer-encoding not supported"));
+    }
+    // 多个 Content-Length 头同样可能让前后两台服务器读取不同的长度，重复或取值不一致都拒绝；
+    // 取值只能是十进制数字，不接受 "+5"、"5, 5" 这样的写法
+    let mut lengths = request.headers.iter().filter(|(name, _)| name == "content-length");
+    if let Some((_, length)) = lengths.next() {
+        if lengths.next().is_some() {
+            return Err(invalid("duplicate content-length"));
+        }
+        if length.is_empty() || !length.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid("invalid content-length"));
+        }
+        let length: usize = length.parse().map_err(|_| invalid("invalid content-length"))?;
+        if length > MAX_BODY {
+            return Err(invalid("body too large"));
+        }
+        request.body = vec![0; length];
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(Some(request))
+}
+
+/// 正在处理的连接，停止服务器时关闭它们的读端，唤醒阻塞在读取上的工作线程
+#[derive(Default)]
+struct OpenConnections {
+    next_id: AtomicU64,
+    streams: Mutex<HashMap<u64, TcpStream>>,
+}
+
+impl OpenConnections {
+    /// 登记连接；服务器已经在停止时返回 None
+    fn register(&self, stream: &TcpStream, stopping: &AtomicBool) -> Option<u64> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.streams.lock().unwrap().insert(id, stream.try_clone().ok()?);
+        // 先登记再检查标志：要么 close_all 能看到这个连接，要么这里能看到标志
+        if stopping.load(Ordering::SeqCst) {
+            self.remove(id);
+            return None;
+        }
+        Some(id)
+    }
+
+    fn remove(&self, id: u64) {
+        self.streams.lock().unwrap().remove(&id);
+    }
+
+    /// 只关闭读端：空闲连接立即读到 EOF，正在写的响应仍然能发完
+    fn close_all(&self) {
+        for stream in self.streams.lock().unwrap().values() {
+            let _ = stream.shutdown(Shutdown::Read);
+        }
+    }
+}
+
+/// 处理一个连接上的全部请求，直到对端关闭、请求不再 keep-alive、读取超时或服务器停止
+fn serve_connection(
+    stream: TcpStream,
+    chain: &dyn HttpHandler,
+    options: &ServerOptions,
+    stopping: &AtomicBool,
+    connections: &OpenConnections,
+) {
+    let Some(id) = connections.register(&stream, stopping) else { return };
+    serve_requests(stream, chain, options, stopping);
+    connections.remove(id);
+}
+
+fn serve_requests(stream: TcpStream, chain: &dyn HttpHandler, options: &ServerOptions, stopping: &AtomicBool) {
+    if stream.set_read_timeout(Some(options.keep_alive_timeout)).is_err() {
+        return;
+    }
+    let Ok(mut writer) = stream.try_clone() else { return };
+    let mut reader = BufReader::new(stream);
+
+    for served in 1.. {
+        let (response, keep_alive) = match read_request(&mut reader) {
+            Ok(None) => return,
+            Ok(Some(request)) => {
+                let keep_alive = request.keep_alive()
+                    && served < options.max_requests_per_connection
+                    && !stopping.load(Ordering::SeqCst);
+                let response = chain.handle(&request).unwrap_or_else(|| HttpResponse::text(404, "not found\n"));
+                (response, keep_alive)
+            }
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => return,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => (HttpResponse::text(400, "bad request\n"), false),
+            // 请求体没有读取，连接上剩下的字节无法分界，回复后必须关闭连接
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => (HttpResponse::text(501, "not implemented\n"), false),
+            Err(_) => return,
+        };
+        if writer.write_all(&response.to_bytes(keep_alive)).is_err() || !keep_alive {
+            return;
+        }
+    }
+}
+
+//-----------------有界线程池---------------------------------------------------------------------
+type Job = Box<dyn FnOnce() + Send>;
+
+/// 固定线程数、任务队列有界的线程池
+struct ThreadPool {
+    workers: Vec<JoinHandle<()>>,
+    sender: Option<SyncSender<Job>>,
+}
+
+impl ThreadPool {
+    fn new(size: usize, queue: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(queue);
+        let receiver: Arc<Mutex<Receiver<Job>>> = Arc::new(Mutex::new(receiver));
+        let workers = (0..size.max(1))
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || loop {
+                    // 取到任务后立即释放锁，再执行任务
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+        ThreadPool { workers, sender: Some(sender) }
+    }
+
+    /// 提交任务，队列已满时把任务原样退回
+    fn try_execute(&self, job: Job) -> Result<(), Job> {
+        match self.sender.as_ref() {
+            Some(sender) => sender.try_send(job).map_err(|e| match e {
+                TrySendError::Full(job) | TrySendError::Disconnected(job) => job,
+            }),
+            None => Err(job),
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        // 关闭队列，等待工作线程处理完手上的连接后退出
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+//-----------------服务器---------------------------------------------------------------------
+/// 服务器参数
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /// 工作线程数
+    pub workers: usize,
+    /// 等待处理的连接队列长度，队列满时直接返回 503
+    pub queue: usize,
+    /// keep-alive 连接空闲多久后关闭
+    pub keep_alive_timeout: Duration,
+    /// 一个连接上最多处理的请求数
+    pub max_requests_per_connection: usize,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        ServerOptions {
+            workers: 4,
+            queue: 64,
+            keep_alive_timeout: Duration::from_secs(5),
+            max_requests_per_connection: 100,
+        }
+    }
+}
+
+/// 运行中的服务器，drop 或调用 shutdown 时停止
+pub struct HttpServer {
+    addr: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    connections: Arc<OpenConnections>,
+    acceptor: Option<JoinHandle<()>>,
+}
+
+impl HttpServer {
+    /// 绑定地址并在后台线程中开始接收连接；端口为 0 时由系统分配
+    pub fn start(addr: impl ToSocketAddrs, chain: Box<dyn HttpHandler>, options: ServerOptions) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let stopping = Arc::new(AtomicBool::new(false));
+        let connections = Arc::new(OpenConnections::default());
+        let chain: Arc<dyn HttpHandler> = Arc::from(chain);
+
+        let (flag, open) = (stopping.clone(), connections.clone());
+        let acceptor = thread::spawn(move || {
+            let pool = ThreadPool::new(options.workers, options.queue);
+            let options = Arc::new(options);
+            for stream in listener.incoming() {
+                if flag.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let Ok(mut overflow) = stream.try_clone() else { continue };
+                let (chain, options, flag, open) = (chain.clone(), options.clone(), flag.clone(), open.clone());
+                let job: Job = Box::new(move || serve_connection(stream, chain.as_ref(), &options, &flag, &open));
+                if pool.try_execute(job).is_err() {
+                    // 队列已满：回复 503 后关闭连接
+                    let _ = overflow.write_all(&HttpResponse::text(503, "server busy\n").to_bytes(false));
+                }
+            }
+        });
+
+        Ok(HttpServer { addr, stopping, connections, acceptor: Some(acceptor) })
+    }
+
+    /// 实际监听的地址
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 停止接收新连接，关闭空闲的 keep-alive 连接，并等待正在处理的请求结束
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(acceptor) = self.acceptor.take() {
+            self.stopping.store(true, Ordering::SeqCst);
+            // 不等 keep-alive 超时：让阻塞在读取上的工作线程立即读到 EOF
+            self.connections.close_all();
+            // 连一下自己，唤醒阻塞在 accept 上的线程
+            let _ = TcpStream::connect(self.addr);
+            let _ = acceptor.join();
+        }
+    }
+}
+
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+/// 客户端收到的响应：(状态码, 响应头, 响应体)
+type RawResponse = (u16, Vec<(String, String)>, String);
+
+/// 在已有连接上发送一个请求并读取响应
+fn send(reader: &mut BufReader<TcpStream>, raw: &str) -> io::Result<RawResponse> {
+    reader.get_mut().write_all(raw.as_bytes())?;
+    let status_line = read_line(reader)?.unwrap_or_default();
+    let status = status_line.split_whitespace().nth(1).and_then(|code| code.parse().ok()).unwrap_or(0);
+    let mut headers = Vec::new();
+    while let Some(line) = read_line(reader)? {
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    let length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok((status, headers, String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// 示例用的责任链：日志（同时写到 writer）-> 鉴权 -> 路由 -> 静态文件，返回链头和日志
+fn demo_chain(root: &Path, writer: impl Write + Send + 'static) -> (Box<dyn HttpHandler>, Arc<Mutex<Vec<String>>>) {
+    let mut logging = LoggingHandler::new().with_writer(writer);
+    let log = logging.lines();
+    let mut auth = AuthHandler::new("/admin", "secret");
+    let mut router = Router::new()
+        .route("GET", "/", |_| HttpResponse::text(200, "index\n"))
+        .route("GET", "/admin/stats", |_| HttpResponse::text(200, "stats\n"))
+        .route("POST", "/echo", |request| HttpResponse::new(200).with_body(request.body.clone()));
+    router.set_next(Box::new(StaticFiles::new("/static/", root)));
+    auth.set_next(Box::new(router));
+    logging.set_next(Box::new(auth));
+    (Box::new(logging), log)
+}
+
+pub fn main() {
+    // 准备静态文件目录
+    let root = std::env::temp_dir().join(format!("http_chain_{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("hello.txt"), "hello from disk\n").unwrap();
+
+    let (chain, log) = demo_chain(&root, io::stdout());
+    let server = HttpServer::start("127.0.0.1:0", chain, ServerOptions::default()).unwrap();
+    println!("listening on {}", server.local_addr());
+
+    // 同一个 keep-alive 连接上发送多个请求
+    let mut conn = BufReader::new(TcpStream::connect(server.local_addr()).unwrap());
+    for raw in [
+        "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        "GET /admin/stats HTTP/1.1\r\n\r\n",
+        "GET /admin/stats HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n",
+        "POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello",
+        "GET /static/hello.txt HTTP/1.1\r\n\r\n",
+        "GET /missing HTTP/1.1\r\nConnection: close\r\n\r\n",
+    ] {
+        let (status, _, body) = send(&mut conn, raw).unwrap();
+        println!("{} {:?}", status, body);
+    }
+
+    server.shutdown();
+    println!("{:#?}", log.lock().unwrap());
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// 可以在测试里读取内容的日志输出
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// 在临时目录上启动示例服务器，测试结束时删除目录
+    struct Fixture {
+        server: Option<HttpServer>,
+        log: Arc<Mutex<Vec<String>>>,
+        output: SharedBuffer,
+        root: PathBuf,
+    }
+
+    impl Fixture {
+        fn start(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("http_chain_{}_{}", name, std::process::id()));
+            fs::create_dir_all(&root).unwrap();
+            fs::write(root.join("hello.txt"), "hello from disk\n").unwrap();
+            let output = SharedBuffer::default();
+            let (chain, log) = demo_chain(&root, output.clone());
+            let server = HttpServer::start("127.0.0.1:0", chain, ServerOptions::default()).unwrap();
+            Fixture { server: Some(server), log, output, root }
+        }
+
+        fn connect(&self) -> BufReader<TcpStream> {
+            let addr = self.server.as_ref().unwrap().local_addr();
+            BufReader::new(TcpStream::connect(addr).unwrap())
+        }
+
+        fn shutdown(&mut self) {
+            self.server.take().unwrap().shutdown();
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            self.server.take();
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn has_header(headers: &[(String, String)], name: &str, value: &str) -> bool {
+        headers.contains(&(name.to_string(), value.to_string()))
+    }
+
+    #[test]
+    fn keep_alive_connection_serves_many_requests() {
+        let fixture = Fixture::start("keep_alive");
+        let mut conn = fixture.connect();
+
+        let (status, headers, body) = send(&mut conn, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        assert_eq!((status, body.as_str()), (200, "index\n"));
+        assert!(has_header(&headers, "connection", "keep-alive"));
+        let (status, _, _) = send(&mut conn, "GET /missing HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        assert_eq!(status, 404);
+        let (status, _, body) = send(&mut conn, "POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello").unwrap();
+        assert_eq!((status, body.as_str()), (200, "hello"));
+
+        let (status, headers, _) = send(&mut conn, "GET / HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+        assert_eq!(status, 200);
+        assert!(has_header(&headers, "connection", "close"));
+        let mut rest = Vec::new();
+        assert_eq!(conn.read_to_end(&mut rest).unwrap(), 0);
+    }
+
+    #[test]
+    fn auth_guards_prefix() {
+        let fixture = Fixture::start("auth");
+        let mut conn = fixture.connect();
+
+        let (status, headers, _) = send(&mut conn, "GET /admin/stats HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(status, 401);
+        assert!(has_header(&headers, "www-authenticate", "Bearer"));
+        let (status, _, _) = send(&mut conn, "GET /admin/stats HTTP/1.1\r\nAuthorization: Bearer wrong\r\n\r\n").unwrap();
+        assert_eq!(status, 401);
+        let (status, _, body) =
+            send(&mut conn, "GET /admin/stats HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n").unwrap();
+        assert_eq!((status, body.as_str()), (200, "stats\n"));
+    }
+
+    #[test]
+    fn static_files_stay_inside_root() {
+        let fixture = Fixture::start("static");
+        let mut conn = fixture.connect();
+
+        let (status, headers, body) = send(&mut conn, "GET /static/hello.txt HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!((status, body.as_str()), (200, "hello from disk\n"));
+        assert!(has_header(&headers, "content-type", "text/plain; charset=utf-8"));
+        let (status, _, _) = send(&mut conn, "GET /static/../etc/passwd HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn logging_records_final_status() {
+        let fixture = Fixture::start("logging");
+        let mut conn = fixture.connect();
+        send(&mut conn, "GET / HTTP/1.1\r\n\r\n").unwrap();
+        send(&mut conn, "GET /admin/stats HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+        assert_eq!(*fixture.log.lock().unwrap(), ["GET / -> 200", "GET /admin/stats -> 401"]);
+        let output = String::from_utf8(fixture.output.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output, "[http] GET / -> 200\n[http] GET /admin/stats -> 401\n");
+    }
+
+    #[test]
+    fn malformed_request_gets_400() {
+        let fixture = Fixture::start("malformed");
+        let mut conn = fixture.connect();
+        let (status, headers, _) = send(&mut conn, "NONSENSE\r\n\r\n").unwrap();
+        assert_eq!(status, 400);
+        assert!(has_header(&headers, "connection", "close"));
+
+        let mut conn = fixture.connect();
+        let (status, _, _) = send(&mut conn, "POST /echo HTTP/1.1\r\nContent-Length: abc\r\n\r\n").unwrap();
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn transfer_encoding_is_rejected() {
+        let fixture = Fixture::start("chunked");
+        let mut conn = fixture.connect();
+        let raw = "POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+        let (status, headers, _) = send(&mut conn, raw).unwrap();
+        assert_eq!(status, 501);
+        assert!(has_header(&headers, "connection", "close"));
+        // 分块请求体不会被当成下一个请求解析
+        let mut rest = Vec::new();
+        assert_eq!(conn.read_to_end(&mut rest).unwrap(), 0);
+
+        // 同时带 Content-Length 也一样拒绝
+        let mut conn = fixture.connect();
+        let raw = "POST /echo HTTP/1.1\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\nhello";
+        let (status, _, _) = send(&mut conn, raw).unwrap();
+        assert_eq!(status, 501);
+    }
+
+    #[test]
+    fn duplicate_or_conflicting_content_length_is_rejected() {
+        let fixture = Fixture::start("content_length");
+        for raw in [
+            "POST /echo HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 0\r\n\r\nhello",
+            "POST /echo HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 5\r\n\r\nhello",
+            "POST /echo HTTP/1.1\r\nContent-Length: 5, 5\r\n\r\nhello",
+            "POST /echo HTTP/1.1\r\nContent-Length: +5\r\n\r\nhello",
+        ] {
+            let mut conn = fixture.connect();
+            let (status, headers, _) = send(&mut conn, raw).unwrap();
+            assert_eq!(status, 400, "{:?}", raw);
+            assert!(has_header(&headers, "connection", "close"));
+            // 请求体不会被当成下一个请求解析
+            let mut rest = Vec::new();
+            assert_eq!(conn.read_to_end(&mut rest).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn shutdown_does_not_wait_for_idle_connections() {
+        let mut fixture = Fixture::start("shutdown");
+        let mut idle = fixture.connect();
+        let (status, _, _) = send(&mut idle, "GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(status, 200);
+
+        let started = Instant::now();
+        fixture.shutdown();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        let mut rest = Vec::new();
+        assert_eq!(idle.read_to_end(&mut rest).unwrap(), 0);
+    }
+}
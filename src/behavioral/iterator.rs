@@ -27,10 +27,9 @@ impl<'a> Iterator for MyIterator<'a> {
     }
 }
 
-fn main() {
+pub fn main() {
     let numbers = [1, 2, 3, 4, 5];
-    let mut iter = MyIterator::new(&numbers);
-    while let Some(number) = iter.next() {
+    for number in MyIterator::new(&numbers) {
         println!("Number: {}", number);
     }
 }
@@ -0,0 +1,694 @@
+//! 多个连接池之间的负载均衡
+//! creational::objectpoll::ConnectionPool 只管理到单个后端的一组连接。
+//! LoadBalancer 管理多个后端，每个后端有自己的连接池，选择后端的算法是可替换的策略：
+//!     RoundRobin          依次轮流
+//!     WeightedRoundRobin  平滑加权轮询，按权重分配且不会连续集中到同一个后端
+//!     LeastOutstanding    当前借出连接最少的后端
+//!     PowerOfTwoChoices   随机取两个，选借出连接较少的一个
+//!     ConsistentHash      按键哈希到虚拟节点环上，后端增减时只有少量键换后端
+//! 连续失败的后端会被摘除一段时间，到期后自动恢复，也可以用健康检查主动摘除和恢复；
+//! 摘除事件通过 on_eject 登记的回调通知使用方。
+//! 策略给出后端的优先顺序，选中的后端连接池已空时依次尝试下一个；
+//! 一致性哈希沿环顺时针找下一个后端，环本身不因连接池已空而重建。
+//! 借出的连接（Lease）持有后端的引用，运行时切换策略不会影响已经借出的连接。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use crate::behavioral::clock::{Clock, ManualClock};
+use crate::behavioral::retry::{RandomSource, SeededRng};
+use crate::creational::objectpoll::{Connection, ConnectionPool};
+
+//-----------------后端---------------------------------------------------------------------
+/// 一个后端及其连接池
+pub struct Backend {
+    name: String,
+    weight: u32,
+    pool: Arc<ConnectionPool>,
+    /// 借出未归还的连接数
+    outstanding: AtomicUsize,
+    /// 连续失败次数
+    failures: AtomicU32,
+    /// 被摘除时记录恢复的时间
+    ejected_until: Mutex<Option<Duration>>,
+}
+
+impl Backend {
+    pub fn new(name: &str, weight: u32, pool: Arc<ConnectionPool>) -> Self {
+        Backend {
+            name: name.to_string(),
+            weight: weight.max(1),
+            pool,
+            outstanding: AtomicUsize::new(0),
+            failures: AtomicU32::new(0),
+            ejected_until: Mutex::new(None),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    pub fn outstanding(&self) -> usize {
+        self.outstanding.load(Ordering::SeqCst)
+    }
+
+    pub fn pool(&self) -> &Arc<ConnectionPool> {
+        &self.pool
+    }
+
+    /// 在 now 时刻是否可用；摘除到期的后端在这里恢复
+    fn available_at(&self, now: Duration) -> bool {
+        let mut ejected = self.ejected_until.lock().unwrap();
+        match *ejected {
+            Some(until) if now < until => false,
+            Some(_) => {
+                *ejected = None;
+                self.failures.store(0, Ordering::SeqCst);
+                true
+            }
+            None => true,
+        }
+    }
+}
+
+/// FNV-1a 64 位哈希
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash: u64, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+//-----------------选择策略---------------------------------------------------------------------
+/// 后端选择策略；candidates 只包含当前可用的后端，且顺序固定
+pub trait BalanceStrategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// 返回选中的后端在 candidates 中的下标；key 为一致性哈希等需要的请求键
+    fn pick(&self, candidates: &[Arc<Backend>], key: Option<&str>) -> Option<usize>;
+
+    /// 按优先顺序依次给出候选下标，acquire 在连接池已空时取下一个；
+    /// 默认每次在剩下的候选中重新 pick，只有需要下一个时才调用
+    fn preference<'a>(&'a self, candidates: &'a [Arc<Backend>], key: Option<&'a str>) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+        Box::new(std::iter::from_fn(move || {
+            let subset: Vec<Arc<Backend>> = remaining.iter().map(|&i| candidates[i].clone()).collect();
+            let chosen = self.pick(&subset, key)?;
+            Some(remaining.remove(chosen))
+        }))
+    }
+}
+
+/// 依次轮流
+#[derive(Default)]
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+
+impl BalanceStrategy for RoundRobin {
+    fn name(&self) -> &str {
+        "round robin"
+    }
+
+    fn pick(&self, candidates: &[Arc<Backend>], _: Option<&str>) -> Option<usize> {
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(self.next.fetch_add(1, Ordering::SeqCst) % candidates.len())
+    }
+}
+
+/// 平滑加权轮询：每次给每个后端加上自己的权重，选当前值最大的，再从它身上减去总权重
+#[derive(Default)]
+pub struct WeightedRoundRobin {
+    current: Mutex<HashMap<String, i64>>,
+}
+
+impl BalanceStrategy for WeightedRoundRobin {
+    fn name(&self) -> &str {
+        "weighted round robin"
+    }
+
+    fn pick(&self, candidates: &[Arc<Backend>], _: Option<&str>) -> Option<usize> {
+        let mut current = self.current.lock().unwrap();
+        let total: i64 = candidates.iter().map(|b| b.weight() as i64).sum();
+        let mut best: Option<(usize, i64)> = None;
+        for (i, backend) in candidates.iter().enumerate() {
+            let value = current.entry(backend.name().to_string()).or_insert(0);
+            *value += backend.weight() as i64;
+            if best.is_none_or(|(_, max)| *value > max) {
+                best = Some((i, *value));
+            }
+        }
+        let (chosen, _) = best?;
+        *current.get_mut(candidates[chosen].name()).unwrap() -= total;
+        Some(chosen)
+    }
+}
+
+/// 借出连接最少的后端，相同时取靠前的
+pub struct LeastOutstanding;
+
+impl BalanceStrategy for LeastOutstanding {
+    fn name(&self) -> &str {
+        "least outstanding"
+    }
+
+    fn pick(&self, candidates: &[Arc<Backend>], _: Option<&str>) -> Option<usize> {
+        (0..candidates.len()).min_by_key(|&i| candidates[i].outstanding())
+    }
+}
+
+/// 随机取两个不同的后端，选借出连接较少的一个
+pub struct PowerOfTwoChoices {
+    rng: Mutex<SeededRng>,
+}
+
+impl PowerOfTwoChoices {
+    pub fn new(seed: u64) -> Self {
+        PowerOfTwoChoices { rng: Mutex::new(SeededRng::new(seed)) }
+    }
+}
+
+impl BalanceStrategy for PowerOfTwoChoices {
+    fn name(&self) -> &str {
+        "power of two choices"
+    }
+
+    fn pick(&self, candidates: &[Arc<Backend>], _: Option<&str>) -> Option<usize> {
+        let n = candidates.len();
+        if n < 2 {
+            return (n == 1).then_some(0);
+        }
+        let mut rng = self.rng.lock().unwrap();
+        let a = (rng.next_u64() % n as u64) as usize;
+        let b = (a + 1 + (rng.next_u64() % (n as u64 - 1)) as usize) % n;
+        Some(if candidates[b].outstanding() < candidates[a].outstanding() { b } else { a })
+    }
+}
+
+/// 一致性哈希：每个后端在环上放 replicas 个虚拟节点，键落在顺时针方向的第一个节点上；
+/// 没有键的请求按空字符串处理。
+/// 环按候选后端缓存，只在后端增减或被摘除、恢复时重建，选择时二分查找；
+/// 落点的后端连接池已空时继续沿环顺时针查找
+pub struct ConsistentHash {
+    replicas: usize,
+    ring: RwLock<Arc<Ring>>,
+}
+
+/// 虚拟节点环，names 记录构建时的候选后端
+#[derive(Default)]
+struct Ring {
+    names: Vec<String>,
+    /// (哈希, 候选下标)，按哈希排序
+    points: Vec<(u64, usize)>,
+}
+
+impl Ring {
+    fn build(candidates: &[Arc<Backend>], replicas: usize) -> Self {
+        let mut points: Vec<(u64, usize)> = candidates
+            .iter()
+            .enumerate()
+            .flat_map(|(i, backend)| (0..replicas).map(move |r| (fnv1a(&format!("{}#{}", backend.name(), r)), i)))
+            .collect();
+        points.sort_unstable();
+        Ring { names: candidates.iter().map(|b| b.name().to_string()).collect(), points }
+    }
+
+    fn built_for(&self, candidates: &[Arc<Backend>]) -> bool {
+        self.names.len() == candidates.len() && self.names.iter().zip(candidates).all(|(name, b)| name == b.name())
+    }
+
+    fn lookup(&self, hash: u64) -> Option<usize> {
+        self.walk(hash).next()
+    }
+
+    /// 从 hash 开始顺时针经过的后端，每个只出现一次
+    fn walk(&self, hash: u64) -> impl Iterator<Item = usize> + '_ {
+        let start = self.points.partition_point(|(point, _)| *point < hash);
+        let mut seen = vec![false; self.names.len()];
+        (0..self.points.len()).filter_map(move |k| {
+            let (_, i) = self.points[(start + k) % self.points.len()];
+            (!std::mem::replace(&mut seen[i], true)).then_some(i)
+        })
+    }
+}
+
+impl ConsistentHash {
+    pub fn new(replicas: usize) -> Self {
+        ConsistentHash { replicas: replicas.max(1), ring: RwLock::new(Arc::new(Ring::default())) }
+    }
+
+    /// 为 candidates 构建的环，候选后端没有变化时直接使用缓存
+    fn ring_for(&self, candidates: &[Arc<Backend>]) -> Arc<Ring> {
+        {
+            let ring = self.ring.read().unwrap();
+            if ring.built_for(candidates) {
+                return ring.clone();
+            }
+        }
+        let mut ring = self.ring.write().unwrap();
+        if !ring.built_for(candidates) {
+            *ring = Arc::new(Ring::build(candidates, self.replicas));
+        }
+        ring.clone()
+    }
+}
+
+impl BalanceStrategy for ConsistentHash {
+    fn name(&self) -> &str {
+        "consistent hash"
+    }
+
+    fn pick(&self, candidates: &[Arc<Backend>], key: Option<&str>) -> Option<usize> {
+        self.ring_for(candidates).lookup(fnv1a(key.unwrap_or("")))
+    }
+
+    fn preference<'a>(&'a self, candidates: &'a [Arc<Backend>], key: Option<&'a str>) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let ring = self.ring_for(candidates);
+        let order: Vec<usize> = ring.walk(fnv1a(key.unwrap_or(""))).collect();
+        Box::new(order.into_iter())
+    }
+}
+
+//-----------------负载均衡器---------------------------------------------------------------------
+/// 获取连接失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum BalanceError {
+    /// 所有后端都被摘除
+    NoHealthyBackend,
+    /// 所有可用后端的连接池都已空，内容为策略最先选中的后端
+    PoolExhausted(String),
+}
+
+impl fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalanceError::NoHealthyBackend => write!(f, "no healthy backend"),
+            BalanceError::PoolExhausted(name) => write!(f, "connection pool of {} is exhausted", name),
+        }
+    }
+}
+
+impl std::error::Error for BalanceError {}
+
+/// 借出的连接，drop 时归还到所属后端的连接池
+pub struct Lease {
+    backend: Arc<Backend>,
+    connection: Option<Connection>,
+}
+
+impl Lease {
+    pub fn backend(&self) -> &str {
+        self.backend.name()
+    }
+
+    pub fn connection(&self) -> &Connection {
+        self.connection.as_ref().expect("connection is present until drop")
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.backend.pool().release_connection(connection);
+        }
+        self.backend.outstanding.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 后端被摘除的事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ejection {
+    pub backend: String,
+    pub duration: Duration,
+    /// 在时钟上的恢复时间
+    pub until: Duration,
+}
+
+/// 摘除事件的回调
+pub type EjectHook = Box<dyn Fn(&Ejection) + Send + Sync>;
+
+/// 负载均衡器
+pub struct LoadBalancer {
+    backends: Vec<Arc<Backend>>,
+    strategy: RwLock<Arc<dyn BalanceStrategy>>,
+    clock: Arc<dyn Clock>,
+    /// 连续失败多少次后摘除
+    eject_after: u32,
+    /// 摘除多长时间
+    eject_for: Duration,
+    on_eject: Vec<EjectHook>,
+}
+
+impl LoadBalancer {
+    pub fn new(backends: Vec<Backend>, strategy: Arc<dyn BalanceStrategy>, clock: Arc<dyn Clock>) -> Self {
+        LoadBalancer {
+            backends: backends.into_iter().map(Arc::new).collect(),
+            strategy: RwLock::new(strategy),
+            clock,
+            eject_after: 3,
+            eject_for: Duration::from_secs(30),
+            on_eject: Vec::new(),
+        }
+    }
+
+    /// 设置摘除规则
+    pub fn with_ejection(mut self, after_failures: u32, duration: Duration) -> Self {
+        self.eject_after = after_failures.max(1);
+        self.eject_for = duration;
+        self
+    }
+
+    /// 登记摘除事件的回调，连续失败、健康检查和手动摘除都会触发
+    pub fn on_eject(mut self, hook: impl Fn(&Ejection) + Send + Sync + 'static) -> Self {
+        self.on_eject.push(Box::new(hook));
+        self
+    }
+
+    /// 运行时切换策略，已经借出的连接不受影响
+    pub fn set_strategy(&self, strategy: Arc<dyn BalanceStrategy>) {
+        *self.strategy.write().unwrap() = strategy;
+    }
+
+    pub fn strategy_name(&self) -> String {
+        self.strategy.read().unwrap().name().to_string()
+    }
+
+    pub fn backends(&self) -> &[Arc<Backend>] {
+        &self.backends
+    }
+
+    /// 当前可用的后端
+    pub fn healthy(&self) -> Vec<Arc<Backend>> {
+        let now = self.clock.now();
+        self.backends.iter().filter(|b| b.available_at(now)).cloned().collect()
+    }
+
+    /// 按当前策略选择后端并借出一个连接；选中的后端连接池已空时，按策略的优先顺序尝试下一个
+    pub fn acquire(&self, key: Option<&str>) -> Result<Lease, BalanceError> {
+        let candidates = self.healthy();
+        let strategy = self.strategy.read().unwrap().clone();
+        let mut first_exhausted = None;
+        for index in strategy.preference(&candidates, key) {
+            let backend = &candidates[index];
+            if let Some(connection) = backend.pool().get_connection() {
+                backend.outstanding.fetch_add(1, Ordering::SeqCst);
+                return Ok(Lease { backend: backend.clone(), connection: Some(connection) });
+            }
+            first_exhausted.get_or_insert_with(|| backend.name().to_string());
+        }
+        Err(first_exhausted.map_or(BalanceError::NoHealthyBackend, BalanceError::PoolExhausted))
+    }
+
+    /// 记录使用连接的结果，连续失败达到阈值时摘除后端
+    pub fn record(&self, lease: &Lease, success: bool) {
+        let backend = &lease.backend;
+        if success {
+            backend.failures.store(0, Ordering::SeqCst);
+            return;
+        }
+        if backend.failures.fetch_add(1, Ordering::SeqCst) + 1 >= self.eject_after {
+            self.eject(backend.name(), self.eject_for);
+        }
+    }
+
+    /// 摘除后端 duration 时间，返回摘除事件；没有这个后端时返回 None
+    pub fn eject(&self, name: &str, duration: Duration) -> Option<Ejection> {
+        let backend = self.backends.iter().find(|b| b.name() == name)?;
+        let until = self.clock.now() + duration;
+        *backend.ejected_until.lock().unwrap() = Some(until);
+        let event = Ejection { backend: name.to_string(), duration, until };
+        for hook in &self.on_eject {
+            hook(&event);
+        }
+        Some(event)
+    }
+
+    /// 立即恢复后端
+    pub fn reinstate(&self, name: &str) {
+        if let Some(backend) = self.backends.iter().find(|b| b.name() == name) {
+            *backend.ejected_until.lock().unwrap() = None;
+            backend.failures.store(0, Ordering::SeqCst);
+        }
+    }
+
+    /// 主动健康检查：探测失败的后端被摘除，已经被摘除的重新计算摘除时间；
+    /// 被摘除但探测成功的后端恢复
+    pub fn health_check(&self, probe: impl Fn(&Backend) -> bool) {
+        let now = self.clock.now();
+        for backend in &self.backends {
+            let ejected = !backend.available_at(now);
+            if !probe(backend) {
+                self.eject(backend.name(), self.eject_for);
+            } else if ejected {
+                self.reinstate(backend.name());
+            }
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+/// 借出并立即归还 n 个连接，统计每个后端被选中的次数
+fn distribution(balancer: &LoadBalancer, n: usize) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = balancer.backends().iter().map(|b| (b.name().to_string(), 0)).collect();
+    for i in 0..n {
+        let lease = balancer.acquire(Some(&format!("user-{}", i))).unwrap();
+        counts.iter_mut().find(|(name, _)| name == lease.backend()).unwrap().1 += 1;
+    }
+    counts
+}
+
+pub fn main() {
+    let clock = Arc::new(ManualClock::new(Duration::ZERO));
+    let backends = vec![
+        Backend::new("a", 5, ConnectionPool::new(4)),
+        Backend::new("b", 1, ConnectionPool::new(4)),
+        Backend::new("c", 1, ConnectionPool::new(4)),
+    ];
+    let balancer = LoadBalancer::new(backends, Arc::new(RoundRobin::default()), clock.clone())
+        .with_ejection(2, Duration::from_secs(10))
+        .on_eject(|event| println!("ejected {} for {:?}", event.backend, event.duration));
+
+    println!("{}: {:?}", balancer.strategy_name(), distribution(&balancer, 9));
+
+    balancer.set_strategy(Arc::new(WeightedRoundRobin::default()));
+    println!("{}: {:?}", balancer.strategy_name(), distribution(&balancer, 14));
+
+    // 持有连接时切换策略：已借出的连接照常归还
+    let held: Vec<Lease> = (0..3).map(|_| balancer.acquire(None).unwrap()).collect();
+    balancer.set_strategy(Arc::new(LeastOutstanding));
+    let next = balancer.acquire(None).unwrap();
+    println!("{} while holding {:?}: {}", balancer.strategy_name(), held.iter().map(Lease::backend).collect::<Vec<_>>(), next.backend());
+    drop(held);
+    drop(next);
+
+    balancer.set_strategy(Arc::new(PowerOfTwoChoices::new(7)));
+    println!("{}: {:?}", balancer.strategy_name(), distribution(&balancer, 30));
+
+    // 一致性哈希：摘除一个后端后，原来不在它上面的键都不换后端
+    balancer.set_strategy(Arc::new(ConsistentHash::new(64)));
+    let keys: Vec<String> = (0..200).map(|i| format!("session-{}", i)).collect();
+    let route = |key: &String| balancer.acquire(Some(key)).unwrap().backend().to_string();
+    let before: Vec<String> = keys.iter().map(route).collect();
+
+    // 连续失败两次被摘除
+    let lease = balancer.acquire(Some("session-0")).unwrap();
+    let failing = lease.backend().to_string();
+    balancer.record(&lease, false);
+    balancer.record(&lease, false);
+    drop(lease);
+    let after: Vec<String> = keys.iter().map(route).collect();
+    let moved = before.iter().zip(&after).filter(|(b, a)| b != a).count();
+    println!("{}: ejected {}, {} of {} keys moved", balancer.strategy_name(), failing, moved, keys.len());
+
+    // 健康检查
+    clock.advance(Duration::from_secs(10));
+    balancer.health_check(|backend| backend.name() != "c");
+    println!("healthy after probing: {:?}", balancer.healthy().iter().map(|b| b.name().to_string()).collect::<Vec<_>>());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 总是选第一个候选后端
+    struct First;
+
+    impl BalanceStrategy for First {
+        fn name(&self) -> &str {
+            "first"
+        }
+
+        fn pick(&self, candidates: &[Arc<Backend>], _: Option<&str>) -> Option<usize> {
+            (!candidates.is_empty()).then_some(0)
+        }
+    }
+
+    fn balancer(sizes: &[usize], strategy: Arc<dyn BalanceStrategy>) -> (LoadBalancer, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let backends = sizes.iter().enumerate().map(|(i, size)| Backend::new(&format!("b{}", i), 1, ConnectionPool::new(*size))).collect();
+        (LoadBalancer::new(backends, strategy, clock.clone()), clock)
+    }
+
+    #[test]
+    fn exhausted_pool_falls_back_to_another_healthy_backend() {
+        let (balancer, _) = balancer(&[1, 1, 0], Arc::new(First));
+        let first = balancer.acquire(None).unwrap();
+        let second = balancer.acquire(None).unwrap();
+        assert_eq!((first.backend(), second.backend()), ("b0", "b1"));
+        assert_eq!(balancer.acquire(None).err(), Some(BalanceError::PoolExhausted("b0".to_string())));
+        drop(first);
+        assert_eq!(balancer.acquire(None).unwrap().backend(), "b0");
+        assert_eq!(balancer.backends().iter().map(|b| b.outstanding()).sum::<usize>(), 1);
+
+        balancer.eject("b0", Duration::from_secs(1));
+        balancer.eject("b1", Duration::from_secs(1));
+        balancer.eject("b2", Duration::from_secs(1));
+        assert_eq!(balancer.acquire(None).err(), Some(BalanceError::NoHealthyBackend));
+    }
+
+    #[test]
+    fn ejections_are_reported_to_hooks() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let (balancer, clock) = balancer(&[2, 2], Arc::new(First));
+        let balancer = balancer.with_ejection(2, Duration::from_secs(5)).on_eject(move |event| seen.lock().unwrap().push(event.clone()));
+
+        clock.advance(Duration::from_secs(1));
+        let lease = balancer.acquire(None).unwrap();
+        balancer.record(&lease, false);
+        assert!(events.lock().unwrap().is_empty());
+        balancer.record(&lease, false);
+        let expected = Ejection { backend: "b0".to_string(), duration: Duration::from_secs(5), until: Duration::from_secs(6) };
+        assert_eq!(*events.lock().unwrap(), vec![expected]);
+
+        balancer.health_check(|backend| backend.name() == "b0");
+        assert_eq!(events.lock().unwrap().last().unwrap().backend, "b1");
+        assert_eq!(balancer.eject("b0", Duration::from_secs(1)).map(|event| event.until), Some(Duration::from_secs(2)));
+        assert_eq!(balancer.eject("missing", Duration::from_secs(1)), None);
+        assert_eq!(events.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn consistent_hash_matches_a_freshly_built_ring() {
+        let (balancer, clock) = balancer(&[8, 8, 8, 8], Arc::new(ConsistentHash::new(16)));
+        let keys: Vec<String> = (0..100).map(|i| format!("key-{}", i)).collect();
+        let expected = |candidates: &[Arc<Backend>]| -> Vec<String> {
+            let ring = Ring::build(candidates, 16);
+            keys.iter().map(|key| candidates[ring.lookup(fnv1a(key)).unwrap()].name().to_string()).collect()
+        };
+        let routes = || -> Vec<String> { keys.iter().map(|key| balancer.acquire(Some(key)).unwrap().backend().to_string()).collect() };
+
+        let all = expected(balancer.backends());
+        assert_eq!(routes(), all);
+        // 摘除后重建，恢复后回到原来的路由
+        balancer.eject("b2", Duration::from_secs(1));
+        assert_eq!(routes(), expected(&balancer.healthy()));
+        assert!(routes().iter().all(|name| name != "b2"));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(routes(), all);
+    }
+
+    fn counts(distribution: &[(String, usize)]) -> Vec<usize> {
+        distribution.iter().map(|(_, n)| *n).collect()
+    }
+
+    fn weighted(strategy: Arc<dyn BalanceStrategy>) -> LoadBalancer {
+        let backends = vec![
+            Backend::new("a", 5, ConnectionPool::new(4)),
+            Backend::new("b", 1, ConnectionPool::new(4)),
+            Backend::new("c", 1, ConnectionPool::new(4)),
+        ];
+        LoadBalancer::new(backends, strategy, Arc::new(ManualClock::new(Duration::ZERO)))
+    }
+
+    #[test]
+    fn round_robin_and_weighted_distribution() {
+        let balancer = weighted(Arc::new(RoundRobin::default()));
+        assert_eq!(counts(&distribution(&balancer, 9)), vec![3, 3, 3]);
+        // 平滑加权轮询：5:1:1，且 a 不会连续被选中超过两次
+        balancer.set_strategy(Arc::new(WeightedRoundRobin::default()));
+        assert_eq!(counts(&distribution(&balancer, 14)), vec![10, 2, 2]);
+        let order: Vec<String> = (0..7).map(|_| balancer.acquire(None).unwrap().backend().to_string()).collect();
+        assert_eq!(order, vec!["a", "a", "b", "a", "c", "a", "a"]);
+    }
+
+    #[test]
+    fn least_outstanding_and_two_choices_avoid_busy_backends() {
+        let balancer = weighted(Arc::new(First));
+        let held: Vec<Lease> = (0..3).map(|_| balancer.acquire(None).unwrap()).collect();
+        assert_eq!(balancer.backends()[0].outstanding(), 3);
+
+        // 切换策略不影响已经借出的连接
+        balancer.set_strategy(Arc::new(LeastOutstanding));
+        let next = balancer.acquire(None).unwrap();
+        assert_eq!(next.backend(), "b");
+        drop(next);
+
+        // 任意两个中总有一个比 a 空闲
+        balancer.set_strategy(Arc::new(PowerOfTwoChoices::new(7)));
+        let spread = counts(&distribution(&balancer, 30));
+        assert_eq!(spread[0], 0);
+        assert_eq!(spread.iter().sum::<usize>(), 30);
+        assert!(spread[1] > 0 && spread[2] > 0);
+
+        drop(held);
+        assert!(balancer.backends().iter().all(|b| b.outstanding() == 0 && b.pool().available() == 4));
+    }
+
+    #[test]
+    fn consistent_hash_walks_the_ring_past_exhausted_backends() {
+        let (balancer, _) = balancer(&[8, 8, 0, 8], Arc::new(ConsistentHash::new(16)));
+        let keys: Vec<String> = (0..100).map(|i| format!("key-{}", i)).collect();
+        // 跳过 b2 与去掉 b2 后重建的环结果相同
+        let without: Vec<Arc<Backend>> = balancer.backends().iter().filter(|b| b.name() != "b2").cloned().collect();
+        let ring = Ring::build(&without, 16);
+        for key in &keys {
+            let lease = balancer.acquire(Some(key)).unwrap();
+            assert_eq!(lease.backend(), without[ring.lookup(fnv1a(key)).unwrap()].name());
+        }
+        assert_eq!(balancer.backends()[2].pool().available(), 0);
+    }
+
+    #[test]
+    fn exhausted_backend_does_not_rebuild_the_ring() {
+        let strategy = Arc::new(ConsistentHash::new(16));
+        let (balancer, _) = balancer(&[0, 0, 1], strategy.clone());
+        let ring = strategy.ring_for(balancer.backends());
+        let lease = balancer.acquire(Some("key")).unwrap();
+        assert_eq!(lease.backend(), "b2");
+        assert!(Arc::ptr_eq(&ring, &strategy.ring_for(balancer.backends())));
+        assert!(matches!(balancer.acquire(Some("key")), Err(BalanceError::PoolExhausted(_))));
+    }
+
+    #[test]
+    fn health_check_uses_expiry_and_re_ejects_failing_backends() {
+        let (balancer, clock) = balancer(&[1, 1], Arc::new(First));
+        let balancer = balancer.with_ejection(3, Duration::from_secs(5));
+
+        // 摘除已经到期，但探测仍然失败：重新摘除
+        balancer.eject("b0", Duration::from_secs(1));
+        clock.advance(Duration::from_secs(2));
+        balancer.health_check(|backend| backend.name() != "b0");
+        assert_eq!(balancer.acquire(None).unwrap().backend(), "b1");
+
+        // 摘除期间探测失败，摘除时间从现在重新计算
+        clock.advance(Duration::from_secs(4));
+        balancer.health_check(|backend| backend.name() != "b0");
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(balancer.healthy().len(), 1);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(balancer.healthy().len(), 2);
+
+        // 探测成功立即恢复
+        balancer.eject("b1", Duration::from_secs(60));
+        balancer.health_check(|_| true);
+        assert_eq!(balancer.healthy().len(), 2);
+    }
+}
@@ -2,6 +2,7 @@
 //! 通常的做法是通过一个结构体来保存目标对象的“快照”，并在需要时还原它。
 //----定义Editor和Memento结构体--------------------------------------------------
 /// 编辑器结构体，包含文本内容。
+#[derive(Default)]
 pub struct Editor {
     content: String,
 }
@@ -40,7 +41,7 @@ impl Editor {
     }
 }
 
-fn main() {
+pub fn main() {
     let mut editor = Editor::new();
 
     editor.set_content("第一次输入");
@@ -6,4 +6,25 @@ pub mod responsibility_chain;
 pub mod strategy;
 pub mod template_method;
 pub mod iterator;
-pub mod memento;
\ No newline at end of file
+pub mod memento;
+pub mod chain_trace;
+pub mod clock;
+pub mod escape;
+pub mod approval;
+pub mod http_server;
+pub mod state_machine;
+pub mod state_diagram;
+pub mod typestate;
+pub mod hierarchical_state;
+pub mod transition_log;
+pub mod election;
+pub mod model_check;
+pub mod expression;
+pub mod geometry;
+pub mod fleet;
+pub mod compression;
+pub mod retry;
+pub mod load_balancer;
+pub mod cache;
+pub mod etl;
+pub mod game_loop;
\ No newline at end of file
@@ -0,0 +1,421 @@
+//! 状态机的穷举模型检查
+//! 手写的迁移逻辑很容易出现这样的问题：某个状态永远到不了、进入某个状态后再也出不来、
+//! 某个状态下忘了处理某个事件，或者上下文进入了不该出现的组合。
+//! ModelChecker 从状态机的当前状态出发，按广度优先依次尝试每个事件，
+//! 在给定的步数内走遍所有可达的 (状态, 上下文) 组合，报告：
+//!     不可达状态、死状态（没有任何事件能触发迁移）、未定义的迁移、违反不变式的情况，
+//! 除不可达状态外，每个问题都附带一条最短的反例路径。
+//! 如果搜索因步数限制停下时还有没走到的组合，报告标记为截断，没到达的状态只标记为未探索，不当作不可达。
+//! 广度优先保证先找到的路径就是最短的。迁移动作会照常执行，守卫看到的是真实的上下文。
+
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::behavioral::state_machine::{voting_machine, StateMachine, StateMachineBuilder, TransitionError, VoteEvent, VoteState};
+
+/// 不变式：每个可达的 (状态, 上下文) 都必须满足
+pub type Invariant<S, C> = Box<dyn Fn(S, &C) -> bool>;
+
+/// 反例路径：从起始状态出发依次触发的事件和到达的状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace<S, E> {
+    pub start: S,
+    pub steps: Vec<(E, S)>,
+}
+
+impl<S: Copy, E: Copy> Trace<S, E> {
+    /// 路径的终点
+    pub fn end(&self) -> S {
+        self.steps.last().map_or(self.start, |(_, state)| *state)
+    }
+
+    fn then(&self, event: E, state: S) -> Self {
+        let mut steps = self.steps.clone();
+        steps.push((event, state));
+        Trace { start: self.start, steps }
+    }
+}
+
+impl<S: fmt::Debug, E: fmt::Debug> fmt::Display for Trace<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.start)?;
+        for (event, state) in &self.steps {
+            write!(f, " --{:?}--> {:?}", event, state)?;
+        }
+        Ok(())
+    }
+}
+
+/// 检查发现的问题
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finding<S, E> {
+    /// 声明过但在步数限制内无法到达的状态
+    Unreachable(S),
+    /// 搜索被步数限制截断时还没到达的状态，可能只是步数不够
+    Unexplored(S),
+    /// 到达后任何事件都无法触发迁移
+    DeadEnd { trace: Trace<S, E> },
+    /// 到达 trace 的终点后，event 没有定义迁移
+    Undefined { trace: Trace<S, E>, event: E },
+    /// trace 的终点违反了不变式
+    InvariantViolated { name: String, trace: Trace<S, E> },
+}
+
+impl<S: fmt::Debug + Copy, E: fmt::Debug + Copy> fmt::Display for Finding<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Unreachable(state) => write!(f, "unreachable state {:?}", state),
+            Finding::Unexplored(state) => write!(f, "state {:?} not reached within the depth bound", state),
+            Finding::DeadEnd { trace } => write!(f, "dead-end state {:?}: {}", trace.end(), trace),
+            Finding::Undefined { trace, event } => {
+                write!(f, "undefined transition {:?} + {:?}: {}", trace.end(), event, trace)
+            }
+            Finding::InvariantViolated { name, trace } => write!(f, "invariant \"{}\" violated: {}", name, trace),
+        }
+    }
+}
+
+/// 检查报告
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelReport<S, E> {
+    pub findings: Vec<Finding<S, E>>,
+    /// 探索到的不同 (状态, 上下文) 组合数
+    pub explored: usize,
+    /// 边界上的组合是否还能迁移到没探索过的组合
+    pub truncated: bool,
+}
+
+impl<S, E> ModelReport<S, E> {
+    /// 没有发现任何问题
+    pub fn is_ok(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+impl<S: fmt::Debug + Copy, E: fmt::Debug + Copy> fmt::Display for ModelReport<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "explored {} configurations{}, {} finding(s)",
+            self.explored,
+            if self.truncated { " (bound reached)" } else { "" },
+            self.findings.len()
+        )?;
+        for finding in &self.findings {
+            writeln!(f, "  {}", finding)?;
+        }
+        Ok(())
+    }
+}
+
+//-----------------模型检查器---------------------------------------------------------------------
+/// 模型检查器
+pub struct ModelChecker<S, E, C> {
+    events: Vec<E>,
+    max_depth: usize,
+    invariants: Vec<(String, Invariant<S, C>)>,
+    /// 有意不处理的 (状态, 事件)，不报告为未定义迁移
+    allowed_undefined: Vec<(S, E)>,
+}
+
+impl<S, E, C> ModelChecker<S, E, C>
+where
+    S: Copy + Eq + Hash + fmt::Debug,
+    E: Copy + PartialEq + fmt::Debug,
+    C: Clone + Eq + Hash,
+{
+    /// events 为要尝试的全部事件，max_depth 为最多探索的步数
+    pub fn new(events: &[E], max_depth: usize) -> Self {
+        ModelChecker { events: events.to_vec(), max_depth, invariants: Vec::new(), allowed_undefined: Vec::new() }
+    }
+
+    /// 添加不变式
+    pub fn invariant(mut self, name: &str, invariant: impl Fn(S, &C) -> bool + 'static) -> Self {
+        self.invariants.push((name.to_string(), Box::new(invariant)));
+        self
+    }
+
+    /// 声明某个状态下有意不处理某个事件
+    pub fn allow_undefined(mut self, state: S, event: E) -> Self {
+        self.allowed_undefined.push((state, event));
+        self
+    }
+
+    /// 从状态机的当前状态和上下文出发检查；检查结束后状态机恢复原样
+    pub fn check(&self, machine: &mut StateMachine<S, E, C>) -> ModelReport<S, E> {
+        let origin = (machine.state(), machine.context().clone());
+        let start = Trace { start: origin.0, steps: Vec::new() };
+
+        let mut findings = Vec::new();
+        let mut visited: HashSet<(S, C)> = HashSet::from([origin.clone()]);
+        let mut frontier: Vec<(S, C, Trace<S, E>)> = vec![(origin.0, origin.1.clone(), start)];
+        let mut reported_dead = Vec::new();
+        let mut reported_undefined = Vec::new();
+        let mut reported_invariants = Vec::new();
+        let mut depth = 0;
+        let mut truncated = false;
+
+        while !frontier.is_empty() && depth <= self.max_depth {
+            let mut next = Vec::new();
+            for (state, context, trace) in frontier {
+                for (name, invariant) in &self.invariants {
+                    if !reported_invariants.contains(name) && !invariant(state, &context) {
+                        reported_invariants.push(name.clone());
+                        findings.push(Finding::InvariantViolated { name: name.clone(), trace: trace.clone() });
+                    }
+                }
+
+                // 到达步数限制的组合只检查不变式，不再展开；只看它还能不能走到新的组合
+                if depth == self.max_depth {
+                    truncated = truncated || self.has_unvisited_successor(machine, state, &context, &visited);
+                    continue;
+                }
+
+                let mut fired = false;
+                let mut undefined = Vec::new();
+                for &event in &self.events {
+                    machine.restore(state, context.clone());
+                    match machine.fire(event) {
+                        Ok(to) => {
+                            fired = true;
+                            let reached = (to, machine.context().clone());
+                            if visited.insert(reached.clone()) {
+                                next.push((reached.0, reached.1, trace.then(event, to)));
+                            }
+                        }
+                        Err(TransitionError::Undefined { .. }) => {
+                            let key = (state, event);
+                            if !self.allowed_undefined.contains(&key) && !reported_undefined.contains(&key) {
+                                undefined.push(key);
+                            }
+                        }
+                        // 守卫拒绝说明迁移有定义，只是当前上下文不满足
+                        Err(TransitionError::GuardRejected { .. }) => {}
+                    }
+                }
+                // 死状态下的每个事件都未定义，只报告死状态本身
+                if fired {
+                    for (state, event) in undefined {
+                        reported_undefined.push((state, event));
+                        findings.push(Finding::Undefined { trace: trace.clone(), event });
+                    }
+                } else if !reported_dead.contains(&state) {
+                    reported_dead.push(state);
+                    findings.push(Finding::DeadEnd { trace });
+                }
+            }
+            frontier = next;
+            depth += 1;
+        }
+
+        for &state in machine.states() {
+            if !visited.iter().any(|(s, _)| *s == state) {
+                findings.push(if truncated { Finding::Unexplored(state) } else { Finding::Unreachable(state) });
+            }
+        }
+        machine.restore(origin.0, origin.1);
+
+        ModelReport { findings, explored: visited.len(), truncated }
+    }
+
+    /// 边界上的组合是否有某个事件能迁移到没访问过的组合
+    fn has_unvisited_successor(
+        &self,
+        machine: &mut StateMachine<S, E, C>,
+        state: S,
+        context: &C,
+        visited: &HashSet<(S, C)>,
+    ) -> bool {
+        self.events.iter().any(|&event| {
+            machine.restore(state, context.clone());
+            match machine.fire(event) {
+                Ok(to) => !visited.contains(&(to, machine.context().clone())),
+                Err(_) => false,
+            }
+        })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+/// 有缺陷的订单流程，用来演示各类问题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Order {
+    Cart,
+    Paid,
+    Shipped,
+    Delivered,
+    Refunded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum OrderEvent {
+    Pay,
+    Ship,
+    Deliver,
+    Refund,
+}
+
+/// 上下文：已付款金额、已退款金额
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Ledger {
+    paid: u32,
+    refunded: u32,
+}
+
+/// 订单流程：Refunded 没有出口，Delivered 忘了接上，退款金额可能超过付款
+fn order_flow() -> StateMachine<Order, OrderEvent, Ledger> {
+    StateMachineBuilder::new(Order::Cart)
+        .state(Order::Delivered)
+        .transition(Order::Cart, OrderEvent::Pay, Order::Paid)
+        .action(|ledger: &mut Ledger| ledger.paid += 10)
+        .transition(Order::Paid, OrderEvent::Ship, Order::Shipped)
+        .transition(Order::Paid, OrderEvent::Refund, Order::Refunded)
+        .action(|ledger: &mut Ledger| ledger.refunded += 10)
+        .transition(Order::Shipped, OrderEvent::Refund, Order::Refunded)
+        .action(|ledger: &mut Ledger| ledger.refunded += 20)
+        .build(Ledger { paid: 0, refunded: 0 })
+}
+
+const ORDER_EVENTS: [OrderEvent; 4] = [OrderEvent::Pay, OrderEvent::Ship, OrderEvent::Deliver, OrderEvent::Refund];
+
+fn order_checker(max_depth: usize) -> ModelChecker<Order, OrderEvent, Ledger> {
+    ModelChecker::new(&ORDER_EVENTS, max_depth)
+        .invariant("refund never exceeds payment", |_, ledger: &Ledger| ledger.refunded <= ledger.paid)
+}
+
+const VOTE_EVENTS: [VoteEvent; 4] = [VoteEvent::Vote, VoteEvent::ClosePoll, VoteEvent::ReopenPoll, VoteEvent::DeadlinePassed];
+
+/// 投票机的检查器：未定义的迁移都是有意为之
+fn voting_checker(max_depth: usize) -> ModelChecker<VoteState, VoteEvent, bool> {
+    use VoteEvent::*;
+    use VoteState::*;
+    ModelChecker::new(&VOTE_EVENTS, max_depth)
+        .allow_undefined(Voted, Vote)
+        .allow_undefined(Locked, Vote)
+        .allow_undefined(Locked, ClosePoll)
+        .allow_undefined(NotVoted, ReopenPoll)
+        .allow_undefined(Voted, ReopenPoll)
+        .invariant("voted state implies a recorded vote", |state, voted: &bool| state != Voted || *voted)
+}
+
+pub fn main() {
+    // 投票机：所有状态可达，没有死状态
+    let report = voting_checker(6).check(&mut voting_machine());
+    print!("voting machine: {}", report);
+
+    // 去掉 allow_undefined，检查器给出最短反例
+    let report = ModelChecker::new(&VOTE_EVENTS, 6).check(&mut voting_machine());
+    print!("voting machine, nothing allowed: {}", report);
+
+    let report = order_checker(10).check(&mut order_flow());
+    print!("order flow: {}", report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use VoteEvent::*;
+    use VoteState::*;
+
+    #[test]
+    fn voting_machine_is_clean() {
+        let mut machine = voting_machine();
+        let report = voting_checker(6).check(&mut machine);
+        assert!(report.is_ok(), "voting machine regressed:\n{}", report);
+        assert!(!report.truncated);
+    }
+
+    #[test]
+    fn check_restores_machine() {
+        let mut machine = voting_machine();
+        machine.fire(Vote).unwrap();
+        voting_checker(6).check(&mut machine);
+        assert_eq!(machine.state(), Voted);
+        assert!(*machine.context());
+    }
+
+    #[test]
+    fn undefined_transition_has_shortest_trace() {
+        let report = ModelChecker::new(&VOTE_EVENTS, 6).check(&mut voting_machine());
+        assert!(report.findings.contains(&Finding::Undefined {
+            trace: Trace { start: NotVoted, steps: vec![(Vote, Voted)] },
+            event: Vote,
+        }));
+        assert!(report.findings.contains(&Finding::Undefined {
+            trace: Trace { start: NotVoted, steps: Vec::new() },
+            event: ReopenPoll,
+        }));
+        // 每个 (状态, 事件) 只报告一次
+        let undefined = report.findings.iter().filter(|f| matches!(f, Finding::Undefined { .. })).count();
+        assert_eq!(undefined, 5);
+    }
+
+    #[test]
+    fn order_flow_defects_are_found() {
+        let report = order_checker(10).check(&mut order_flow());
+        assert!(!report.is_ok());
+        assert!(report.findings.contains(&Finding::Unreachable(Order::Delivered)));
+        assert!(report
+            .findings
+            .iter()
+            .any(|f| matches!(f, Finding::DeadEnd { trace } if trace.end() == Order::Refunded)));
+        // 最短的反例：Pay -> Ship -> Refund
+        let violation = report.findings.iter().find_map(|f| match f {
+            Finding::InvariantViolated { name, trace } => Some((name.as_str(), trace)),
+            _ => None,
+        });
+        let (name, trace) = violation.expect("invariant violation not reported");
+        assert_eq!(name, "refund never exceeds payment");
+        assert_eq!(
+            trace.steps,
+            vec![
+                (OrderEvent::Pay, Order::Paid),
+                (OrderEvent::Ship, Order::Shipped),
+                (OrderEvent::Refund, Order::Refunded)
+            ]
+        );
+    }
+
+    #[test]
+    fn depth_limit_truncates_search() {
+        let report = order_checker(2).check(&mut order_flow());
+        assert!(report.truncated);
+        // 两步内走不到违反不变式的组合
+        assert!(!report.findings.iter().any(|f| matches!(f, Finding::InvariantViolated { .. })));
+        // Cart、Paid、Shipped、Refunded；停在边界上的 Refunded 不展开，也就不报告为死状态
+        assert_eq!(report.explored, 4);
+        assert!(!report.findings.iter().any(|f| matches!(f, Finding::DeadEnd { .. })));
+        // 截断时没走到的 Delivered 只标记为未探索
+        assert!(report.findings.contains(&Finding::Unexplored(Order::Delivered)));
+        assert!(!report.findings.iter().any(|f| matches!(f, Finding::Unreachable(_))));
+    }
+
+    #[test]
+    fn bound_without_new_successors_is_not_truncated() {
+        // 两步内四个组合全部走到，边界上的 (Locked, true) 只能回到访问过的组合
+        let report = voting_checker(2).check(&mut voting_machine());
+        assert_eq!(report.explored, 4);
+        assert!(!report.truncated);
+        assert!(report.is_ok(), "{}", report);
+
+        // 一步时 (Locked, true) 还没走到
+        let report = voting_checker(1).check(&mut voting_machine());
+        assert_eq!(report.explored, 3);
+        assert!(report.truncated);
+    }
+
+    #[test]
+    fn unreachable_is_reported_only_when_search_completes() {
+        let report = order_checker(0).check(&mut order_flow());
+        assert!(report.truncated);
+        assert_eq!(report.explored, 1);
+        for state in [Order::Paid, Order::Shipped, Order::Delivered, Order::Refunded] {
+            assert!(report.findings.contains(&Finding::Unexplored(state)));
+        }
+
+        let report = order_checker(10).check(&mut order_flow());
+        assert!(!report.truncated);
+        assert!(!report.findings.iter().any(|f| matches!(f, Finding::Unexplored(_))));
+    }
+}
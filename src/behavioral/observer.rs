@@ -1,4 +1,4 @@
-///观察者模式（Observer Pattern）可以通过 trait 和组合的方式来实现一对多的依赖通知机制
+//!观察者模式（Observer Pattern）可以通过 trait 和组合的方式来实现一对多的依赖通知机制
 
 ///定义观察者接口
 pub trait Observer  {
@@ -14,6 +14,7 @@ impl Observer for ConcreteObserver {
 
 //-------------------------------------------------------------------
 ///主题
+#[derive(Default)]
 pub struct Subject {
     /// 观察者列表
     observers: Vec<Box<dyn Observer>>,
@@ -39,7 +40,7 @@ impl Subject  {
     }
 }
 //--------------------------------------------------------------------
-fn main() {
+pub fn main() {
     // 创建主题
     let mut subject = Subject::new();
     // 创建观察者
@@ -2,10 +2,35 @@
 pub struct Request{
     pub content : String
 }
+
+//处理者对请求的决定
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    //当前处理者处理了请求
+    Handled,
+    //交给下一个处理者，附带原因
+    Pass(String),
+}
+
 //处理者trait
 pub trait Handler{
     fn set_next(&mut self, handler : Box<dyn Handler>);
-    fn handle_request(&self, request: &Request);
+    //处理者名称，用于追踪和统计
+    fn name(&self) -> &str;
+    //尝试处理请求，只做决定，不负责转发给下一个处理者
+    fn try_handle(&self, request: &Request) -> Decision;
+    //下一个处理者
+    fn next(&self) -> Option<&dyn Handler>;
+
+    //处理请求：当前处理者处理不了则交给下一个
+    fn handle_request(&self, request: &Request) {
+        if let Decision::Pass(_) = self.try_handle(request) {
+            match self.next() {
+                Some(handler) => handler.handle_request(request),
+                None => println!("No handler can handle the request"),
+            }
+        }
+    }
 }
 
 //具体处理者A
@@ -19,16 +44,23 @@ impl Handler for ConcreteHandlerA {
         self.next_handler = Some(handler);
     }
 
+    fn name(&self) -> &str {
+        "ConcreteHandlerA"
+    }
+
     //处理请求
-    fn handle_request(&self, request: &Request) {
+    fn try_handle(&self, request: &Request) -> Decision {
         if request.content.contains("A") {
             println!("Handled by ConcreteHandlerA");
-        }else if let Some(ref handler) = self.next_handler{
-            handler.handle_request(request);
+            Decision::Handled
         }else {
-            println!("No handler can handle the request");
+            Decision::Pass(String::from("content does not contain \"A\""))
         }
     }
+
+    fn next(&self) -> Option<&dyn Handler> {
+        self.next_handler.as_deref()
+    }
 }
 
 //具体处理者B
@@ -41,13 +73,20 @@ impl Handler for ConcreteHandlerB {
         self.next_handler = Some(handler);
     }
 
-    fn handle_request(&self, request: &Request) {
+    fn name(&self) -> &str {
+        "ConcreteHandlerB"
+    }
+
+    fn try_handle(&self, request: &Request) -> Decision {
         if request.content.contains("B") {
             println!("Handled by ConcreteHandlerB");
-        }else if let Some(ref handler) = self.next_handler{
-            handler.handle_request(request);
-        }else{
-            println!("No handler can handle the request");
+            Decision::Handled
+        }else {
+            Decision::Pass(String::from("content does not contain \"B\""))
         }
     }
+
+    fn next(&self) -> Option<&dyn Handler> {
+        self.next_handler.as_deref()
+    }
 }
@@ -0,0 +1,498 @@
+//! 重试与退避策略
+//! creational::builder_default::Config 中的 retries 和 timeout 一直没有被用到。
+//! 这里的重试策略从 Config 读取上限：retries 为失败后最多重试的次数，timeout 为整个重试过程的期限。
+//! 注意 ConfigBuilder 没有设置的字段是零值（不重试、期限为 0），需要默认上限时使用 Config::default()。
+//! 策略决定每次失败后等待多久，或者放弃：
+//!     FixedDelay          每次等待固定时间
+//!     ExponentialBackoff  等待时间翻倍，有上限
+//!     DecorrelatedJitter  在 [base, 上次等待 * 3] 之间随机，有上限，避免大量客户端同时重试
+//!     DeadlineBounded     包装任意策略，保证总耗时不超过期限
+//! Retrier 包装任意可能失败的操作，由调用方把错误分为可重试和致命两类，并记录每次尝试。
+//! 时间和随机数都可以注入：ManualClock 的 sleep 只拨动时钟，SeededRng 的序列由种子决定。
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::behavioral::clock::{Clock, ManualClock, SystemClock};
+use crate::creational::builder_default::{Config, ConfigBuilder};
+
+//-----------------随机数---------------------------------------------------------------------
+/// 随机数来源
+pub trait RandomSource {
+    fn next_u64(&mut self) -> u64;
+
+    /// [low, high] 之间均匀分布的时间
+    fn between(&mut self, low: Duration, high: Duration) -> Duration {
+        if high <= low {
+            return low;
+        }
+        let span = (high - low).as_nanos() as u64;
+        low + Duration::from_nanos(self.next_u64() % (span + 1))
+    }
+}
+
+/// xorshift64* 随机数，相同的种子产生相同的序列
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng(seed.max(1))
+    }
+
+    /// 以当前时间为种子
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64;
+        SeededRng::new(nanos)
+    }
+}
+
+impl RandomSource for SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+//-----------------策略---------------------------------------------------------------------
+/// 放弃重试的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GiveUp {
+    /// 错误不可重试
+    Fatal,
+    /// 重试次数用完
+    RetriesExhausted,
+    /// 再等下去会超过期限
+    DeadlineExceeded,
+}
+
+impl fmt::Display for GiveUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GiveUp::Fatal => write!(f, "fatal error"),
+            GiveUp::RetriesExhausted => write!(f, "retries exhausted"),
+            GiveUp::DeadlineExceeded => write!(f, "deadline exceeded"),
+        }
+    }
+}
+
+/// 重试策略
+pub trait RetryStrategy {
+    fn name(&self) -> &str;
+
+    /// 第 failures 次失败后应等待多久；elapsed 为从第一次尝试开始经过的时间
+    fn next_delay(&mut self, failures: u32, elapsed: Duration, rng: &mut dyn RandomSource) -> Result<Duration, GiveUp>;
+
+    /// 开始新一轮重试前清除内部状态
+    fn reset(&mut self) {}
+}
+
+/// 固定等待时间
+pub struct FixedDelay {
+    pub delay: Duration,
+    pub max_retries: u32,
+}
+
+impl FixedDelay {
+    pub fn from_config(config: &Config, delay: Duration) -> Self {
+        FixedDelay { delay, max_retries: config.retries() }
+    }
+}
+
+impl RetryStrategy for FixedDelay {
+    fn name(&self) -> &str {
+        "fixed delay"
+    }
+
+    fn next_delay(&mut self, failures: u32, _: Duration, _: &mut dyn RandomSource) -> Result<Duration, GiveUp> {
+        if failures > self.max_retries {
+            return Err(GiveUp::RetriesExhausted);
+        }
+        Ok(self.delay)
+    }
+}
+
+/// 指数退避：base, 2*base, 4*base ... 不超过 max_delay
+pub struct ExponentialBackoff {
+    pub base: Duration,
+    pub max_delay: Duration,
+    pub max_retries: u32,
+}
+
+impl ExponentialBackoff {
+    pub fn from_config(config: &Config, base: Duration, max_delay: Duration) -> Self {
+        ExponentialBackoff { base, max_delay, max_retries: config.retries() }
+    }
+}
+
+impl RetryStrategy for ExponentialBackoff {
+    fn name(&self) -> &str {
+        "exponential backoff"
+    }
+
+    fn next_delay(&mut self, failures: u32, _: Duration, _: &mut dyn RandomSource) -> Result<Duration, GiveUp> {
+        if failures > self.max_retries {
+            return Err(GiveUp::RetriesExhausted);
+        }
+        let factor = 1u32.checked_shl(failures - 1).unwrap_or(u32::MAX);
+        Ok(self.base.saturating_mul(factor).min(self.max_delay))
+    }
+}
+
+/// 去相关抖动：sleep = min(cap, random(base, 上次等待 * 3))
+pub struct DecorrelatedJitter {
+    pub base: Duration,
+    pub cap: Duration,
+    pub max_retries: u32,
+    previous: Duration,
+}
+
+impl DecorrelatedJitter {
+    pub fn from_config(config: &Config, base: Duration, cap: Duration) -> Self {
+        DecorrelatedJitter { base, cap, max_retries: config.retries(), previous: base }
+    }
+}
+
+impl RetryStrategy for DecorrelatedJitter {
+    fn name(&self) -> &str {
+        "decorrelated jitter"
+    }
+
+    fn next_delay(&mut self, failures: u32, _: Duration, rng: &mut dyn RandomSource) -> Result<Duration, GiveUp> {
+        if failures > self.max_retries {
+            return Err(GiveUp::RetriesExhausted);
+        }
+        let delay = rng.between(self.base, self.previous.saturating_mul(3)).min(self.cap);
+        self.previous = delay;
+        Ok(delay)
+    }
+
+    fn reset(&mut self) {
+        self.previous = self.base;
+    }
+}
+
+/// 给任意策略加上总期限：等待后会超过期限时放弃，期限取 Config 的 timeout
+pub struct DeadlineBounded {
+    pub inner: Box<dyn RetryStrategy>,
+    pub deadline: Duration,
+    name: String,
+}
+
+impl DeadlineBounded {
+    pub fn from_config(config: &Config, inner: Box<dyn RetryStrategy>) -> Self {
+        let name = format!("{} within {:?}", inner.name(), config.timeout());
+        DeadlineBounded { inner, deadline: config.timeout(), name }
+    }
+}
+
+impl RetryStrategy for DeadlineBounded {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn next_delay(&mut self, failures: u32, elapsed: Duration, rng: &mut dyn RandomSource) -> Result<Duration, GiveUp> {
+        let delay = self.inner.next_delay(failures, elapsed, rng)?;
+        if elapsed + delay >= self.deadline {
+            return Err(GiveUp::DeadlineExceeded);
+        }
+        Ok(delay)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+//-----------------执行重试---------------------------------------------------------------------
+/// 错误分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    Retryable,
+    Fatal,
+}
+
+/// 一次尝试
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    /// 从 1 开始
+    pub number: u32,
+    /// 开始尝试时距第一次尝试的时间
+    pub started: Duration,
+    /// 失败时的错误描述
+    pub error: Option<String>,
+    /// 失败后等待的时间，放弃时为 None
+    pub delay: Option<Duration>,
+}
+
+/// 重试报告
+#[derive(Debug)]
+pub struct RetryReport<T, E> {
+    pub strategy: String,
+    pub attempts: Vec<Attempt>,
+    /// 成功的结果，或最后一个错误和放弃的原因
+    pub outcome: Result<T, (E, GiveUp)>,
+}
+
+impl<T, E: fmt::Display> fmt::Display for RetryReport<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {} attempt(s)", self.strategy, self.attempts.len())?;
+        for attempt in &self.attempts {
+            write!(f, "  #{} at {:?}: ", attempt.number, attempt.started)?;
+            match (&attempt.error, attempt.delay) {
+                (None, _) => writeln!(f, "ok")?,
+                (Some(error), Some(delay)) => writeln!(f, "{}, retry in {:?}", error, delay)?,
+                (Some(error), None) => writeln!(f, "{}", error)?,
+            }
+        }
+        match &self.outcome {
+            Ok(_) => writeln!(f, "  succeeded"),
+            Err((error, reason)) => writeln!(f, "  gave up ({}): {}", reason, error),
+        }
+    }
+}
+
+/// 按策略重试操作
+pub struct Retrier {
+    strategy: Box<dyn RetryStrategy>,
+    clock: Arc<dyn Clock>,
+    rng: Box<dyn RandomSource>,
+}
+
+impl Retrier {
+    pub fn new(strategy: Box<dyn RetryStrategy>, clock: Arc<dyn Clock>, rng: Box<dyn RandomSource>) -> Self {
+        Retrier { strategy, clock, rng }
+    }
+
+    /// 执行 operation 直到成功或放弃；operation 的参数为尝试序号（从 1 开始）
+    pub fn run<T, E: fmt::Display>(
+        &mut self,
+        classify: impl Fn(&E) -> ErrorClass,
+        mut operation: impl FnMut(u32) -> Result<T, E>,
+    ) -> RetryReport<T, E> {
+        self.strategy.reset();
+        let start = self.clock.now();
+        let mut attempts = Vec::new();
+        let mut number = 1;
+        loop {
+            let started = self.clock.now().saturating_sub(start);
+            let error = match operation(number) {
+                Ok(value) => {
+                    attempts.push(Attempt { number, started, error: None, delay: None });
+                    return RetryReport { strategy: self.strategy.name().to_string(), attempts, outcome: Ok(value) };
+                }
+                Err(error) => error,
+            };
+
+            let decision = match classify(&error) {
+                ErrorClass::Fatal => Err(GiveUp::Fatal),
+                ErrorClass::Retryable => {
+                    let elapsed = self.clock.now().saturating_sub(start);
+                    self.strategy.next_delay(number, elapsed, self.rng.as_mut())
+                }
+            };
+            attempts.push(Attempt { number, started, error: Some(error.to_string()), delay: decision.ok() });
+            match decision {
+                Ok(delay) => self.clock.sleep(delay),
+                Err(reason) => {
+                    return RetryReport {
+                        strategy: self.strategy.name().to_string(),
+                        attempts,
+                        outcome: Err((error, reason)),
+                    };
+                }
+            }
+            number += 1;
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+/// 示例中的网络错误
+#[derive(Debug, Clone, PartialEq)]
+enum NetError {
+    Timeout,
+    Unavailable,
+    Unauthorized,
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Timeout => write!(f, "request timed out"),
+            NetError::Unavailable => write!(f, "503 service unavailable"),
+            NetError::Unauthorized => write!(f, "401 unauthorized"),
+        }
+    }
+}
+
+fn classify(error: &NetError) -> ErrorClass {
+    match error {
+        NetError::Timeout | NetError::Unavailable => ErrorClass::Retryable,
+        NetError::Unauthorized => ErrorClass::Fatal,
+    }
+}
+
+pub fn main() {
+    let config = ConfigBuilder::default().retries(4).timeout(Duration::from_secs(3)).build();
+    let ms = Duration::from_millis;
+    let clock = Arc::new(ManualClock::new(Duration::ZERO));
+    let retrier = |strategy: Box<dyn RetryStrategy>| Retrier::new(strategy, clock.clone(), Box::new(SeededRng::new(7)));
+
+    // 第 3 次成功
+    let mut r = retrier(Box::new(FixedDelay::from_config(&config, ms(100))));
+    print!("{}", r.run(classify, |n| if n < 3 { Err(NetError::Timeout) } else { Ok("payload") }));
+
+    // 一直失败：重试 4 次后放弃
+    let mut r = retrier(Box::new(ExponentialBackoff::from_config(&config, ms(100), ms(10_000))));
+    print!("{}", r.run(classify, |_| Err::<(), _>(NetError::Unavailable)));
+    // 致命错误不重试
+    print!("{}", r.run(classify, |_| Err::<(), _>(NetError::Unauthorized)));
+
+    // 抖动
+    let mut r = retrier(Box::new(DecorrelatedJitter::from_config(&config, ms(100), ms(2_000))));
+    print!("{}", r.run(classify, |_| Err::<(), _>(NetError::Timeout)));
+
+    // 总期限 3 秒
+    let bounded = DeadlineBounded::from_config(&config, Box::new(ExponentialBackoff::from_config(&config, ms(500), ms(10_000))));
+    let mut r = retrier(Box::new(bounded));
+    print!("{}", r.run(classify, |_| Err::<(), _>(NetError::Timeout)));
+
+    // 真实时钟：等待很短的时间
+    let mut r = Retrier::new(
+        Box::new(FixedDelay { delay: ms(5), max_retries: 2 }),
+        Arc::new(SystemClock),
+        Box::new(SeededRng::from_time()),
+    );
+    print!("{}", r.run(classify, |n| if n < 2 { Err(NetError::Unavailable) } else { Ok(()) }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn config() -> Config {
+        ConfigBuilder::default().retries(4).timeout(Duration::from_secs(3)).build()
+    }
+
+    fn retrier(strategy: Box<dyn RetryStrategy>, clock: &Arc<ManualClock>, seed: u64) -> Retrier {
+        Retrier::new(strategy, clock.clone(), Box::new(SeededRng::new(seed)))
+    }
+
+    fn delays<T, E>(report: &RetryReport<T, E>) -> Vec<Duration> {
+        report.attempts.iter().filter_map(|a| a.delay).collect()
+    }
+
+    #[test]
+    fn fixed_delay_succeeds_on_third_attempt() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let mut r = retrier(Box::new(FixedDelay::from_config(&config(), ms(100))), &clock, 7);
+        let report = r.run(classify, |n| if n < 3 { Err(NetError::Timeout) } else { Ok("payload") });
+        assert_eq!(report.outcome.as_ref().ok(), Some(&"payload"));
+        let started: Vec<_> = report.attempts.iter().map(|a| a.started).collect();
+        assert_eq!(started, [ms(0), ms(100), ms(200)]);
+        assert_eq!(report.attempts[2], Attempt { number: 3, started: ms(200), error: None, delay: None });
+        // ManualClock 的 sleep 只拨动时钟
+        assert_eq!(clock.now(), ms(200));
+    }
+
+    #[test]
+    fn exponential_backoff_gives_up_after_retries() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let mut r = retrier(Box::new(ExponentialBackoff::from_config(&config(), ms(100), ms(10_000))), &clock, 7);
+        let report = r.run(classify, |_| Err::<(), _>(NetError::Unavailable));
+        assert_eq!(delays(&report), [ms(100), ms(200), ms(400), ms(800)]);
+        assert_eq!(report.attempts.len(), 5);
+        assert_eq!(report.outcome.unwrap_err(), (NetError::Unavailable, GiveUp::RetriesExhausted));
+    }
+
+    #[test]
+    fn exponential_backoff_is_capped() {
+        let mut strategy = ExponentialBackoff { base: ms(100), max_delay: ms(300), max_retries: 40 };
+        let mut rng = SeededRng::new(1);
+        let delays: Vec<_> = [1, 2, 3, 40].iter().map(|&n| strategy.next_delay(n, ms(0), &mut rng).unwrap()).collect();
+        assert_eq!(delays, [ms(100), ms(200), ms(300), ms(300)]);
+    }
+
+    #[test]
+    fn fatal_error_is_not_retried() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let mut r = retrier(Box::new(FixedDelay::from_config(&config(), ms(100))), &clock, 7);
+        let report = r.run(classify, |_| Err::<(), _>(NetError::Unauthorized));
+        assert_eq!(report.attempts.len(), 1);
+        assert_eq!(report.attempts[0].delay, None);
+        assert_eq!(report.outcome.unwrap_err().1, GiveUp::Fatal);
+        assert_eq!(clock.now(), Duration::ZERO);
+    }
+
+    #[test]
+    fn jitter_is_reproducible_and_bounded() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let jitter = |seed| {
+            let mut r = retrier(Box::new(DecorrelatedJitter::from_config(&config(), ms(100), ms(2_000))), &clock, seed);
+            delays(&r.run(classify, |_| Err::<(), _>(NetError::Timeout)))
+        };
+        let first = jitter(7);
+        assert_eq!(first.len(), 4);
+        assert_eq!(first, jitter(7));
+        assert_ne!(first, jitter(8));
+        assert!(first.iter().all(|d| *d >= ms(100) && *d <= ms(2_000)));
+    }
+
+    #[test]
+    fn jitter_resets_between_runs() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let mut r = Retrier::new(
+            Box::new(DecorrelatedJitter::from_config(&config(), ms(100), ms(2_000))),
+            clock,
+            Box::new(SeededRng::new(7)),
+        );
+        let first = delays(&r.run(classify, |_| Err::<(), _>(NetError::Timeout)));
+        let second = delays(&r.run(classify, |_| Err::<(), _>(NetError::Timeout)));
+        // 随机数继续前进，但第一次等待的上限重新从 base * 3 开始
+        assert!(first[0] <= ms(300) && second[0] <= ms(300));
+    }
+
+    #[test]
+    fn deadline_stops_before_timeout() {
+        let config = config();
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let inner = Box::new(ExponentialBackoff::from_config(&config, ms(500), ms(10_000)));
+        let mut r = retrier(Box::new(DeadlineBounded::from_config(&config, inner)), &clock, 7);
+        let report = r.run(classify, |_| Err::<(), _>(NetError::Timeout));
+        // 等待 500、1000 后已过 1.5 秒，再等 2 秒会超过 3 秒的期限
+        assert_eq!(delays(&report), [ms(500), ms(1000)]);
+        assert_eq!(report.outcome.unwrap_err().1, GiveUp::DeadlineExceeded);
+        assert!(clock.now() < config.timeout());
+    }
+
+    #[test]
+    fn seeded_rng_is_deterministic() {
+        let mut a = SeededRng::new(42);
+        let mut b = SeededRng::new(42);
+        let first: Vec<_> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        // 种子 0 不会让 xorshift 卡在 0
+        assert_ne!(SeededRng::new(0).next_u64(), 0);
+        for _ in 0..100 {
+            let d = a.between(ms(10), ms(20));
+            assert!(d >= ms(10) && d <= ms(20));
+        }
+        assert_eq!(a.between(ms(20), ms(10)), ms(20));
+    }
+
+    #[test]
+    fn config_limits_are_used() {
+        let default = Config::default();
+        let strategy = FixedDelay::from_config(&default, ms(1));
+        assert_eq!(strategy.max_retries, 3);
+        let bounded = DeadlineBounded::from_config(&default, Box::new(strategy));
+        assert_eq!(bounded.deadline, Duration::from_secs(5));
+    }
+}
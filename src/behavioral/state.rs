@@ -7,83 +7,449 @@
 //!     Voted：已经投票
 //!     Locked：投票已锁定，无法更改
 //! 每个状态下有不同的行为，比如只有在 NotVoted 状态下可以投票。
+//! 管理员可以开放投票并设置截止时间、关闭投票、凭授权重新开放投票；
+//! 截止时间由可注入的时钟判断，到期后投票机自动锁定。
+//! 每个状态对象自己决定各个操作之后进入哪个状态；next_status 把枚举换成状态对象再询问它，
+//! behavioral::visitor、election 等模块都通过它或 Poll 使用同一套规则。
+//! State 是按事件和上下文泛型定义的状态 trait，投票状态通过 VotingState 自动实现它，
+//! behavioral::hierarchical_state 在 State 之上组合出嵌套状态。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::behavioral::clock::{Clock, ManualClock, SystemClock};
+use crate::behavioral::visitor::Visitable;
+
+//-----------------共享的迁移模型---------------------------------------------------------------------------------
+/// 投票机的状态，state 与 visitor 两个模块共用
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VoteStatus {
+    NotVoted,
+    Voted,
+    Locked,
+}
+
+impl VoteStatus {
+    /// 对应的状态对象
+    pub fn state(self) -> Box<dyn VotingState> {
+        match self {
+            VoteStatus::NotVoted => Box::new(NotVoted),
+            VoteStatus::Voted => Box::new(Voted),
+            VoteStatus::Locked => Box::new(Locked),
+        }
+    }
+}
+
+/// 会引起状态变化的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VoteOperation {
+    /// 投票
+    Vote,
+    /// 管理员关闭投票
+    ClosePoll,
+    /// 管理员重新开放投票（授权由调用方检查）
+    ReopenPoll,
+    /// 到达投票截止时间
+    DeadlinePassed,
+}
+
+/// 迁移规则：由 current 对应的状态对象决定操作之后的新状态；
+/// 不允许的操作返回原因，状态保持不变
+pub fn next_status(current: VoteStatus, operation: VoteOperation, has_voted: bool) -> Result<VoteStatus, String> {
+    current.state().apply(operation, has_voted).map(|next| next.kind())
+}
 
 //-----------------定义状态trait 和 枚举---------------------------------------------------------------------------------
-// 定义状态的 trait
-trait VotingState {
-    // 投票
-    fn vote(self: Box<Self>) -> Box<dyn VotingState>;
+// 定义状态的 trait：每个操作返回新的状态对象，不允许的操作返回原因
+// 接受访问者由 Visitable 提供，见 behavioral::visitor
+pub trait VotingState: Visitable + Send + Sync {
+    // 在迁移模型中对应的状态
+    fn kind(&self) -> VoteStatus;
     // 状态
     fn status(&self);
+    // 投票
+    fn vote(&self) -> Result<Box<dyn VotingState>, String>;
+    // 管理员关闭投票
+    fn close(&self) -> Result<Box<dyn VotingState>, String>;
+    // 到达截止时间
+    fn deadline_passed(&self) -> Box<dyn VotingState>;
+    // 重新开放投票，has_voted 表示锁定前是否已经投过票
+    fn reopen(&self, has_voted: bool) -> Result<Box<dyn VotingState>, String>;
+
+    // 开放投票（设置截止时间），状态不变
+    fn open(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    // 按操作分派到上面的方法
+    fn apply(&self, operation: VoteOperation, has_voted: bool) -> Result<Box<dyn VotingState>, String> {
+        match operation {
+            VoteOperation::Vote => self.vote(),
+            VoteOperation::ClosePoll => self.close(),
+            VoteOperation::DeadlinePassed => Ok(self.deadline_passed()),
+            VoteOperation::ReopenPoll => self.reopen(has_voted),
+        }
+    }
+}
+
+//-----------------通用状态 trait---------------------------------------------------------------------------------
+/// 状态对事件的响应
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    /// 处理了事件，不发生迁移
+    Handled,
+    /// 处理了事件，迁移到指定名称的状态
+    Transition(&'static str),
+    /// 不处理，交给父状态
+    Ignored,
+}
+
+/// 通用状态 trait：E 为事件类型，C 为状态机携带的上下文
+pub trait State<E, C> {
+    /// 状态名称，在整个状态机中唯一
+    fn name(&self) -> &'static str;
+    /// 进入状态时执行
+    fn on_entry(&mut self, _context: &mut C) {}
+    /// 离开状态时执行
+    fn on_exit(&mut self, _context: &mut C) {}
+    /// 处理事件
+    fn handle(&mut self, event: &E, context: &mut C) -> Response;
+}
+
+/// 状态名称，与 VoteStatus 的 Debug 输出一致
+fn status_name(status: VoteStatus) -> &'static str {
+    match status {
+        VoteStatus::NotVoted => "NotVoted",
+        VoteStatus::Voted => "Voted",
+        VoteStatus::Locked => "Locked",
+    }
+}
+
+/// 每个投票状态都是事件为 VoteOperation、上下文为“是否已经投过票”的通用状态：
+/// 迁移规则仍由 apply 决定，不允许的操作不处理，交给父状态
+impl<T: VotingState> State<VoteOperation, bool> for T {
+    fn name(&self) -> &'static str {
+        status_name(self.kind())
+    }
+
+    fn handle(&mut self, event: &VoteOperation, has_voted: &mut bool) -> Response {
+        match self.apply(*event, *has_voted) {
+            Ok(next) => {
+                if *event == VoteOperation::Vote {
+                    *has_voted = true;
+                }
+                if next.kind() == self.kind() {
+                    Response::Handled
+                } else {
+                    Response::Transition(status_name(next.kind()))
+                }
+            }
+            Err(_) => Response::Ignored,
+        }
+    }
 }
 
 // 定义状态枚举
 /// 未投票状态
-struct NotVoted;
+#[derive(Debug)]
+pub struct NotVoted;
 /// 已投票状态
-struct Voted;
+#[derive(Debug)]
+pub struct Voted;
 /// 锁定状态
-struct Locked;
+#[derive(Debug)]
+pub struct Locked;
 
 /// 未投票状态 实现 VotingState trait
-/// 实现投票行为，并返回已投票状态
 impl VotingState for NotVoted {
-    /// 投票操作：当前状态可以投票，投票后转为 Voted 状态
-    fn vote(self: Box<Self>) -> Box<dyn VotingState> {
-        println!("You voted successfully!");
-        Box::new(Voted)
+    fn kind(&self) -> VoteStatus {
+        VoteStatus::NotVoted
     }
 
     fn status(&self) {
         println!("Current state: NotVoted");
     }
+
+    /// 投票操作：当前状态可以投票，投票后转为 Voted 状态
+    fn vote(&self) -> Result<Box<dyn VotingState>, String> {
+        Ok(Box::new(Voted))
+    }
+
+    fn close(&self) -> Result<Box<dyn VotingState>, String> {
+        Ok(Box::new(Locked))
+    }
+
+    fn deadline_passed(&self) -> Box<dyn VotingState> {
+        Box::new(Locked)
+    }
+
+    fn reopen(&self, _: bool) -> Result<Box<dyn VotingState>, String> {
+        Err("Poll is not closed.".to_string())
+    }
 }
 
 /// 已投票状态 实现 VotingState trait
-/// 实现投票行为，并返回锁定状态
 impl VotingState for Voted {
-    /// 投票操作：当前状态已经投票，无法再次投票，转为 Locked 状态
-    fn vote(self: Box<Self>) -> Box<dyn VotingState> {
-        println!("You have already voted.");
-        self
+    fn kind(&self) -> VoteStatus {
+        VoteStatus::Voted
     }
+
     fn status(&self) {
         println!("Current state: Voted");
     }
+
+    /// 投票操作：当前状态已经投票，无法再次投票
+    fn vote(&self) -> Result<Box<dyn VotingState>, String> {
+        Err("You have already voted.".to_string())
+    }
+
+    fn close(&self) -> Result<Box<dyn VotingState>, String> {
+        Ok(Box::new(Locked))
+    }
+
+    fn deadline_passed(&self) -> Box<dyn VotingState> {
+        Box::new(Locked)
+    }
+
+    fn reopen(&self, _: bool) -> Result<Box<dyn VotingState>, String> {
+        Err("Poll is not closed.".to_string())
+    }
 }
 
 /// 锁定状态 实现 VotingState trait
 impl VotingState for Locked {
-    /// 投票操作：当前状态已锁定，无法投票
-    fn vote(self: Box<Self>) -> Box<dyn VotingState> {
-        println!("Voting is locked, you cannot vote anymore.");
-        self
+    fn kind(&self) -> VoteStatus {
+        VoteStatus::Locked
     }
 
     fn status(&self) {
         println!("Current state: Locked");
     }
+
+    /// 投票操作：当前状态已锁定，无法投票
+    fn vote(&self) -> Result<Box<dyn VotingState>, String> {
+        Err("Voting is locked, you cannot vote anymore.".to_string())
+    }
+
+    fn close(&self) -> Result<Box<dyn VotingState>, String> {
+        Err("Poll is already closed.".to_string())
+    }
+
+    fn deadline_passed(&self) -> Box<dyn VotingState> {
+        Box::new(Locked)
+    }
+
+    /// 重新开放后回到锁定前的状态，已经投过的票仍然有效
+    fn reopen(&self, has_voted: bool) -> Result<Box<dyn VotingState>, String> {
+        if has_voted {
+            Ok(Box::new(Voted))
+        } else {
+            Ok(Box::new(NotVoted))
+        }
+    }
+
+    fn open(&self) -> Result<(), String> {
+        Err("Poll is closed, reopen it with authorization.".to_string())
+    }
+}
+
+//-----------------投票活动---------------------------------------------------------------------
+/// 投票活动：记录是否已经投票、截止时间和管理员授权，检查通过后交给状态对象完成迁移
+pub struct Poll {
+    has_voted: bool,
+    deadline: Option<Duration>,
+    /// 管理员口令，为 None 时不能重新开放投票
+    admin_token: Option<String>,
+    clock: Arc<dyn Clock>,
 }
+
+impl Poll {
+    /// 创建投票活动，没有截止时间
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Poll { has_voted: false, deadline: None, admin_token: None, clock }
+    }
+
+    /// 设置管理员口令
+    pub fn with_admin_token(mut self, token: &str) -> Self {
+        self.admin_token = Some(token.to_string());
+        self
+    }
+
+    /// 截止时间（时钟读数）
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    /// 是否已经投过票
+    pub fn has_voted(&self) -> bool {
+        self.has_voted
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// 截止时间到了且尚未锁定时返回锁定后的状态
+    pub fn expire(&self, current: &dyn VotingState) -> Option<Box<dyn VotingState>> {
+        match self.deadline {
+            Some(deadline) if current.kind() != VoteStatus::Locked && self.clock.now() >= deadline => {
+                println!("Poll deadline passed, voting is locked.");
+                Some(current.deadline_passed())
+            }
+            _ => None,
+        }
+    }
+
+    /// 投票
+    pub fn vote(&mut self, current: &dyn VotingState) -> Result<Box<dyn VotingState>, String> {
+        let next = current.vote()?;
+        self.has_voted = true;
+        Ok(next)
+    }
+
+    /// 开放投票并设置截止时间（None 表示不限时）；已关闭的投票需要 reopen
+    pub fn open(&mut self, current: &dyn VotingState, deadline: Option<Duration>) -> Result<(), String> {
+        current.open()?;
+        self.check_deadline(deadline)?;
+        self.deadline = deadline;
+        Ok(())
+    }
+
+    /// 关闭投票
+    pub fn close(&mut self, current: &dyn VotingState) -> Result<Box<dyn VotingState>, String> {
+        current.close()
+    }
+
+    /// 凭管理员口令重新开放投票，并设置新的截止时间
+    pub fn reopen(&mut self, current: &dyn VotingState, token: &str, deadline: Option<Duration>) -> Result<Box<dyn VotingState>, String> {
+        if self.admin_token.as_deref() != Some(token) {
+            return Err("Not authorized to reopen the poll.".to_string());
+        }
+        self.check_deadline(deadline)?;
+        let next = current.reopen(self.has_voted)?;
+        self.deadline = deadline;
+        Ok(next)
+    }
+
+    // 新的截止时间必须晚于当前时间
+    fn check_deadline(&self, deadline: Option<Duration>) -> Result<(), String> {
+        match deadline {
+            Some(deadline) if deadline <= self.clock.now() => Err("Deadline is already in the past.".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
 //-----------------定义上下文结构体---------------------------------------------------------------------
-/// 投票机器
+/// 投票机
 pub struct VotingMachine {
     state: Box<dyn VotingState>,
+    poll: Poll,
+    /// 进入当前状态时的时钟读数
+    entered_at: Duration,
+    /// 离开各状态时累计的停留时间，按 NotVoted、Voted、Locked 排列
+    spent: [Duration; 3],
+}
+
+/// 状态在 spent 中的下标
+fn slot(status: VoteStatus) -> usize {
+    match status {
+        VoteStatus::NotVoted => 0,
+        VoteStatus::Voted => 1,
+        VoteStatus::Locked => 2,
+    }
 }
 
 impl VotingMachine {
-    /// 创建投票机器
+    /// 创建投票机，使用系统时钟，没有截止时间，也没有管理员
     pub fn new() -> Self {
+        VotingMachine::with_poll(Poll::new(Arc::new(SystemClock)))
+    }
+
+    /// 使用指定的投票活动创建投票机
+    pub fn with_poll(poll: Poll) -> Self {
+        let entered_at = poll.clock().now();
         VotingMachine {
             state: Box::new(NotVoted),
+            poll,
+            entered_at,
+            spent: [Duration::ZERO; 3],
+        }
+    }
+
+    /// 切换到新状态，并累计在旧状态停留的时间
+    fn set_state(&mut self, next: Box<dyn VotingState>) {
+        if next.kind() != self.state.kind() {
+            let now = self.poll.clock().now();
+            self.spent[slot(self.state.kind())] += now.saturating_sub(self.entered_at);
+            self.entered_at = now;
+            self.state = next;
         }
     }
+
+    /// 在 status 状态累计停留的时间，包括当前状态已经停留的时间
+    pub fn time_in(&self, status: VoteStatus) -> Duration {
+        let mut spent = self.spent[slot(status)];
+        if status == self.state.kind() {
+            spent += self.poll.clock().now().saturating_sub(self.entered_at);
+        }
+        spent
+    }
+
+    pub fn poll(&self) -> &Poll {
+        &self.poll
+    }
+
+    /// 当前的状态对象
+    pub fn current(&self) -> &dyn VotingState {
+        self.state.as_ref()
+    }
+
+    /// 检查截止时间，到期则自动锁定；返回是否已锁定
+    pub fn tick(&mut self) -> bool {
+        if let Some(next) = self.poll.expire(self.state.as_ref()) {
+            self.set_state(next);
+        }
+        self.state.kind() == VoteStatus::Locked
+    }
+
     /// 投票操作
     pub fn vote(&mut self) {
-        // 调用当前状态的投票方法，并更新状态
-        // 因为在调用 vote 方法时, 需要移动，所以这里用了获取所有权的方式
-        let old_state = std::mem::replace(&mut self.state, Box::new(NotVoted)); // 临时占位
-        let new_state = old_state.vote(); // 调用 vote 获取新状态
-        self.state = new_state; // 更新为新状态
+        self.tick();
+        match self.poll.vote(self.state.as_ref()) {
+            Ok(next) => {
+                println!("You voted successfully!");
+                self.set_state(next);
+            }
+            Err(reason) => println!("{}", reason),
+        }
+    }
+
+    /// 管理员开放投票，并设置截止时间
+    pub fn open_poll(&mut self, deadline: Option<Duration>) -> Result<(), String> {
+        self.tick();
+        self.poll.open(self.state.as_ref(), deadline)
+    }
+
+    /// 管理员关闭投票
+    pub fn close_poll(&mut self) -> Result<(), String> {
+        self.tick();
+        let next = self.poll.close(self.state.as_ref())?;
+        self.set_state(next);
+        Ok(())
+    }
+
+    /// 管理员凭口令重新开放投票
+    pub fn reopen_poll(&mut self, token: &str, deadline: Option<Duration>) -> Result<(), String> {
+        self.tick();
+        let next = self.poll.reopen(self.state.as_ref(), token, deadline)?;
+        self.set_state(next);
+        Ok(())
+    }
+
+    /// 当前状态
+    pub fn state(&self) -> VoteStatus {
+        self.state.kind()
     }
 
     /// 获取当前状态
@@ -91,8 +457,14 @@ impl VotingMachine {
         self.state.status();
     }
 }
+
+impl Default for VotingMachine {
+    fn default() -> Self {
+        VotingMachine::new()
+    }
+}
 //--------------------------------------------------------------------------------------------------
-fn main() {
+pub fn main() {
     // 创建投票机器
     let mut machine = VotingMachine::new();
 
@@ -105,7 +477,90 @@ fn main() {
     // 再次投票，应该提示已经投票
     machine.vote();
 
-    // 尝试在已投票状态下再次投票，应该转为 Locked 状态
+    // 管理员关闭投票，转为 Locked 状态
+    machine.close_poll().unwrap();
     machine.vote();
     machine.status();
+
+    // 截止时间到期后自动锁定
+    let clock = Arc::new(ManualClock::new(Duration::ZERO));
+    let poll = Poll::new(clock.clone()).with_admin_token("secret");
+    let mut machine = VotingMachine::with_poll(poll);
+    machine.open_poll(Some(Duration::from_secs(60))).unwrap();
+    clock.advance(Duration::from_secs(61));
+    machine.vote(); // Poll deadline passed, voting is locked.
+    machine.status();
+
+    // 没有授权不能重新开放
+    println!("{:?}", machine.reopen_poll("guess", None));
+    machine.reopen_poll("secret", Some(Duration::from_secs(120))).unwrap();
+    machine.vote();
+    machine.status();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_status, ManualClock, Poll, VoteOperation::*, VoteStatus::*, VotingMachine};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn state_objects_define_every_transition() {
+        let table = [
+            (NotVoted, Vote, Ok(Voted)),
+            (NotVoted, ClosePoll, Ok(Locked)),
+            (NotVoted, DeadlinePassed, Ok(Locked)),
+            (NotVoted, ReopenPoll, Err("Poll is not closed.")),
+            (Voted, Vote, Err("You have already voted.")),
+            (Voted, ClosePoll, Ok(Locked)),
+            (Voted, DeadlinePassed, Ok(Locked)),
+            (Voted, ReopenPoll, Err("Poll is not closed.")),
+            (Locked, Vote, Err("Voting is locked, you cannot vote anymore.")),
+            (Locked, ClosePoll, Err("Poll is already closed.")),
+            (Locked, DeadlinePassed, Ok(Locked)),
+        ];
+        for (current, operation, expected) in table {
+            assert_eq!(next_status(current, operation, false), expected.map_err(str::to_string), "{:?} + {:?}", current, operation);
+        }
+        // 重新开放后回到锁定前的状态
+        assert_eq!(next_status(Locked, ReopenPoll, true), Ok(Voted));
+        assert_eq!(next_status(Locked, ReopenPoll, false), Ok(NotVoted));
+    }
+
+    #[test]
+    fn deadline_locks_and_admin_reopens() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let mut machine = VotingMachine::with_poll(Poll::new(clock.clone()).with_admin_token("secret"));
+        machine.open_poll(Some(Duration::from_secs(60))).unwrap();
+        clock.advance(Duration::from_secs(61));
+        machine.vote();
+        assert_eq!(machine.state(), Locked);
+        assert!(!machine.poll().has_voted());
+
+        assert_eq!(machine.open_poll(None), Err("Poll is closed, reopen it with authorization.".to_string()));
+        assert_eq!(machine.reopen_poll("guess", None), Err("Not authorized to reopen the poll.".to_string()));
+        assert_eq!(machine.reopen_poll("secret", Some(Duration::from_secs(10))), Err("Deadline is already in the past.".to_string()));
+        machine.reopen_poll("secret", Some(Duration::from_secs(120))).unwrap();
+        assert_eq!(machine.state(), NotVoted);
+        machine.vote();
+        assert_eq!(machine.state(), Voted);
+
+        machine.close_poll().unwrap();
+        machine.reopen_poll("secret", None).unwrap();
+        assert_eq!(machine.state(), Voted);
+    }
+
+    #[test]
+    fn time_in_state_accumulates() {
+        let clock = Arc::new(ManualClock::new(Duration::ZERO));
+        let mut machine = VotingMachine::with_poll(Poll::new(clock.clone()));
+        clock.advance(Duration::from_secs(5));
+        machine.vote();
+        clock.advance(Duration::from_secs(3));
+        machine.vote(); // 已经投过票，状态不变
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(machine.time_in(NotVoted), Duration::from_secs(5));
+        assert_eq!(machine.time_in(Voted), Duration::from_secs(5));
+        assert_eq!(machine.time_in(Locked), Duration::ZERO);
+    }
 }
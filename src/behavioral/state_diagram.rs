@@ -0,0 +1,192 @@
+//! 状态机导出为图
+//! 迁移表是数据，可以直接生成 Graphviz DOT 和 Mermaid stateDiagram 文本：
+//! 状态为节点，迁移为边，边上标注 "事件 [守卫]"，当前状态高亮显示。
+//! 审阅时看图即可理解流程，不必再去各个 impl 块里读 vote() 的实现。
+
+use std::fmt;
+
+use crate::behavioral::state_machine::{voting_machine, StateMachine, VoteEvent};
+
+/// 边上的标签：事件，有守卫时追加 "[守卫]"
+fn edge_label(event: &str, guard: Option<&str>) -> String {
+    match guard {
+        Some(guard) => format!("{} [{}]", event, guard),
+        None => event.to_string(),
+    }
+}
+
+/// DOT 字符串中的转义
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Mermaid 状态 id 只能包含字母、数字和下划线
+fn mermaid_id(text: &str) -> String {
+    text.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+/// 为每个状态分配不重复的 Mermaid id：替换字符后与前面的状态重名时追加 _2、_3……
+fn mermaid_ids<S: fmt::Debug>(states: &[S]) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::with_capacity(states.len());
+    for state in states {
+        let base = mermaid_id(&format!("{:?}", state));
+        let mut id = base.clone();
+        let mut n = 1;
+        while ids.contains(&id) {
+            n += 1;
+            id = format!("{}_{}", base, n);
+        }
+        ids.push(id);
+    }
+    ids
+}
+
+/// 生成 Graphviz DOT 文本
+pub fn to_dot<S, E, C>(machine: &StateMachine<S, E, C>, name: &str) -> String
+where
+    S: Copy + PartialEq + fmt::Debug,
+    E: Copy + PartialEq + fmt::Debug,
+{
+    let mut out = format!("digraph \"{}\" {{\n", escape_dot(name));
+    out.push_str("    rankdir=LR;\n");
+    out.push_str("    node [shape=box, style=rounded];\n");
+    out.push_str("    __start [shape=point];\n");
+    for state in machine.states() {
+        let id = escape_dot(&format!("{:?}", state));
+        if *state == machine.state() {
+            out.push_str(&format!("    \"{}\" [style=\"rounded,filled,bold\", fillcolor=lightblue];\n", id));
+        } else {
+            out.push_str(&format!("    \"{}\";\n", id));
+        }
+    }
+    out.push_str(&format!("    __start -> \"{}\";\n", escape_dot(&format!("{:?}", machine.initial()))));
+    for transition in machine.transitions() {
+        out.push_str(&format!(
+            "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+            escape_dot(&format!("{:?}", transition.from())),
+            escape_dot(&format!("{:?}", transition.to())),
+            escape_dot(&edge_label(&format!("{:?}", transition.event()), transition.guard_name()))
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// 生成 Mermaid stateDiagram 文本
+pub fn to_mermaid<S, E, C>(machine: &StateMachine<S, E, C>) -> String
+where
+    S: Copy + PartialEq + fmt::Debug,
+    E: Copy + PartialEq + fmt::Debug,
+{
+    let states = machine.states();
+    let ids = mermaid_ids(states);
+    let id_of = |state: S| {
+        let index = states.iter().position(|s| *s == state).expect("the builder declares every state");
+        ids[index].as_str()
+    };
+
+    let mut out = String::from("stateDiagram-v2\n");
+    for (state, id) in states.iter().zip(&ids) {
+        let label = format!("{:?}", state);
+        if *id != label {
+            out.push_str(&format!("    state \"{}\" as {}\n", label.replace('"', "'"), id));
+        }
+    }
+    out.push_str(&format!("    [*] --> {}\n", id_of(machine.initial())));
+    for transition in machine.transitions() {
+        // 标签中的冒号会被 Mermaid 当作分隔符
+        let label = edge_label(&format!("{:?}", transition.event()), transition.guard_name()).replace(':', "#58;");
+        out.push_str(&format!("    {} --> {} : {}\n", id_of(transition.from()), id_of(transition.to()), label));
+    }
+    out.push_str("    classDef current fill:#add8e6,stroke-width:2px\n");
+    out.push_str(&format!("    class {} current\n", id_of(machine.state())));
+    out
+}
+
+//--------------------------------------------------------------------------------------------------
+/// 自定义状态机示例：带守卫的文档审批
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Doc {
+    Draft,
+    Review,
+    Published,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DocEvent {
+    Submit,
+    Approve,
+    Reject,
+}
+
+pub fn main() {
+    let mut machine = voting_machine();
+    machine.fire(VoteEvent::Vote).unwrap();
+    println!("{}", to_dot(&machine, "VotingMachine"));
+    println!("{}", to_mermaid(&machine));
+
+    let document = crate::behavioral::state_machine::StateMachineBuilder::new(Doc::Draft)
+        .transition(Doc::Draft, DocEvent::Submit, Doc::Review)
+        .transition(Doc::Review, DocEvent::Approve, Doc::Published)
+        .guard("reviewers >= 2", |reviewers: &u32| *reviewers >= 2)
+        .transition(Doc::Review, DocEvent::Reject, Doc::Draft)
+        .build(0u32);
+    println!("{}", to_mermaid(&document));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavioral::state_machine::StateMachineBuilder;
+
+    /// Debug 输出就是名称本身的状态，用来构造需要转义或会重名的标签
+    #[derive(Clone, Copy, PartialEq)]
+    struct Named(&'static str);
+
+    impl fmt::Debug for Named {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    #[test]
+    fn dot_lists_states_edges_guards_and_highlights_the_current_state() {
+        let mut machine = voting_machine();
+        machine.fire(VoteEvent::Vote).unwrap();
+        let dot = to_dot(&machine, "Voting \"v2\"");
+        assert!(dot.starts_with("digraph \"Voting \\\"v2\\\"\" {\n"));
+        assert!(dot.contains("    __start -> \"NotVoted\";\n"));
+        assert!(dot.contains("    \"Voted\" [style=\"rounded,filled,bold\", fillcolor=lightblue];\n"));
+        assert!(dot.contains("    \"NotVoted\";\n"));
+        assert!(dot.contains("    \"NotVoted\" -> \"Voted\" [label=\"Vote\"];\n"));
+        assert!(dot.contains("    \"Locked\" -> \"Voted\" [label=\"ReopenPoll [has voted]\"];\n"));
+        assert_eq!(dot.matches(" -> ").count(), machine.transitions().len() + 1);
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn mermaid_lists_edges_guards_and_highlights_the_current_state() {
+        let machine = voting_machine();
+        let mermaid = to_mermaid(&machine);
+        assert!(mermaid.starts_with("stateDiagram-v2\n    [*] --> NotVoted\n"));
+        assert!(mermaid.contains("    NotVoted --> Voted : Vote\n"));
+        assert!(mermaid.contains("    Locked --> NotVoted : ReopenPoll [not voted]\n"));
+        assert!(mermaid.ends_with("    class NotVoted current\n"));
+        assert!(!mermaid.contains("state \""));
+    }
+
+    #[test]
+    fn mermaid_ids_stay_unique_after_sanitizing() {
+        let machine = StateMachineBuilder::new(Named("ready state"))
+            .transition(Named("ready state"), Named("go: now"), Named("ready-state"))
+            .transition(Named("ready-state"), Named("back"), Named("ready_state"))
+            .build(());
+        let mermaid = to_mermaid(&machine);
+        assert!(mermaid.contains("    state \"ready state\" as ready_state\n"));
+        assert!(mermaid.contains("    state \"ready-state\" as ready_state_2\n"));
+        assert!(mermaid.contains("    state \"ready_state\" as ready_state_3\n"));
+        assert!(mermaid.contains("    ready_state --> ready_state_2 : go#58; now\n"));
+        assert!(mermaid.contains("    ready_state_2 --> ready_state_3 : back\n"));
+        assert!(mermaid.ends_with("    class ready_state current\n"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,479 @@
+//! 声明式状态机
+//! behavioral::state 中每个迁移都手写在 `impl VotingState for ...` 里，整体流程要翻遍各个 impl 才能看清，
+//! 没有定义的操作也只是打印一句话然后原地不动。
+//! 这里把状态和事件写成迁移表：每一行是 "状态 + 事件 => 新状态"，可以附带守卫条件和迁移动作，
+//! 每个状态可以有进入/退出动作，只在状态真正改变时执行，自迁移（A + 事件 => A）只执行迁移动作；
+//! 对未定义的迁移返回错误，而不是悄悄停在原状态。
+//! 迁移表既可以用 StateMachineBuilder 链式构建，也可以用 state_machine! 宏书写。
+
+use std::error::Error;
+use std::fmt;
+
+/// 守卫条件：根据上下文判断迁移能否发生
+pub type Guard<C> = Box<dyn Fn(&C) -> bool + Send + Sync>;
+/// 动作：迁移时或进入/退出状态时修改上下文
+pub type Action<C> = Box<dyn Fn(&mut C) + Send + Sync>;
+
+//-----------------迁移与错误---------------------------------------------------------------------
+/// 迁移表中的一行
+pub struct Transition<S, E, C> {
+    from: S,
+    event: E,
+    to: S,
+    guard: Option<(String, Guard<C>)>,
+    action: Option<Action<C>>,
+}
+
+impl<S: Copy, E: Copy, C> Transition<S, E, C> {
+    pub fn from(&self) -> S {
+        self.from
+    }
+
+    pub fn event(&self) -> E {
+        self.event
+    }
+
+    pub fn to(&self) -> S {
+        self.to
+    }
+
+    /// 守卫条件的名称，没有守卫时为 None
+    pub fn guard_name(&self) -> Option<&str> {
+        self.guard.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    /// 在给定上下文下守卫是否通过，没有守卫时总是通过
+    pub fn guard_passes(&self, context: &C) -> bool {
+        self.guard.as_ref().is_none_or(|(_, guard)| guard(context))
+    }
+}
+
+/// 选择迁移时的一次守卫检查
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuardCheck {
+    pub name: String,
+    pub passed: bool,
+}
+
+/// 一次触发的结果，以及选择迁移时按顺序检查过的守卫；每个守卫只求值一次
+#[derive(Debug, Clone, PartialEq)]
+pub struct Firing<S, E> {
+    pub result: Result<S, TransitionError<S, E>>,
+    pub guards: Vec<GuardCheck>,
+}
+
+/// 触发事件失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransitionError<S, E> {
+    /// 当前状态下没有为该事件定义迁移
+    Undefined { state: S, event: E },
+    /// 定义了迁移，但所有守卫条件都没有通过
+    GuardRejected { state: S, event: E, guards: Vec<String> },
+}
+
+impl<S: fmt::Debug, E: fmt::Debug> fmt::Display for TransitionError<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionError::Undefined { state, event } => {
+                write!(f, "no transition defined for event {:?} in state {:?}", event, state)
+            }
+            TransitionError::GuardRejected { state, event, guards } => write!(
+                f,
+                "event {:?} in state {:?} rejected by guard(s): {}",
+                event,
+                state,
+                guards.join(", ")
+            ),
+        }
+    }
+}
+
+impl<S: fmt::Debug, E: fmt::Debug> Error for TransitionError<S, E> {}
+
+//-----------------状态机---------------------------------------------------------------------
+/// 由迁移表驱动的状态机，C 为状态机携带的上下文数据
+pub struct StateMachine<S, E, C = ()> {
+    initial: S,
+    state: S,
+    context: C,
+    /// 声明过的全部状态，按声明顺序
+    states: Vec<S>,
+    transitions: Vec<Transition<S, E, C>>,
+    entry_actions: Vec<(S, Action<C>)>,
+    exit_actions: Vec<(S, Action<C>)>,
+}
+
+impl<S, E, C> StateMachine<S, E, C>
+where
+    S: Copy + PartialEq + fmt::Debug,
+    E: Copy + PartialEq + fmt::Debug,
+{
+    /// 初始状态
+    pub fn initial(&self) -> S {
+        self.initial
+    }
+
+    /// 当前状态
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    pub fn context(&self) -> &C {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut C {
+        &mut self.context
+    }
+
+    /// 声明过的全部状态
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    /// 迁移表
+    pub fn transitions(&self) -> &[Transition<S, E, C>] {
+        &self.transitions
+    }
+
+    /// 直接把状态机置于指定的状态和上下文，不执行任何动作，供模型检查等工具探索状态空间
+    pub(crate) fn restore(&mut self, state: S, context: C) {
+        self.state = state;
+        self.context = context;
+    }
+
+    /// 当前状态下 event 会选中的迁移：按声明顺序取第一条守卫通过的；
+    /// 检查过的守卫按顺序追加到 guards
+    fn select(&self, event: E, guards: &mut Vec<GuardCheck>) -> Result<usize, TransitionError<S, E>> {
+        for (i, transition) in self.transitions.iter().enumerate() {
+            if transition.from != self.state || transition.event != event {
+                continue;
+            }
+            let passed = transition.guard_passes(&self.context);
+            if let Some(name) = transition.guard_name() {
+                guards.push(GuardCheck { name: name.to_string(), passed });
+            }
+            if passed {
+                return Ok(i);
+            }
+        }
+        if guards.is_empty() {
+            Err(TransitionError::Undefined { state: self.state, event })
+        } else {
+            let rejected = guards.iter().map(|check| check.name.clone()).collect();
+            Err(TransitionError::GuardRejected { state: self.state, event, guards: rejected })
+        }
+    }
+
+    /// 当前状态下能否触发 event
+    pub fn can_fire(&self, event: E) -> bool {
+        self.select(event, &mut Vec::new()).is_ok()
+    }
+
+    /// 触发事件：依次执行旧状态的退出动作、迁移动作、新状态的进入动作，返回新状态；
+    /// 自迁移没有离开状态，只执行迁移动作
+    pub fn fire(&mut self, event: E) -> Result<S, TransitionError<S, E>> {
+        self.fire_traced(event).result
+    }
+
+    /// 与 fire 相同，同时返回选择迁移时检查过的守卫，供迁移日志等工具记录
+    pub fn fire_traced(&mut self, event: E) -> Firing<S, E> {
+        let mut guards = Vec::new();
+        let result = self.select(event, &mut guards).map(|index| self.apply(index));
+        Firing { result, guards }
+    }
+
+    /// 执行第 index 条迁移，返回新状态
+    fn apply(&mut self, index: usize) -> S {
+        let (from, to) = (self.transitions[index].from, self.transitions[index].to);
+        let changed = from != to;
+
+        if changed {
+            for (_, action) in self.exit_actions.iter().filter(|(state, _)| *state == from) {
+                action(&mut self.context);
+            }
+        }
+        if let Some(action) = &self.transitions[index].action {
+            action(&mut self.context);
+        }
+        self.state = to;
+        if changed {
+            for (_, action) in self.entry_actions.iter().filter(|(state, _)| *state == to) {
+                action(&mut self.context);
+            }
+        }
+        to
+    }
+}
+
+//-----------------构建器---------------------------------------------------------------------
+/// 状态机构建器
+pub struct StateMachineBuilder<S, E, C> {
+    initial: S,
+    states: Vec<S>,
+    transitions: Vec<Transition<S, E, C>>,
+    entry_actions: Vec<(S, Action<C>)>,
+    exit_actions: Vec<(S, Action<C>)>,
+}
+
+impl<S, E, C> StateMachineBuilder<S, E, C>
+where
+    S: Copy + PartialEq + fmt::Debug,
+    E: Copy + PartialEq + fmt::Debug,
+{
+    /// 以 initial 为初始状态开始构建
+    pub fn new(initial: S) -> Self {
+        StateMachineBuilder {
+            initial,
+            states: vec![initial],
+            transitions: Vec::new(),
+            entry_actions: Vec::new(),
+            exit_actions: Vec::new(),
+        }
+    }
+
+    /// 声明一个状态；出现在迁移表中的状态会自动声明
+    pub fn state(mut self, state: S) -> Self {
+        if !self.states.contains(&state) {
+            self.states.push(state);
+        }
+        self
+    }
+
+    /// 添加一行迁移
+    pub fn transition(self, from: S, event: E, to: S) -> Self {
+        let mut builder = self.state(from).state(to);
+        builder.transitions.push(Transition { from, event, to, guard: None, action: None });
+        builder
+    }
+
+    /// 为最近添加的迁移设置守卫条件
+    ///
+    /// # Panics
+    /// 还没有添加任何迁移时 panic
+    pub fn guard(mut self, name: &str, guard: impl Fn(&C) -> bool + Send + Sync + 'static) -> Self {
+        let transition = self.transitions.last_mut().expect("guard() must follow a transition()");
+        transition.guard = Some((name.to_string(), Box::new(guard)));
+        self
+    }
+
+    /// 为最近添加的迁移设置迁移动作
+    ///
+    /// # Panics
+    /// 还没有添加任何迁移时 panic
+    pub fn action(mut self, action: impl Fn(&mut C) + Send + Sync + 'static) -> Self {
+        let transition = self.transitions.last_mut().expect("action() must follow a transition()");
+        transition.action = Some(Box::new(action));
+        self
+    }
+
+    /// 进入 state 时执行的动作
+    pub fn on_entry(mut self, state: S, action: impl Fn(&mut C) + Send + Sync + 'static) -> Self {
+        self.entry_actions.push((state, Box::new(action)));
+        self.state(state)
+    }
+
+    /// 离开 state 时执行的动作
+    pub fn on_exit(mut self, state: S, action: impl Fn(&mut C) + Send + Sync + 'static) -> Self {
+        self.exit_actions.push((state, Box::new(action)));
+        self.state(state)
+    }
+
+    /// 使用给定上下文构建状态机
+    pub fn build(self, context: C) -> StateMachine<S, E, C> {
+        StateMachine {
+            initial: self.initial,
+            state: self.initial,
+            context,
+            states: self.states,
+            transitions: self.transitions,
+            entry_actions: self.entry_actions,
+            exit_actions: self.exit_actions,
+        }
+    }
+}
+
+/// 用迁移表书写状态机，展开为 StateMachineBuilder，之后可以继续添加进入/退出动作再 build
+/// ```
+/// use design_pattern::state_machine;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum Door { Closed, Open, Locked }
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum Action { Open, Close, Lock }
+/// struct Ctx { has_key: bool, locks: u32 }
+///
+/// let mut door = state_machine! {
+///     Door, Action;
+///     initial Closed;
+///     Closed + Open => Open;
+///     Open + Close => Closed;
+///     Closed + Lock => Locked, guard "has key" |c: &Ctx| c.has_key, action |c: &mut Ctx| c.locks += 1;
+/// }
+/// .build(Ctx { has_key: false, locks: 0 });
+///
+/// assert!(door.fire(Action::Lock).is_err());
+/// door.context_mut().has_key = true;
+/// assert_eq!(door.fire(Action::Lock), Ok(Door::Locked));
+/// assert_eq!(door.context().locks, 1);
+/// assert!(door.fire(Action::Open).is_err());
+/// ```
+#[macro_export]
+macro_rules! state_machine {
+    (
+        $state:ident, $event:ident;
+        initial $initial:ident;
+        $(
+            $from:ident + $on:ident => $to:ident
+            $(, guard $guard_name:literal $guard:expr)?
+            $(, action $action:expr)?
+        );* $(;)?
+    ) => {{
+        let builder = $crate::behavioral::state_machine::StateMachineBuilder::new($state::$initial);
+        $(
+            let builder = builder.transition($state::$from, $event::$on, $state::$to);
+            $( let builder = builder.guard($guard_name, $guard); )?
+            $( let builder = builder.action($action); )?
+        )*
+        builder
+    }};
+}
+
+//-----------------示例：用状态机重写投票机---------------------------------------------------------------------
+/// 投票机的状态和事件直接使用 behavioral::state 中共享的迁移模型
+pub use crate::behavioral::state::{VoteOperation as VoteEvent, VoteStatus as VoteState};
+
+/// behavioral::state::VotingMachine 的迁移表版本（与 next_status 一致），上下文记录是否已经投过票
+pub fn voting_machine() -> StateMachine<VoteState, VoteEvent, bool> {
+    state_machine! {
+        VoteState, VoteEvent;
+        initial NotVoted;
+        NotVoted + Vote => Voted, action |voted: &mut bool| {
+            *voted = true;
+            println!("You voted successfully!");
+        };
+        NotVoted + ClosePoll => Locked;
+        Voted + ClosePoll => Locked;
+        NotVoted + DeadlinePassed => Locked;
+        Voted + DeadlinePassed => Locked;
+        Locked + DeadlinePassed => Locked;
+        Locked + ReopenPoll => Voted, guard "has voted" |voted: &bool| *voted;
+        Locked + ReopenPoll => NotVoted, guard "not voted" |voted: &bool| !*voted;
+    }
+    .on_entry(VoteState::Locked, |_| println!("Voting is locked."))
+    .build(false)
+}
+
+//--------------------------------------------------------------------------------------------------
+/// 带上下文和守卫的示例：十字转门，投币后才能通过
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Turnstile {
+    Closed,
+    Open,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TurnstileEvent {
+    Coin,
+    Push,
+}
+
+#[derive(Default)]
+struct Till {
+    coins: u32,
+    passes: u32,
+    broken: bool,
+}
+
+pub fn main() {
+    let mut machine = voting_machine();
+    println!("{:?}", machine.state());
+    println!("{:?}", machine.fire(VoteEvent::Vote));
+    // 已经投过票：未定义的迁移返回错误，状态不变
+    match machine.fire(VoteEvent::Vote) {
+        Ok(state) => println!("unexpected: {:?}", state),
+        Err(e) => println!("Error: {}", e),
+    }
+    println!("{:?}", machine.fire(VoteEvent::ClosePoll));
+    println!("{:?}", machine.fire(VoteEvent::ReopenPoll));
+    assert_eq!(machine.state(), VoteState::Voted);
+    println!("{:?}", machine.fire(VoteEvent::ClosePoll));
+    assert_eq!(machine.state(), VoteState::Locked);
+
+    let mut turnstile = state_machine! {
+        Turnstile, TurnstileEvent;
+        initial Closed;
+        Closed + Coin => Open, guard "not broken" |till: &Till| !till.broken, action |till: &mut Till| till.coins += 1;
+        Open + Push => Closed, action |till: &mut Till| till.passes += 1;
+        Open + Coin => Open;
+    }
+    .on_exit(Turnstile::Open, |_| println!("gate closing"))
+    .build(Till::default());
+
+    turnstile.fire(TurnstileEvent::Coin).unwrap();
+    turnstile.fire(TurnstileEvent::Push).unwrap();
+    turnstile.context_mut().broken = true;
+    println!("{}", turnstile.fire(TurnstileEvent::Coin).unwrap_err());
+    println!("{}", turnstile.fire(TurnstileEvent::Push).unwrap_err());
+    assert_eq!((turnstile.context().coins, turnstile.context().passes), (1, 1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 记录每种动作执行的次数：(进入 Locked, 离开 Locked, 迁移动作)
+    fn counting_machine() -> StateMachine<VoteState, VoteEvent, (u32, u32, u32)> {
+        state_machine! {
+            VoteState, VoteEvent;
+            initial NotVoted;
+            NotVoted + ClosePoll => Locked;
+            Locked + DeadlinePassed => Locked, action |c: &mut (u32, u32, u32)| c.2 += 1;
+            Locked + ReopenPoll => NotVoted;
+        }
+        .on_entry(VoteState::Locked, |c| c.0 += 1)
+        .on_exit(VoteState::Locked, |c| c.1 += 1)
+        .build((0, 0, 0))
+    }
+
+    #[test]
+    fn self_transition_skips_entry_and_exit_actions() {
+        let mut machine = counting_machine();
+        machine.fire(VoteEvent::ClosePoll).unwrap();
+        assert_eq!(*machine.context(), (1, 0, 0));
+        machine.fire(VoteEvent::DeadlinePassed).unwrap();
+        machine.fire(VoteEvent::DeadlinePassed).unwrap();
+        assert_eq!(*machine.context(), (1, 0, 2));
+        machine.fire(VoteEvent::ReopenPoll).unwrap();
+        assert_eq!(*machine.context(), (1, 1, 2));
+    }
+
+    #[test]
+    fn reopening_does_not_count_as_voting() {
+        let mut machine = voting_machine();
+        machine.fire(VoteEvent::Vote).unwrap();
+        machine.fire(VoteEvent::ClosePoll).unwrap();
+        assert_eq!(machine.fire(VoteEvent::ReopenPoll), Ok(VoteState::Voted));
+        assert_eq!(machine.fire(VoteEvent::Vote), Err(TransitionError::Undefined { state: VoteState::Voted, event: VoteEvent::Vote }));
+    }
+
+    #[test]
+    #[should_panic(expected = "guard() must follow a transition()")]
+    fn guard_without_a_transition_panics() {
+        StateMachineBuilder::<VoteState, VoteEvent, bool>::new(VoteState::NotVoted).guard("has voted", |voted| *voted);
+    }
+
+    #[test]
+    #[should_panic(expected = "action() must follow a transition()")]
+    fn action_without_a_transition_panics() {
+        StateMachineBuilder::<VoteState, VoteEvent, bool>::new(VoteState::NotVoted).action(|voted| *voted = true);
+    }
+
+    #[test]
+    fn guards_pick_the_reopen_target() {
+        let mut machine = voting_machine();
+        machine.fire(VoteEvent::DeadlinePassed).unwrap();
+        assert_eq!(machine.fire(VoteEvent::ReopenPoll), Ok(VoteState::NotVoted));
+        assert!(!*machine.context());
+    }
+}
@@ -1,13 +1,60 @@
+//! Strategy
+//! 策略模式：把一组可以互相替换的算法封装起来，使用方只依赖统一的接口。
+//! Strategy<In, Out> 接收输入并返回结果，默认的 In = ()、Out = () 即最初无参数、无返回值的形式；
+//! StrategyRegistry 按名称登记策略，可以根据配置项或命令行参数在运行时选择，
+//! 名称写错时错误信息会列出全部可用的策略。
+//! Context 保存 Box<dyn Strategy>，每次调用都经过虚表；策略在编译期已知时可以用 StaticContext<S>，
+//! 由编译器为每种策略单独生成代码；策略是一个封闭集合时可以用 strategy_enum! 生成枚举，用 match 分发。
+//! benchmark 在循环中比较几种形式的调用开销，完整的测量用 cargo bench --bench dispatch。
+
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
 //策略trait
-pub trait Strategy {
-    fn execute(&self);
+pub trait Strategy<In = (), Out = ()> {
+    fn execute(&self, input: In) -> Out;
+
+    /// 策略的说明和参数，用于帮助信息
+    fn metadata(&self) -> StrategyInfo {
+        StrategyInfo::default()
+    }
+}
+
+/// 策略参数的说明
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub description: String,
+    /// 当前取值
+    pub value: String,
+}
+
+/// 策略的元数据
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StrategyInfo {
+    pub description: String,
+    pub params: Vec<Param>,
+}
+
+impl StrategyInfo {
+    pub fn new(description: &str) -> Self {
+        StrategyInfo { description: description.to_string(), params: Vec::new() }
+    }
+
+    pub fn param(mut self, name: &str, description: &str, value: impl fmt::Display) -> Self {
+        self.params.push(Param { name: name.to_string(), description: description.to_string(), value: value.to_string() });
+        self
+    }
 }
 
 //具体策略类 A
 pub struct ConcreteStrategyA;
 
 impl Strategy for ConcreteStrategyA {
-    fn execute(&self) {
+    fn execute(&self, _: ()) {
         println!("Executing strategy A");
     }
 }
@@ -16,28 +63,523 @@ impl Strategy for ConcreteStrategyA {
 pub struct ConcreteStrategyB;
 
 impl Strategy for ConcreteStrategyB {
-    fn execute(&self) {
+    fn execute(&self, _: ()) {
         println!("Executing strategy B");
     }
 }
 
 //环境类
-pub struct Context {
-    strategy: Box<dyn Strategy>,
+pub struct Context<In = (), Out = ()> {
+    strategy: Box<dyn Strategy<In, Out>>,
 }
 
-impl Context {
+impl<In, Out> Context<In, Out> {
     //创建环境实例
-    pub fn new(strategy: Box<dyn Strategy>) -> Self {
+    pub fn new(strategy: Box<dyn Strategy<In, Out>>) -> Self {
         Context { strategy }
     }
 
     //为环境实例设置策略实例
-    pub fn set_strategy(&mut self, strategy: Box<dyn Strategy>){
+    pub fn set_strategy(&mut self, strategy: Box<dyn Strategy<In, Out>>){
         self.strategy = strategy;
     }
 
+    /// 用当前策略处理输入
+    pub fn execute(&self, input: In) -> Out {
+        self.strategy.execute(input)
+    }
+}
+
+impl<In: 'static, Out: 'static> Context<In, Out> {
+    /// 转成泛型形式，策略仍然通过虚表调用
+    pub fn into_static(self) -> StaticContext<Box<dyn Strategy<In, Out>>> {
+        StaticContext::new(self.strategy)
+    }
+}
+
+impl Context {
     pub fn execute_strategy(&self){
-        self.strategy.execute();
+        self.strategy.execute(());
+    }
+}
+
+//-----------------静态分发---------------------------------------------------------------------
+/// Box 中的策略也是策略，StaticContext<Box<dyn Strategy>> 与 Context 等价
+impl<In, Out, S: Strategy<In, Out> + ?Sized> Strategy<In, Out> for Box<S> {
+    fn execute(&self, input: In) -> Out {
+        (**self).execute(input)
+    }
+
+    fn metadata(&self) -> StrategyInfo {
+        (**self).metadata()
+    }
+}
+
+/// 泛型环境：策略类型是类型参数，调用在编译期确定，不需要堆分配
+pub struct StaticContext<S> {
+    strategy: S,
+}
+
+impl<S> StaticContext<S> {
+    pub fn new(strategy: S) -> Self {
+        StaticContext { strategy }
+    }
+
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// 只能换成同一类型的策略；需要在几种策略之间切换时用 strategy_enum! 生成的枚举作为 S
+    pub fn set_strategy(&mut self, strategy: S) {
+        self.strategy = strategy;
+    }
+
+    pub fn execute<In, Out>(&self, input: In) -> Out
+    where
+        S: Strategy<In, Out>,
+    {
+        self.strategy.execute(input)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.strategy
+    }
+
+    /// 转换策略类型，例如把具体策略换成包含它的枚举
+    pub fn convert<T: From<S>>(self) -> StaticContext<T> {
+        StaticContext::new(T::from(self.strategy))
+    }
+}
+
+/// 泛型或枚举形式转成 Context；反方向只能得到 StaticContext<Box<dyn Strategy>>，
+/// 因为 Box<dyn Strategy> 已经丢掉了具体类型
+impl<In, Out, S: Strategy<In, Out> + 'static> From<StaticContext<S>> for Context<In, Out> {
+    fn from(context: StaticContext<S>) -> Self {
+        Context::new(Box::new(context.strategy))
+    }
+}
+
+/// 为一组策略生成枚举：每个变体包含一个策略，枚举本身实现 Strategy，调用时 match 分发，
+/// 并为每个策略类型生成到枚举的 From
+/// ```
+/// use design_pattern::behavioral::strategy::{BulkDiscount, PercentageOff, StaticContext, Strategy};
+/// use design_pattern::strategy_enum;
+///
+/// strategy_enum! {
+///     /// 促销活动可选的两种折扣
+///     pub enum Promotion: f64 => f64 {
+///         Percent(PercentageOff),
+///         Bulk(BulkDiscount),
+///     }
+/// }
+///
+/// let mut context: StaticContext<Promotion> = StaticContext::new(PercentageOff { percent: 10.0 }.into());
+/// assert_eq!(context.execute(50.0), 45.0);
+/// context.set_strategy(Promotion::Bulk(BulkDiscount { threshold: 100.0, off: 15.0 }));
+/// assert_eq!(context.execute(120.0), 105.0);
+/// assert_eq!(context.strategy().metadata().params.len(), 2);
+/// ```
+#[macro_export]
+macro_rules! strategy_enum {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident : $in:ty => $out:ty { $($variant:ident($strategy:ty)),* $(,)? }) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant($strategy)),*
+        }
+
+        impl $crate::behavioral::strategy::Strategy<$in, $out> for $name {
+            fn execute(&self, input: $in) -> $out {
+                match self {
+                    $($name::$variant(strategy) => strategy.execute(input)),*
+                }
+            }
+
+            fn metadata(&self) -> $crate::behavioral::strategy::StrategyInfo {
+                match self {
+                    $($name::$variant(strategy) => strategy.metadata()),*
+                }
+            }
+        }
+
+        $(
+            impl From<$strategy> for $name {
+                fn from(strategy: $strategy) -> Self {
+                    $name::$variant(strategy)
+                }
+            }
+        )*
+    };
+}
+
+/// 一种调用形式的测量结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Benchmark {
+    pub form: String,
+    pub iterations: u64,
+    pub elapsed: Duration,
+}
+
+impl Benchmark {
+    /// 每次调用的平均耗时（纳秒）
+    pub fn nanos_per_call(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.iterations.max(1) as f64
+    }
+}
+
+/// 在循环中调用 call iterations 次，输入由循环下标生成；
+/// black_box 防止编译器把调用提到循环外或直接算出结果
+pub fn benchmark<In, Out>(form: &str, iterations: u64, input: impl Fn(u64) -> In, call: impl Fn(In) -> Out) -> Benchmark {
+    let start = Instant::now();
+    for i in 0..iterations {
+        black_box(call(black_box(input(i))));
+    }
+    Benchmark { form: form.to_string(), iterations, elapsed: start.elapsed() }
+}
+
+/// 测量结果表格，最后一列是相对第一行的倍数
+pub fn benchmark_report(results: &[Benchmark]) -> String {
+    let base = results.first().map_or(1.0, |b| b.nanos_per_call().max(1e-9));
+    let mut out = format!("{:<24} {:>12} {:>10} {:>8}\n", "form", "iterations", "ns/call", "ratio");
+    for b in results {
+        out.push_str(&format!(
+            "{:<24} {:>12} {:>10.3} {:>7.2}x\n",
+            b.form,
+            b.iterations,
+            b.nanos_per_call(),
+            b.nanos_per_call() / base
+        ));
+    }
+    out
+}
+
+//-----------------按名称登记的策略---------------------------------------------------------------------
+/// 创建策略实例的工厂
+pub type StrategyFactory<In, Out> = Box<dyn Fn() -> Box<dyn Strategy<In, Out>>>;
+
+/// 请求的策略不存在
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownStrategy {
+    pub requested: String,
+    pub available: Vec<String>,
+}
+
+impl fmt::Display for UnknownStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown strategy \"{}\"", self.requested)?;
+        // 与请求的名称最接近的一个，编辑距离不超过 2 时提示
+        let closest = self
+            .available
+            .iter()
+            .map(|name| (edit_distance(&self.requested, name), name))
+            .min()
+            .filter(|(distance, _)| *distance <= 2);
+        if let Some((_, name)) = closest {
+            write!(f, ", did you mean \"{}\"?", name)?;
+        }
+        write!(f, " (available: {})", self.available.join(", "))
+    }
+}
+
+impl std::error::Error for UnknownStrategy {}
+
+/// 两个名称之间的编辑距离
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(diagonal + usize::from(ca != *cb));
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+/// 登记的策略：元数据第一次用到时才创建一个实例读取，之后缓存
+struct Entry<In, Out> {
+    factory: StrategyFactory<In, Out>,
+    info: OnceCell<StrategyInfo>,
+}
+
+impl<In, Out> Entry<In, Out> {
+    fn info(&self) -> &StrategyInfo {
+        self.info.get_or_init(|| (self.factory)().metadata())
+    }
+}
+
+/// 策略注册表，名称按字母顺序排列
+pub struct StrategyRegistry<In = (), Out = ()> {
+    entries: BTreeMap<String, Entry<In, Out>>,
+}
+
+impl<In, Out> Default for StrategyRegistry<In, Out> {
+    fn default() -> Self {
+        StrategyRegistry { entries: BTreeMap::new() }
+    }
+}
+
+impl<In, Out> StrategyRegistry<In, Out> {
+    pub fn new() -> Self {
+        StrategyRegistry::default()
+    }
+
+    /// 登记策略，同名的策略会被替换；登记时不调用 factory
+    pub fn register(mut self, name: &str, factory: impl Fn() -> Box<dyn Strategy<In, Out>> + 'static) -> Self {
+        self.entries.insert(name.to_string(), Entry { factory: Box::new(factory), info: OnceCell::new() });
+        self
+    }
+
+    /// 全部策略名称
+    pub fn names(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    pub fn info(&self, name: &str) -> Result<&StrategyInfo, UnknownStrategy> {
+        self.entries.get(name).map(Entry::info).ok_or_else(|| self.unknown(name))
+    }
+
+    /// 按名称创建策略
+    pub fn create(&self, name: &str) -> Result<Box<dyn Strategy<In, Out>>, UnknownStrategy> {
+        self.entries.get(name).map(|entry| (entry.factory)()).ok_or_else(|| self.unknown(name))
+    }
+
+    /// 按名称创建使用该策略的环境
+    pub fn context(&self, name: &str) -> Result<Context<In, Out>, UnknownStrategy> {
+        self.create(name).map(Context::new)
+    }
+
+    /// 从命令行参数中读取 `--flag name` 或 `--flag=name` 选择策略，没有该参数时使用 default
+    pub fn select_from_args(&self, args: &[String], flag: &str, default: &str) -> Result<Box<dyn Strategy<In, Out>>, UnknownStrategy> {
+        let prefix = format!("{}=", flag);
+        let mut name = default;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if let Some(value) = arg.strip_prefix(&prefix) {
+                name = value;
+            } else if arg == flag {
+                name = iter.next().map(String::as_str).unwrap_or("");
+            }
+        }
+        self.create(name)
+    }
+
+    /// 帮助信息：每个策略的名称、说明和参数
+    pub fn help(&self) -> String {
+        let mut out = String::new();
+        for (name, entry) in &self.entries {
+            let info = entry.info();
+            out.push_str(&format!("  {:<12} {}\n", name, info.description));
+            for param in &info.params {
+                out.push_str(&format!("  {:<12}   {} = {}  {}\n", "", param.name, param.value, param.description));
+            }
+        }
+        out
+    }
+
+    fn unknown(&self, name: &str) -> UnknownStrategy {
+        UnknownStrategy { requested: name.to_string(), available: self.names() }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// 带输入和返回值的策略：根据订单金额计算应付金额，benches/dispatch.rs 也用它们测量调用开销
+pub struct NoDiscount;
+
+impl Strategy<f64, f64> for NoDiscount {
+    fn execute(&self, total: f64) -> f64 {
+        total
+    }
+
+    fn metadata(&self) -> StrategyInfo {
+        StrategyInfo::new("charge the full price")
+    }
+}
+
+pub struct PercentageOff {
+    pub percent: f64,
+}
+
+impl Strategy<f64, f64> for PercentageOff {
+    fn execute(&self, total: f64) -> f64 {
+        total * (1.0 - self.percent / 100.0)
+    }
+
+    fn metadata(&self) -> StrategyInfo {
+        StrategyInfo::new("fixed percentage off every order").param("percent", "discount in percent", self.percent)
+    }
+}
+
+pub struct BulkDiscount {
+    pub threshold: f64,
+    pub off: f64,
+}
+
+impl Strategy<f64, f64> for BulkDiscount {
+    fn execute(&self, total: f64) -> f64 {
+        if total >= self.threshold {
+            total - self.off
+        } else {
+            total
+        }
+    }
+
+    fn metadata(&self) -> StrategyInfo {
+        StrategyInfo::new("flat amount off large orders")
+            .param("threshold", "minimum order total", self.threshold)
+            .param("off", "amount taken off", self.off)
+    }
+}
+
+strategy_enum! {
+    /// 定价策略的封闭集合
+    pub enum Pricing: f64 => f64 {
+        None(NoDiscount),
+        Percent(PercentageOff),
+        Bulk(BulkDiscount),
+    }
+}
+
+pub fn main() {
+    let registry = StrategyRegistry::new()
+        .register("none", || Box::new(NoDiscount))
+        .register("percent", || Box::new(PercentageOff { percent: 10.0 }))
+        .register("bulk", || Box::new(BulkDiscount { threshold: 100.0, off: 15.0 }));
+    print!("{}", registry.help());
+
+    // 配置项选择
+    let context = registry.context("bulk").unwrap();
+    println!("bulk: 120 => {}", context.execute(120.0));
+
+    // 命令行参数选择
+    let args: Vec<String> = ["app", "--pricing=percent"].iter().map(|s| s.to_string()).collect();
+    let strategy = registry.select_from_args(&args, "--pricing", "none").unwrap();
+    println!("--pricing=percent: 50 => {}", strategy.execute(50.0));
+
+    // 名称写错
+    println!("{}", registry.context("precent").err().unwrap());
+
+    // 静态分发、枚举分发和动态分发之间的转换
+    let mut pricing: StaticContext<Pricing> = StaticContext::new(PercentageOff { percent: 10.0 }).convert();
+    pricing.set_strategy(BulkDiscount { threshold: 100.0, off: 15.0 }.into());
+    let dynamic: Context<f64, f64> = pricing.into();
+    println!("enum converted to Context: 120 => {}", dynamic.execute(120.0));
+
+    // 调用开销：直接调用、泛型、枚举和 Box<dyn Strategy>
+    let iterations = 2_000_000;
+    let input = |i: u64| (i % 1000) as f64;
+    let strategy = PercentageOff { percent: 10.0 };
+    let generic = StaticContext::new(PercentageOff { percent: 10.0 });
+    let enumerated: StaticContext<Pricing> = StaticContext::new(PercentageOff { percent: 10.0 }.into());
+    let dynamic = registry.context("percent").unwrap();
+    let (strategy, generic, enumerated, dynamic) = (black_box(&strategy), black_box(&generic), black_box(&enumerated), black_box(&dynamic));
+    let results = vec![
+        benchmark("direct call", iterations, input, |x| strategy.execute(x)),
+        benchmark("StaticContext<S>", iterations, input, |x| generic.execute(x)),
+        benchmark("StaticContext<enum>", iterations, input, |x| enumerated.execute(x)),
+        benchmark("Context (dyn)", iterations, input, |x| dynamic.execute(x)),
+    ];
+    print!("{}", benchmark_report(&results));
+
+    // 原来的用法不变
+    let mut context = Context::new(Box::new(ConcreteStrategyA));
+    context.execute_strategy();
+    context.set_strategy(Box::new(ConcreteStrategyB));
+    context.execute_strategy();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn pricing_registry() -> StrategyRegistry<f64, f64> {
+        StrategyRegistry::new()
+            .register("none", || Box::new(NoDiscount))
+            .register("percent", || Box::new(PercentageOff { percent: 10.0 }))
+            .register("bulk", || Box::new(BulkDiscount { threshold: 100.0, off: 15.0 }))
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn registry_creates_strategies_by_name() {
+        let registry = pricing_registry();
+        assert_eq!(registry.names(), vec!["bulk", "none", "percent"]);
+        assert_eq!(registry.context("bulk").unwrap().execute(120.0), 105.0);
+        assert_eq!(registry.create("percent").unwrap().execute(50.0), 45.0);
+        assert_eq!(registry.info("bulk").unwrap().params.len(), 2);
+        assert!(registry.help().contains("percent = 10"));
+
+        // 同名登记替换原来的策略
+        let registry = registry.register("none", || Box::new(PercentageOff { percent: 50.0 }));
+        assert_eq!(registry.names().len(), 3);
+        assert_eq!(registry.create("none").unwrap().execute(10.0), 5.0);
+    }
+
+    #[test]
+    fn register_is_lazy_and_metadata_is_cached() {
+        let created = Rc::new(Cell::new(0));
+        let counter = created.clone();
+        let registry: StrategyRegistry<f64, f64> = StrategyRegistry::new().register("percent", move || {
+            counter.set(counter.get() + 1);
+            Box::new(PercentageOff { percent: 10.0 })
+        });
+        assert_eq!(created.get(), 0);
+        registry.info("percent").unwrap();
+        registry.help();
+        assert_eq!(created.get(), 1);
+        registry.create("percent").unwrap();
+        assert_eq!(created.get(), 2);
+    }
+
+    #[test]
+    fn select_from_args_accepts_both_flag_forms() {
+        let registry = pricing_registry();
+        let select = |list: &[&str]| registry.select_from_args(&args(list), "--pricing", "none").map(|s| s.execute(120.0));
+        assert_eq!(select(&["app", "--pricing=percent"]), Ok(108.0));
+        assert_eq!(select(&["app", "--pricing", "bulk"]), Ok(105.0));
+        // 没有参数时用默认值，多次出现时以最后一次为准
+        assert_eq!(select(&["app"]), Ok(120.0));
+        assert_eq!(select(&["app", "--pricing", "bulk", "--pricing=percent"]), Ok(108.0));
+        // 其他参数不受影响；--flag 在末尾没有值时是空名称
+        assert_eq!(select(&["app", "--pricing-mode=bulk"]), Ok(120.0));
+        assert_eq!(select(&["app", "--pricing"]).unwrap_err().requested, "");
+    }
+
+    #[test]
+    fn unknown_name_suggests_the_closest() {
+        let registry = pricing_registry();
+        let error = registry.context("precent").err().unwrap();
+        assert_eq!(error.available, vec!["bulk", "none", "percent"]);
+        assert_eq!(error.to_string(), r#"unknown strategy "precent", did you mean "percent"? (available: bulk, none, percent)"#);
+        // 相差太多时不提示
+        let error = registry.create("flat").err().unwrap();
+        assert_eq!(error.to_string(), r#"unknown strategy "flat" (available: bulk, none, percent)"#);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn conversions_between_dispatch_forms() {
+        let percent = StaticContext::new(PercentageOff { percent: 10.0 });
+        assert_eq!(percent.execute(50.0), 45.0);
+        let mut pricing: StaticContext<Pricing> = percent.convert();
+        assert!(matches!(pricing.strategy(), Pricing::Percent(_)));
+        assert_eq!(pricing.execute(50.0), 45.0);
+        pricing.set_strategy(BulkDiscount { threshold: 100.0, off: 15.0 }.into());
+        assert_eq!(pricing.execute(120.0), 105.0);
+        assert_eq!(pricing.strategy().metadata().params.len(), 2);
+
+        let dynamic: Context<f64, f64> = pricing.into();
+        assert_eq!(dynamic.execute(120.0), 105.0);
+        let boxed = pricing_registry().context("percent").unwrap().into_static();
+        assert_eq!(boxed.execute(50.0), 45.0);
+        assert_eq!(boxed.strategy().metadata().description, "fixed percentage off every order");
+        assert_eq!(boxed.into_inner().execute(10.0), 9.0);
     }
 }
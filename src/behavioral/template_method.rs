@@ -1,3 +1,8 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::behavioral::escape::{csv_field, markup};
+
 //定义模板方法的抽象类
 pub trait AbstractClass {
     //模板方法
@@ -43,4 +48,962 @@ impl AbstractClass for ConcreteClassB {
     fn step3(&self) {
         println!("ConcreteClassB: Step 3")
     }
-}
\ No newline at end of file
+}
+
+//-----------------可失败的模板方法---------------------------------------------------------------------
+// 批处理任务的步骤可能失败：FallibleTemplate 的步骤返回 Result，第一个失败的步骤结束整个流程，
+// 错误中带有失败步骤的名称；步骤可以按条件跳过，前后钩子默认什么也不做，每个步骤的耗时都会记录下来。
+
+/// 步骤的执行结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepStatus {
+    Completed,
+    Skipped,
+    Failed,
+}
+
+/// 单个步骤的记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepTiming {
+    pub name: &'static str,
+    pub status: StepStatus,
+    pub elapsed: Duration,
+}
+
+/// 一次运行中各步骤的记录，按执行顺序排列
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateReport {
+    pub steps: Vec<StepTiming>,
+}
+
+impl TemplateReport {
+    pub fn total(&self) -> Duration {
+        self.steps.iter().map(|s| s.elapsed).sum()
+    }
+
+    pub fn status(&self, name: &str) -> Option<StepStatus> {
+        self.steps.iter().find(|s| s.name == name).map(|s| s.status)
+    }
+}
+
+impl fmt::Display for TemplateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "  {:<12} {:<9} {:?}", step.name, format!("{:?}", step.status), step.elapsed)?;
+        }
+        writeln!(f, "  {:<12} {:<9} {:?}", "total", "", self.total())
+    }
+}
+
+/// 失败的步骤、它返回的错误，以及失败之前的运行记录
+#[derive(Debug)]
+pub struct StepError<E> {
+    pub step: &'static str,
+    pub error: E,
+    pub report: TemplateReport,
+}
+
+impl<E: fmt::Display> fmt::Display for StepError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {} failed: {}", self.step, self.error)
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for StepError<E> {}
+
+//定义可失败模板方法的抽象类
+pub trait FallibleTemplate {
+    type Error;
+
+    /// 三个步骤的名称，用于钩子、记录和错误信息
+    const STEPS: [&'static str; 3] = ["step1", "step2", "step3"];
+
+    //模板方法：依次执行三个步骤，遇到第一个错误就停止
+    fn run(&mut self) -> Result<TemplateReport, StepError<Self::Error>> {
+        let mut report = TemplateReport::default();
+        for (index, name) in Self::STEPS.into_iter().enumerate() {
+            if !self.should_run(name) {
+                report.steps.push(StepTiming { name, status: StepStatus::Skipped, elapsed: Duration::ZERO });
+                continue;
+            }
+            self.before_step(name);
+            let start = Instant::now();
+            let result = match index {
+                0 => self.step1(),
+                1 => self.step2(),
+                _ => self.step3(),
+            };
+            let status = if result.is_ok() { StepStatus::Completed } else { StepStatus::Failed };
+            let timing = StepTiming { name, status, elapsed: start.elapsed() };
+            self.after_step(&timing);
+            report.steps.push(timing);
+            if let Err(error) = result {
+                return Err(StepError { step: name, error, report });
+            }
+        }
+        Ok(report)
+    }
+
+    //定义算法的具体步骤，由子类实现
+    fn step1(&mut self) -> Result<(), Self::Error>;
+    fn step2(&mut self) -> Result<(), Self::Error>;
+    fn step3(&mut self) -> Result<(), Self::Error>;
+
+    /// 返回 false 时跳过该步骤，钩子也不会被调用
+    fn should_run(&self, _step: &str) -> bool {
+        true
+    }
+
+    /// 步骤开始前调用
+    fn before_step(&mut self, _step: &str) {}
+
+    /// 步骤结束后调用，失败的步骤也会调用
+    fn after_step(&mut self, _timing: &StepTiming) {}
+}
+
+//-----------------报表模板---------------------------------------------------------------------
+// 报表的结构是固定的：标题和元信息、若干章节（段落和表格）、页脚。
+// ReportRenderer::render 是模板方法，按这个结构依次调用各个格式化步骤，
+// Markdown、HTML 和 CSV 三种渲染器只实现各自的格式化步骤。
+
+/// 列的对齐方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// 数字的显示格式
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberFormat {
+    /// 固定小数位，可选千位分隔符
+    Fixed { decimals: usize, thousands: bool },
+    /// 0.25 显示为 25.0%
+    Percent { decimals: usize },
+    /// 带货币符号和千位分隔符
+    Currency { symbol: String, decimals: usize },
+}
+
+impl NumberFormat {
+    /// 原始数值需要保留的小数位数；百分比显示时乘以 100，所以多两位
+    pub fn decimals(&self) -> usize {
+        match self {
+            NumberFormat::Fixed { decimals, .. } | NumberFormat::Currency { decimals, .. } => *decimals,
+            NumberFormat::Percent { decimals } => decimals + 2,
+        }
+    }
+
+    /// 舍入到 decimals 位小数，去掉求和累积的浮点误差
+    pub fn round(&self, value: f64) -> f64 {
+        let scale = 10f64.powi(self.decimals() as i32);
+        (value * scale).round() / scale
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            NumberFormat::Fixed { decimals, thousands } => {
+                let text = format!("{:.*}", decimals, value);
+                if *thousands { group_thousands(&text) } else { text }
+            }
+            NumberFormat::Percent { decimals } => format!("{:.*}%", decimals, value * 100.0),
+            NumberFormat::Currency { symbol, decimals } => {
+                let text = group_thousands(&format!("{:.*}", decimals, value.abs()));
+                let sign = if value < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+                format!("{}{}{}", sign, symbol, text)
+            }
+        }
+    }
+}
+
+/// 在整数部分每三位插入逗号
+fn group_thousands(number: &str) -> String {
+    let (sign, digits) = number.strip_prefix('-').map_or(("", number), |rest| ("-", rest));
+    let (integer, fraction) = digits.split_once('.').map_or((digits, None), |(i, f)| (i, Some(f)));
+    let mut grouped = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    match fraction {
+        Some(fraction) => format!("{}{}.{}", sign, grouped, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+/// 表格单元格
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Number(f64),
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::Text(text.to_string())
+    }
+}
+
+impl From<f64> for Cell {
+    fn from(value: f64) -> Self {
+        Cell::Number(value)
+    }
+}
+
+/// 表格的列
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub title: String,
+    pub align: Align,
+    /// 数字列的格式；文本列为 None
+    pub format: Option<NumberFormat>,
+    /// 是否出现在合计行中
+    pub total: bool,
+}
+
+impl Column {
+    /// 左对齐的文本列
+    pub fn text(title: &str) -> Self {
+        Column { title: title.to_string(), align: Align::Left, format: None, total: false }
+    }
+
+    /// 右对齐的数字列；百分比不参与合计，其余参与
+    pub fn number(title: &str, format: NumberFormat) -> Self {
+        let total = !matches!(format, NumberFormat::Percent { .. });
+        Column { title: title.to_string(), align: Align::Right, format: Some(format), total }
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn total(mut self, total: bool) -> Self {
+        self.total = total;
+        self
+    }
+
+    fn format(&self, cell: &Cell) -> String {
+        match (cell, &self.format) {
+            (Cell::Number(value), Some(format)) => format.format(*value),
+            (Cell::Number(value), None) => value.to_string(),
+            (Cell::Text(text), _) => text.clone(),
+        }
+    }
+}
+
+/// 表格
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Cell>>,
+    /// 合计行第一列的文字；None 表示没有合计行
+    pub totals_label: Option<String>,
+}
+
+impl Table {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Table { columns, rows: Vec::new(), totals_label: None }
+    }
+
+    /// 追加一行，单元格不足的列留空
+    pub fn row(mut self, cells: Vec<Cell>) -> Self {
+        self.rows.push(cells);
+        self
+    }
+
+    /// 在表格末尾加合计行
+    pub fn totals(mut self, label: &str) -> Self {
+        self.totals_label = Some(label.to_string());
+        self
+    }
+
+    /// 合计行的原始值：参与合计的列为数字之和，按列的小数位数舍入；
+    /// 标签放在第一个不参与合计的列，其余为空。所有列都参与合计时，标签写在第一列的合计值前面
+    pub fn totals_row(&self) -> Option<Vec<Cell>> {
+        let label = self.totals_label.as_ref()?;
+        let label_at = self.columns.iter().position(|column| !column.total);
+        let mut cells: Vec<Cell> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                if column.total {
+                    let sum: f64 = self.rows.iter().filter_map(|row| match row.get(i) {
+                        Some(Cell::Number(value)) => Some(*value),
+                        _ => None,
+                    }).sum();
+                    Cell::Number(column.format.as_ref().map_or(sum, |format| format.round(sum)))
+                } else if label_at == Some(i) {
+                    Cell::Text(label.clone())
+                } else {
+                    Cell::Text(String::new())
+                }
+            })
+            .collect();
+        if let (None, Some(first)) = (label_at, cells.first()) {
+            cells[0] = Cell::Text(format!("{} {}", label, self.columns[0].format(first)));
+        }
+        Some(cells)
+    }
+
+    /// 按列格式化后的正文行
+    pub fn formatted_rows(&self) -> Vec<Vec<String>> {
+        self.rows.iter().map(|row| self.format_row(row)).collect()
+    }
+
+    /// 按列格式化后的合计行
+    pub fn formatted_totals(&self) -> Option<Vec<String>> {
+        self.totals_row().map(|row| self.format_row(&row))
+    }
+
+    fn format_row(&self, row: &[Cell]) -> Vec<String> {
+        self.columns.iter().enumerate().map(|(i, column)| row.get(i).map(|cell| column.format(cell)).unwrap_or_default()).collect()
+    }
+}
+
+/// 章节中的内容块，按添加顺序输出
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Paragraph(String),
+    Table(Table),
+}
+
+/// 章节
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub title: String,
+    pub blocks: Vec<Block>,
+}
+
+impl Section {
+    pub fn new(title: &str) -> Self {
+        Section { title: title.to_string(), blocks: Vec::new() }
+    }
+
+    pub fn paragraph(mut self, text: &str) -> Self {
+        self.blocks.push(Block::Paragraph(text.to_string()));
+        self
+    }
+
+    pub fn table(mut self, table: Table) -> Self {
+        self.blocks.push(Block::Table(table));
+        self
+    }
+}
+
+/// 报表
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub title: String,
+    /// 标题下方的键值信息，例如日期、作者
+    pub meta: Vec<(String, String)>,
+    pub sections: Vec<Section>,
+    pub footer: String,
+}
+
+impl Report {
+    pub fn new(title: &str) -> Self {
+        Report { title: title.to_string(), ..Report::default() }
+    }
+
+    pub fn meta(mut self, key: &str, value: &str) -> Self {
+        self.meta.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn section(mut self, section: Section) -> Self {
+        self.sections.push(section);
+        self
+    }
+
+    pub fn footer(mut self, footer: &str) -> Self {
+        self.footer = footer.to_string();
+        self
+    }
+}
+
+//定义报表渲染的抽象类
+pub trait ReportRenderer {
+    //模板方法：文档结构固定，各步骤把内容追加到 out
+    fn render(&self, report: &Report) -> String {
+        let mut out = String::new();
+        self.begin_document(&mut out, report);
+        self.header(&mut out, &report.title, &report.meta);
+        for section in &report.sections {
+            self.section_heading(&mut out, &section.title);
+            for block in &section.blocks {
+                match block {
+                    Block::Paragraph(text) => self.paragraph(&mut out, text),
+                    Block::Table(table) => self.table(&mut out, table),
+                }
+            }
+        }
+        if !report.footer.is_empty() {
+            self.footer(&mut out, &report.footer);
+        }
+        self.end_document(&mut out);
+        out
+    }
+
+    /// 文档开始，默认什么也不输出
+    fn begin_document(&self, _out: &mut String, _report: &Report) {}
+
+    fn header(&self, out: &mut String, title: &str, meta: &[(String, String)]);
+    fn section_heading(&self, out: &mut String, title: &str);
+    fn paragraph(&self, out: &mut String, text: &str);
+    fn table(&self, out: &mut String, table: &Table);
+    fn footer(&self, out: &mut String, text: &str);
+
+    /// 文档结束，默认什么也不输出
+    fn end_document(&self, _out: &mut String) {}
+}
+
+/// 按对齐方式把 text 填充到 width 个字符
+fn pad(text: &str, width: usize, align: Align) -> String {
+    let fill = width.saturating_sub(text.chars().count());
+    match align {
+        Align::Left => format!("{}{}", text, " ".repeat(fill)),
+        Align::Right => format!("{}{}", " ".repeat(fill), text),
+        Align::Center => format!("{}{}{}", " ".repeat(fill / 2), text, " ".repeat(fill - fill / 2)),
+    }
+}
+
+/// Markdown：表格按列宽对齐，分隔行标出对齐方式，合计行加粗；
+/// 所有文本都经过转义，不会被当成 Markdown 标记或 HTML
+pub struct MarkdownRenderer;
+
+impl MarkdownRenderer {
+    /// 标记字符前加反斜杠，换行替换为 newline：标题、元信息和页脚只能占一行，表格单元格用 <br>
+    fn escape_with(text: &str, newline: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        for (i, line) in text.lines().enumerate() {
+            if i > 0 {
+                out.push_str(newline);
+            }
+            for c in line.chars() {
+                if "\\`*_[]<>#|&~".contains(c) {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    fn escape(text: &str) -> String {
+        Self::escape_with(text, " ")
+    }
+
+    /// 段落保留换行；行首的空白、-、+、= 和 "1." 会变成代码块、列表或标题，去掉空白并转义
+    fn escape_paragraph(text: &str) -> String {
+        let lines: Vec<String> = text
+            .lines()
+            .map(str::trim_start)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let digits = line.chars().take_while(char::is_ascii_digit).count();
+                let list = line.starts_with(['-', '+', '=']) || (digits > 0 && line[digits..].starts_with(['.', ')']));
+                let escaped = Self::escape(line);
+                if !list {
+                    escaped
+                } else if digits > 0 {
+                    format!("{}\\{}", &escaped[..digits], &escaped[digits..])
+                } else {
+                    format!("\\{}", escaped)
+                }
+            })
+            .collect();
+        lines.join("\n")
+    }
+}
+
+impl ReportRenderer for MarkdownRenderer {
+    fn header(&self, out: &mut String, title: &str, meta: &[(String, String)]) {
+        out.push_str(&format!("# {}\n\n", Self::escape(title)));
+        for (key, value) in meta {
+            out.push_str(&format!("**{}:** {}  \n", Self::escape(key), Self::escape(value)));
+        }
+        if !meta.is_empty() {
+            out.push('\n');
+        }
+    }
+
+    fn section_heading(&self, out: &mut String, title: &str) {
+        out.push_str(&format!("## {}\n\n", Self::escape(title)));
+    }
+
+    fn paragraph(&self, out: &mut String, text: &str) {
+        out.push_str(&format!("{}\n\n", Self::escape_paragraph(text)));
+    }
+
+    fn table(&self, out: &mut String, table: &Table) {
+        let cell = |text: &str| Self::escape_with(text, "<br>");
+        let header: Vec<String> = table.columns.iter().map(|c| cell(&c.title)).collect();
+        let mut rows: Vec<Vec<String>> = table.formatted_rows().iter().map(|row| row.iter().map(|c| cell(c)).collect()).collect();
+        if let Some(totals) = table.formatted_totals() {
+            rows.push(totals.iter().map(|c| if c.is_empty() { String::new() } else { format!("**{}**", cell(c)) }).collect());
+        }
+        let widths: Vec<usize> = (0..header.len())
+            .map(|i| rows.iter().map(|row| row[i].chars().count()).chain([header[i].chars().count(), 3]).max().unwrap_or(3))
+            .collect();
+        let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        out.push_str(&line(header.iter().zip(&table.columns).enumerate().map(|(i, (h, c))| pad(h, widths[i], c.align)).collect()));
+        out.push_str(&line(
+            table
+                .columns
+                .iter()
+                .zip(&widths)
+                .map(|(column, &width)| match column.align {
+                    Align::Left => format!(":{}", "-".repeat(width - 1)),
+                    Align::Right => format!("{}:", "-".repeat(width - 1)),
+                    Align::Center => format!(":{}:", "-".repeat(width - 2)),
+                })
+                .collect(),
+        ));
+        for row in &rows {
+            out.push_str(&line(row.iter().zip(&table.columns).enumerate().map(|(i, (cell, c))| pad(cell, widths[i], c.align)).collect()));
+        }
+        out.push('\n');
+    }
+
+    fn footer(&self, out: &mut String, text: &str) {
+        out.push_str(&format!("---\n\n_{}_\n", Self::escape(text)));
+    }
+}
+
+/// 独立的 HTML 文档，所有文本都经过转义
+pub struct HtmlRenderer;
+
+impl HtmlRenderer {
+    fn cells(tag: &str, cells: &[String], columns: &[Column]) -> String {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(columns)
+            .map(|(cell, column)| {
+                let align = match column.align {
+                    Align::Left => "left",
+                    Align::Center => "center",
+                    Align::Right => "right",
+                };
+                format!("<{} style=\"text-align:{}\">{}</{}>", tag, align, markup(cell), tag)
+            })
+            .collect();
+        format!("<tr>{}</tr>\n", cells.join(""))
+    }
+}
+
+impl ReportRenderer for HtmlRenderer {
+    fn begin_document(&self, out: &mut String, report: &Report) {
+        out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        out.push_str(&format!("<title>{}</title>\n", markup(&report.title)));
+        out.push_str("<style>table{border-collapse:collapse}th,td{border:1px solid #ccc;padding:4px 8px}tfoot td{font-weight:bold}</style>\n");
+        out.push_str("</head>\n<body>\n");
+    }
+
+    fn header(&self, out: &mut String, title: &str, meta: &[(String, String)]) {
+        out.push_str(&format!("<h1>{}</h1>\n", markup(title)));
+        if !meta.is_empty() {
+            out.push_str("<dl>\n");
+            for (key, value) in meta {
+                out.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", markup(key), markup(value)));
+            }
+            out.push_str("</dl>\n");
+        }
+    }
+
+    fn section_heading(&self, out: &mut String, title: &str) {
+        out.push_str(&format!("<h2>{}</h2>\n", markup(title)));
+    }
+
+    fn paragraph(&self, out: &mut String, text: &str) {
+        out.push_str(&format!("<p>{}</p>\n", markup(text)));
+    }
+
+    fn table(&self, out: &mut String, table: &Table) {
+        let header: Vec<String> = table.columns.iter().map(|c| c.title.clone()).collect();
+        out.push_str("<table>\n<thead>\n");
+        out.push_str(&Self::cells("th", &header, &table.columns));
+        out.push_str("</thead>\n<tbody>\n");
+        for row in table.formatted_rows() {
+            out.push_str(&Self::cells("td", &row, &table.columns));
+        }
+        out.push_str("</tbody>\n");
+        if let Some(totals) = table.formatted_totals() {
+            out.push_str("<tfoot>\n");
+            out.push_str(&Self::cells("td", &totals, &table.columns));
+            out.push_str("</tfoot>\n");
+        }
+        out.push_str("</table>\n");
+    }
+
+    fn footer(&self, out: &mut String, text: &str) {
+        out.push_str(&format!("<footer>{}</footer>\n", markup(text)));
+    }
+
+    fn end_document(&self, out: &mut String) {
+        out.push_str("</body>\n</html>\n");
+    }
+}
+
+/// CSV：只输出表格，数字不做格式化，便于再导入（合计值按列的小数位数舍入）；多个表格之间空一行
+pub struct CsvRenderer;
+
+impl ReportRenderer for CsvRenderer {
+    fn header(&self, _out: &mut String, _title: &str, _meta: &[(String, String)]) {}
+
+    fn section_heading(&self, _out: &mut String, _title: &str) {}
+
+    fn paragraph(&self, _out: &mut String, _text: &str) {}
+
+    fn table(&self, out: &mut String, table: &Table) {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let line = |cells: Vec<String>| cells.iter().map(|c| csv_field(c)).collect::<Vec<_>>().join(",") + "\n";
+        out.push_str(&line(table.columns.iter().map(|c| c.title.clone()).collect()));
+        let raw = |row: &[Cell]| -> Vec<String> {
+            (0..table.columns.len())
+                .map(|i| match row.get(i) {
+                    Some(Cell::Number(value)) => value.to_string(),
+                    Some(Cell::Text(text)) => text.clone(),
+                    None => String::new(),
+                })
+                .collect()
+        };
+        for row in &table.rows {
+            out.push_str(&line(raw(row)));
+        }
+        if let Some(totals) = table.totals_row() {
+            out.push_str(&line(raw(&totals)));
+        }
+    }
+
+    fn footer(&self, _out: &mut String, _text: &str) {}
+}
+
+//--------------------------------------------------------------------------------------------------
+// 导出任务：读取记录、清洗、写出；没有记录时跳过清洗，目标不可写时写出失败
+struct ExportJob {
+    source: Vec<&'static str>,
+    rows: Vec<String>,
+    writable: bool,
+    written: Vec<String>,
+    log: Vec<String>,
+}
+
+impl ExportJob {
+    fn new(source: Vec<&'static str>, writable: bool) -> Self {
+        ExportJob { source, rows: Vec::new(), writable, written: Vec::new(), log: Vec::new() }
+    }
+}
+
+impl FallibleTemplate for ExportJob {
+    type Error = String;
+
+    const STEPS: [&'static str; 3] = ["extract", "transform", "load"];
+
+    fn step1(&mut self) -> Result<(), String> {
+        self.rows = self.source.iter().map(|s| s.to_string()).collect();
+        Ok(())
+    }
+
+    fn step2(&mut self) -> Result<(), String> {
+        self.rows = self.rows.iter().map(|r| r.trim().to_uppercase()).filter(|r| !r.is_empty()).collect();
+        Ok(())
+    }
+
+    fn step3(&mut self) -> Result<(), String> {
+        if !self.writable {
+            return Err(format!("target is read-only, {} rows not written", self.rows.len()));
+        }
+        self.written.append(&mut self.rows);
+        Ok(())
+    }
+
+    fn should_run(&self, step: &str) -> bool {
+        step != "transform" || !self.rows.is_empty()
+    }
+
+    fn before_step(&mut self, step: &str) {
+        self.log.push(format!("begin {}", step));
+    }
+
+    fn after_step(&mut self, timing: &StepTiming) {
+        self.log.push(format!("end {} {:?}", timing.name, timing.status));
+    }
+}
+
+// 季度销售报表：表头、段落、带合计的表格和页脚，文本中有各种格式需要转义的字符
+fn sales_report() -> Report {
+    let money = || NumberFormat::Currency { symbol: "$".to_string(), decimals: 2 };
+    Report::new("Quarterly sales")
+        .meta("Period", "2024 Q1")
+        .meta("Prepared by", "Finance & Ops")
+        .section(
+            Section::new("Regions")
+                .paragraph("Revenue by region. Note: <script> tags in names must be escaped.")
+                .table(
+                    Table::new(vec![
+                        Column::text("Region"),
+                        Column::number("Orders", NumberFormat::Fixed { decimals: 0, thousands: true }),
+                        Column::number("Revenue", money()),
+                        Column::number("Margin", NumberFormat::Percent { decimals: 1 }),
+                        Column::text("Status").align(Align::Center),
+                    ])
+                    .row(vec!["North".into(), 1200.0.into(), 98765.4.into(), 0.213.into(), "ok".into()])
+                    .row(vec!["South, coastal".into(), 85.0.into(), 4321.0.into(), 0.05.into(), "watch".into()])
+                    .row(vec!["<West>".into(), 15.0.into(), (-120.5).into(), (-0.02).into(), "".into()])
+                    .totals("Total"),
+                ),
+        )
+        .footer("Generated by the reporting job")
+}
+
+pub fn main() {
+    let mut job = ExportJob::new(vec![" a ", "", "b"], true);
+    let report = job.run().unwrap();
+    print!("{}", report);
+    println!("written: {:?}", job.written);
+
+    // 没有记录时跳过清洗
+    let mut empty = ExportJob::new(vec![], true);
+    print!("{}", empty.run().unwrap());
+
+    // 写出失败，错误中带有步骤名称和之前的记录
+    let mut readonly = ExportJob::new(vec!["c"], false);
+    let error = readonly.run().unwrap_err();
+    println!("{}", error);
+    print!("{}", error.report);
+
+    // 同一份报表，三种格式
+    let report = sales_report();
+    print!("{}", MarkdownRenderer.render(&report));
+    print!("{}", HtmlRenderer.render(&report));
+    print!("{}", CsvRenderer.render(&report));
+
+    // 原来的模板方法不变
+    ConcreteClassA.template_method();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在 fail 指定的步骤失败，跳过 skip 中的步骤，按顺序记录调用过的步骤和钩子
+    struct Scripted {
+        fail: Option<&'static str>,
+        skip: Vec<&'static str>,
+        calls: Vec<String>,
+    }
+
+    impl Scripted {
+        fn new(fail: Option<&'static str>, skip: &[&'static str]) -> Self {
+            Scripted { fail, skip: skip.to_vec(), calls: Vec::new() }
+        }
+
+        fn step(&mut self, name: &'static str) -> Result<(), String> {
+            self.calls.push(name.to_string());
+            if self.fail == Some(name) {
+                Err(format!("{} broke", name))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl FallibleTemplate for Scripted {
+        type Error = String;
+
+        fn step1(&mut self) -> Result<(), String> {
+            self.step("step1")
+        }
+
+        fn step2(&mut self) -> Result<(), String> {
+            self.step("step2")
+        }
+
+        fn step3(&mut self) -> Result<(), String> {
+            self.step("step3")
+        }
+
+        fn should_run(&self, step: &str) -> bool {
+            !self.skip.contains(&step)
+        }
+
+        fn before_step(&mut self, step: &str) {
+            self.calls.push(format!("before {}", step));
+        }
+
+        fn after_step(&mut self, timing: &StepTiming) {
+            self.calls.push(format!("after {} {:?}", timing.name, timing.status));
+        }
+    }
+
+    fn statuses(report: &TemplateReport) -> Vec<(&'static str, StepStatus)> {
+        report.steps.iter().map(|s| (s.name, s.status)).collect()
+    }
+
+    #[test]
+    fn hooks_wrap_every_step_in_order() {
+        let mut template = Scripted::new(None, &[]);
+        let report = template.run().unwrap();
+        assert_eq!(
+            template.calls,
+            vec![
+                "before step1", "step1", "after step1 Completed",
+                "before step2", "step2", "after step2 Completed",
+                "before step3", "step3", "after step3 Completed",
+            ]
+        );
+        assert_eq!(report.total(), report.steps.iter().map(|s| s.elapsed).sum());
+    }
+
+    #[test]
+    fn first_error_stops_the_run_and_names_the_step() {
+        let mut template = Scripted::new(Some("step2"), &[]);
+        let error = template.run().unwrap_err();
+        assert_eq!(error.step, "step2");
+        assert_eq!(error.error, "step2 broke");
+        assert_eq!(error.to_string(), "step step2 failed: step2 broke");
+        // after_step 在失败时也会调用，step3 不再执行
+        assert_eq!(
+            template.calls,
+            vec!["before step1", "step1", "after step1 Completed", "before step2", "step2", "after step2 Failed"]
+        );
+        assert_eq!(statuses(&error.report), vec![("step1", StepStatus::Completed), ("step2", StepStatus::Failed)]);
+        assert_eq!(error.report.status("step3"), None);
+    }
+
+    #[test]
+    fn skipped_steps_are_recorded_without_hooks() {
+        let mut template = Scripted::new(Some("step2"), &["step2"]);
+        let report = template.run().unwrap();
+        assert_eq!(
+            statuses(&report),
+            vec![("step1", StepStatus::Completed), ("step2", StepStatus::Skipped), ("step3", StepStatus::Completed)]
+        );
+        assert!(!template.calls.iter().any(|call| call.contains("step2")));
+        assert_eq!(report.steps[1].elapsed, Duration::ZERO);
+    }
+
+    #[test]
+    fn export_job_uses_its_own_step_names() {
+        let mut job = ExportJob::new(vec![" a ", "", "b"], true);
+        job.run().unwrap();
+        assert_eq!(job.written, vec!["A", "B"]);
+        assert_eq!(job.log, vec!["begin extract", "end extract Completed", "begin transform", "end transform Completed", "begin load", "end load Completed"]);
+
+        // 没有记录时跳过清洗
+        let mut empty = ExportJob::new(vec![], true);
+        let report = empty.run().unwrap();
+        assert_eq!(report.status("transform"), Some(StepStatus::Skipped));
+        assert_eq!(report.steps.len(), 3);
+
+        let mut readonly = ExportJob::new(vec!["c"], false);
+        let error = readonly.run().unwrap_err();
+        assert_eq!(error.step, "load");
+        assert_eq!(error.to_string(), "step load failed: target is read-only, 1 rows not written");
+        assert_eq!(error.report.status("load"), Some(StepStatus::Failed));
+        assert_eq!(readonly.log.last().unwrap(), "end load Failed");
+    }
+
+    fn amounts() -> Table {
+        Table::new(vec![Column::text("Item"), Column::number("Amount", NumberFormat::Fixed { decimals: 2, thousands: false })])
+            .row(vec!["a".into(), 0.1.into()])
+            .row(vec!["b".into(), 0.2.into()])
+            .totals("Total")
+    }
+
+    #[test]
+    fn csv_totals_are_rounded_to_the_column_decimals() {
+        let csv = CsvRenderer.render(&Report::new("r").section(Section::new("s").table(amounts())));
+        assert_eq!(csv, "Item,Amount\na,0.1\nb,0.2\nTotal,0.3\n");
+        let percent = Table::new(vec![Column::text("k"), Column::number("p", NumberFormat::Percent { decimals: 1 }).total(true)])
+            .row(vec!["x".into(), 0.1234.into()])
+            .row(vec!["y".into(), 0.0001.into()])
+            .totals("Total");
+        assert_eq!(percent.totals_row().unwrap()[1], Cell::Number(0.124));
+    }
+
+    #[test]
+    fn totals_label_moves_to_the_first_text_column() {
+        let table = Table::new(vec![
+            Column::number("Qty", NumberFormat::Fixed { decimals: 0, thousands: true }),
+            Column::text("Item"),
+            Column::text("Note"),
+        ])
+        .row(vec![1200.0.into(), "a".into(), "".into()])
+        .row(vec![300.0.into(), "b".into(), "".into()])
+        .totals("Total");
+        assert_eq!(table.formatted_totals().unwrap(), vec!["1,500", "Total", ""]);
+
+        let numbers_only = Table::new(vec![Column::number("Qty", NumberFormat::Fixed { decimals: 0, thousands: true })])
+            .row(vec![1200.0.into()])
+            .row(vec![300.0.into()])
+            .totals("Total");
+        assert_eq!(numbers_only.formatted_totals().unwrap(), vec!["Total 1,500"]);
+    }
+
+    #[test]
+    fn markdown_escapes_every_text() {
+        let report = Report::new("Sales *draft*")
+            .meta("Owner", "A & B")
+            .section(
+                Section::new("# Regions")
+                    .paragraph("first line\n- not a list\n2. not a list either\n    not code <b>")
+                    .table(
+                        Table::new(vec![Column::text("Name"), Column::text("Note")])
+                            .row(vec!["a|b".into(), "line one\nline two".into()]),
+                    ),
+            )
+            .footer("_end_");
+        let markdown = MarkdownRenderer.render(&report);
+        assert!(markdown.starts_with("# Sales \\*draft\\*\n\n**Owner:** A \\& B  \n\n## \\# Regions\n\n"));
+        assert!(markdown.contains("first line\n\\- not a list\n2\\. not a list either\nnot code \\<b\\>\n\n"));
+        assert!(markdown.contains("| a\\|b | line one<br>line two |\n"));
+        assert!(markdown.ends_with("_\\_end\\__\n"));
+        // 每一行表格都只有两列
+        let table_lines: Vec<&str> = markdown.lines().filter(|line| line.starts_with('|')).collect();
+        assert_eq!(table_lines.len(), 3);
+        assert!(table_lines.iter().all(|line| line.replace("\\|", "").matches('|').count() == 3));
+    }
+    #[test]
+    fn sales_report_as_markdown() {
+        let markdown = MarkdownRenderer.render(&sales_report());
+        assert!(markdown.starts_with("# Quarterly sales\n\n**Period:** 2024 Q1  \n"));
+        assert!(markdown.contains("| Region         |    Orders |         Revenue | Margin | Status |\n| :------------- | --------: | --------------: | -----: | :----: |\n"));
+        assert!(markdown.contains("| \\<West\\>       |        15 |        -$120.50 |  -2.0% |        |\n"));
+        assert!(markdown.contains("| **Total**      | **1,300** | **$102,965.90** |        |        |\n"));
+    }
+
+    #[test]
+    fn sales_report_as_html() {
+        let html = HtmlRenderer.render(&sales_report());
+        assert!(html.starts_with("<!DOCTYPE html>") && html.ends_with("</html>\n"));
+        assert!(html.contains("<p>Revenue by region. Note: &lt;script&gt; tags in names must be escaped.</p>"));
+        assert!(html.contains("<dd>Finance &amp; Ops</dd>"));
+        assert!(html.contains("<td style=\"text-align:left\">&lt;West&gt;</td>"));
+        assert!(html.contains("<tfoot>\n<tr><td style=\"text-align:left\">Total</td><td style=\"text-align:right\">1,300</td>"));
+    }
+
+    #[test]
+    fn sales_report_as_csv() {
+        assert_eq!(
+            CsvRenderer.render(&sales_report()),
+            "Region,Orders,Revenue,Margin,Status\n\
+             North,1200,98765.4,0.213,ok\n\
+             \"South, coastal\",85,4321,0.05,watch\n\
+             <West>,15,-120.5,-0.02,\n\
+             Total,1300,102965.9,,\n"
+        );
+    }
+
+    #[test]
+    fn number_formats_round_and_group() {
+        assert_eq!(NumberFormat::Fixed { decimals: 2, thousands: true }.format(-1234567.891), "-1,234,567.89");
+        assert_eq!(NumberFormat::Currency { symbol: "$".to_string(), decimals: 2 }.format(-0.001), "$0.00");
+    }
+}
@@ -0,0 +1,285 @@
+//! 状态机迁移日志与回放
+//! RecordingMachine 包装一个 StateMachine，每次触发事件都记录：事件、迁移前后的状态、时间戳、
+//! 各守卫条件的检查结果，以及失败原因。
+//! 设备在现场进入了意料之外的状态时，可以把日志导出为 JSON lines 离线分析，
+//! 也可以用同一个迁移表重新构建状态机，回放日志到任意一条记录，还原当时的状态。
+//! 注意：回放只重放事件，通过 context_mut 在事件之外对上下文的修改不会被记录。
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::behavioral::clock::{Clock, ManualClock};
+use crate::behavioral::escape::json_string;
+use crate::behavioral::state_machine::{voting_machine, Firing, StateMachine, TransitionError, VoteEvent};
+pub use crate::behavioral::state_machine::GuardCheck;
+
+/// 一条迁移记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransitionRecord<S, E> {
+    /// 序号，从 0 开始
+    pub seq: usize,
+    /// 触发时的时钟读数
+    pub at: Duration,
+    pub event: E,
+    pub from: S,
+    /// 迁移后的状态，迁移失败时为 None
+    pub to: Option<S>,
+    /// 按检查顺序记录的守卫结果
+    pub guards: Vec<GuardCheck>,
+    /// 迁移失败的原因
+    pub error: Option<String>,
+}
+
+/// 回放失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// 要回放的记录数超过了日志长度
+    OutOfRange { requested: usize, available: usize },
+    /// 回放结果与记录不一致，通常是迁移表或守卫依赖的上下文发生了变化
+    Diverged { seq: usize, expected: String, actual: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::OutOfRange { requested, available } => {
+                write!(f, "cannot replay {} records, log only has {}", requested, available)
+            }
+            ReplayError::Diverged { seq, expected, actual } => {
+                write!(f, "replay diverged at record {}: expected {}, got {}", seq, expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+//-----------------迁移日志---------------------------------------------------------------------
+/// 迁移日志
+#[derive(Debug, Clone)]
+pub struct TransitionLog<S, E> {
+    records: Vec<TransitionRecord<S, E>>,
+}
+
+impl<S, E> TransitionLog<S, E>
+where
+    S: Copy + PartialEq + fmt::Debug,
+    E: Copy + PartialEq + fmt::Debug,
+{
+    pub fn records(&self) -> &[TransitionRecord<S, E>] {
+        &self.records
+    }
+
+    /// 导出为 JSON lines，每条记录一行；状态和事件使用 Debug 格式
+    pub fn to_json_lines(&self) -> String {
+        let mut out = String::new();
+        for record in &self.records {
+            let guards: Vec<String> = record
+                .guards
+                .iter()
+                .map(|check| format!("{{\"name\":{},\"passed\":{}}}", json_string(&check.name), check.passed))
+                .collect();
+            out.push_str(&format!(
+                "{{\"seq\":{},\"at_ms\":{},\"event\":{},\"from\":{},\"to\":{},\"guards\":[{}],\"error\":{}}}\n",
+                record.seq,
+                record.at.as_millis(),
+                json_string(&format!("{:?}", record.event)),
+                json_string(&format!("{:?}", record.from)),
+                record.to.map_or("null".to_string(), |to| json_string(&format!("{:?}", to))),
+                guards.join(","),
+                record.error.as_deref().map_or("null".to_string(), json_string)
+            ));
+        }
+        out
+    }
+
+    /// 用 factory 构建一个新的状态机，依次回放前 count 条记录中的事件
+    /// 每一步都与记录比对，结果不一致时返回 Diverged
+    pub fn replay<C>(
+        &self,
+        factory: impl FnOnce() -> StateMachine<S, E, C>,
+        count: usize,
+    ) -> Result<StateMachine<S, E, C>, ReplayError> {
+        if count > self.records.len() {
+            return Err(ReplayError::OutOfRange { requested: count, available: self.records.len() });
+        }
+        let mut machine = factory();
+        for record in &self.records[..count] {
+            let diverged = |actual: String| ReplayError::Diverged {
+                seq: record.seq,
+                expected: format!("{:?} -> {:?}", record.from, record.to),
+                actual,
+            };
+            if machine.state() != record.from {
+                return Err(diverged(format!("state {:?}", machine.state())));
+            }
+            let to = machine.fire(record.event).ok();
+            if to != record.to {
+                return Err(diverged(format!("{:?} -> {:?}", record.from, to)));
+            }
+        }
+        Ok(machine)
+    }
+
+    /// 第 seq 条记录发生之后状态机所处的状态，不执行任何动作
+    pub fn state_after(&self, seq: usize) -> Option<S> {
+        self.records.get(seq).map(|record| record.to.unwrap_or(record.from))
+    }
+}
+
+//-----------------带记录的状态机---------------------------------------------------------------------
+/// 记录每次迁移的状态机包装
+pub struct RecordingMachine<S, E, C> {
+    machine: StateMachine<S, E, C>,
+    clock: Arc<dyn Clock>,
+    log: TransitionLog<S, E>,
+}
+
+impl<S, E, C> RecordingMachine<S, E, C>
+where
+    S: Copy + PartialEq + fmt::Debug,
+    E: Copy + PartialEq + fmt::Debug,
+{
+    pub fn new(machine: StateMachine<S, E, C>, clock: Arc<dyn Clock>) -> Self {
+        RecordingMachine { machine, clock, log: TransitionLog { records: Vec::new() } }
+    }
+
+    /// 触发事件并记录，失败的迁移同样会被记录；守卫结果来自状态机自己的检查，不会重复求值
+    pub fn fire(&mut self, event: E) -> Result<S, TransitionError<S, E>> {
+        let from = self.machine.state();
+        let Firing { result, guards } = self.machine.fire_traced(event);
+        self.log.records.push(TransitionRecord {
+            seq: self.log.records.len(),
+            at: self.clock.now(),
+            event,
+            from,
+            to: result.as_ref().ok().copied(),
+            guards,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        result
+    }
+
+    pub fn machine(&self) -> &StateMachine<S, E, C> {
+        &self.machine
+    }
+
+    pub fn log(&self) -> &TransitionLog<S, E> {
+        &self.log
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+pub fn main() {
+    let clock = Arc::new(ManualClock::new(Duration::from_secs(1_700_000_000)));
+    let mut machine = RecordingMachine::new(voting_machine(), clock.clone());
+
+    machine.fire(VoteEvent::Vote).unwrap();
+    clock.advance(Duration::from_millis(250));
+    let _ = machine.fire(VoteEvent::Vote);
+    clock.advance(Duration::from_millis(250));
+    machine.fire(VoteEvent::ClosePoll).unwrap();
+    machine.fire(VoteEvent::ReopenPoll).unwrap();
+
+    print!("{}", machine.log().to_json_lines());
+
+    // 回放前两条记录，还原到锁定之前
+    let replayed = machine.log().replay(voting_machine, 2).unwrap();
+    println!("state after 2 records: {:?}", replayed.state());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavioral::state_machine::{StateMachineBuilder, VoteState};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 投票、重复投票、关闭、重新开放，每步之间时钟前进 250ms
+    fn recorded() -> RecordingMachine<VoteState, VoteEvent, bool> {
+        let clock = Arc::new(ManualClock::new(Duration::from_secs(10)));
+        let mut machine = RecordingMachine::new(voting_machine(), clock.clone());
+        for event in [VoteEvent::Vote, VoteEvent::Vote, VoteEvent::ClosePoll, VoteEvent::ReopenPoll] {
+            let _ = machine.fire(event);
+            clock.advance(Duration::from_millis(250));
+        }
+        machine
+    }
+
+    #[test]
+    fn records_events_states_guards_and_errors() {
+        let machine = recorded();
+        let records = machine.log().records();
+        assert_eq!(records.len(), 4);
+        assert_eq!((records[0].from, records[0].to, records[0].at), (VoteState::NotVoted, Some(VoteState::Voted), Duration::from_secs(10)));
+        assert_eq!(records[1].to, None);
+        assert_eq!(records[1].error.as_deref(), Some("no transition defined for event Vote in state Voted"));
+        assert_eq!(records[3].at, Duration::from_millis(10_750));
+        // Locked + ReopenPoll 的第一条迁移守卫通过，第二条不再检查
+        assert_eq!(records[3].guards, [GuardCheck { name: "has voted".to_string(), passed: true }]);
+        assert_eq!(records[3].to, Some(VoteState::Voted));
+    }
+
+    #[test]
+    fn exports_one_json_object_per_record() {
+        let lines = recorded().log().to_json_lines();
+        let lines: Vec<&str> = lines.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[1],
+            r#"{"seq":1,"at_ms":10250,"event":"Vote","from":"Voted","to":null,"guards":[],"error":"no transition defined for event Vote in state Voted"}"#
+        );
+        assert_eq!(
+            lines[3],
+            r#"{"seq":3,"at_ms":10750,"event":"ReopenPoll","from":"Locked","to":"Voted","guards":[{"name":"has voted","passed":true}],"error":null}"#
+        );
+    }
+
+    #[test]
+    fn replay_rebuilds_the_machine_at_any_earlier_point() {
+        let machine = recorded();
+        let log = machine.log();
+        let expected = [VoteState::NotVoted, VoteState::Voted, VoteState::Voted, VoteState::Locked, VoteState::Voted];
+        for (count, state) in expected.into_iter().enumerate() {
+            let replayed = log.replay(voting_machine, count).unwrap();
+            assert_eq!(replayed.state(), state, "after {} records", count);
+            if count > 0 {
+                assert_eq!(log.state_after(count - 1), Some(state));
+            }
+        }
+        assert!(*log.replay(voting_machine, 1).unwrap().context());
+        assert_eq!(log.state_after(4), None);
+        assert_eq!(log.replay(voting_machine, 5).err(), Some(ReplayError::OutOfRange { requested: 5, available: 4 }));
+    }
+
+    #[test]
+    fn replay_reports_divergence_from_a_changed_table() {
+        let machine = recorded();
+        let changed = || {
+            StateMachineBuilder::new(VoteState::NotVoted)
+                .transition(VoteState::NotVoted, VoteEvent::Vote, VoteState::Locked)
+                .build(false)
+        };
+        let error = machine.log().replay(changed, 2).err().unwrap();
+        assert_eq!(
+            error,
+            ReplayError::Diverged { seq: 0, expected: "NotVoted -> Some(Voted)".to_string(), actual: "NotVoted -> Some(Locked)".to_string() }
+        );
+    }
+
+    #[test]
+    fn guards_are_evaluated_once_per_event() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let table = StateMachineBuilder::new(VoteState::NotVoted)
+            .transition(VoteState::NotVoted, VoteEvent::Vote, VoteState::Voted)
+            .guard("counted", move |_: &()| counter.fetch_add(1, Ordering::SeqCst) > 0)
+            .build(());
+        let mut machine = RecordingMachine::new(table, Arc::new(ManualClock::new(Duration::ZERO)));
+        assert!(machine.fire(VoteEvent::Vote).is_err());
+        assert!(machine.fire(VoteEvent::Vote).is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let guards: Vec<bool> = machine.log().records().iter().map(|r| r.guards[0].passed).collect();
+        assert_eq!(guards, [false, true]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Typestate（编译期状态）版本的投票机
+//! behavioral::state 中的 VotingMachine 用 `Box<dyn VotingState>` 保存状态，
+//! 投票时还要借助 `std::mem::replace` 放一个临时的 NotVoted 占位，非法操作只能在运行时打印提示。
+//! 这里把状态放进类型参数：`VotingMachine<NotVoted>`、`VotingMachine<Voted>`、`VotingMachine<Locked>`，
+//! 迁移方法消耗 self 并返回下一个状态的类型，某个状态下不允许的操作根本没有对应的方法，
+//! 写错了在编译期就会报错，运行时不需要任何检查。
+//!
+//! ```
+//! use design_pattern::behavioral::typestate::VotingMachine;
+//!
+//! let machine = VotingMachine::new("alice");
+//! let machine = machine.vote();
+//! let machine = machine.lock();
+//! assert_eq!(machine.state_name(), "Locked");
+//! ```
+
+use std::marker::PhantomData;
+
+/// 防止在模块外实现新的状态
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// 状态标记 trait
+pub trait VotingState: sealed::Sealed {
+    /// 状态名称
+    const NAME: &'static str;
+}
+
+/// 未投票状态
+pub struct NotVoted;
+/// 已投票状态
+pub struct Voted;
+/// 锁定状态
+pub struct Locked;
+
+impl sealed::Sealed for NotVoted {}
+impl sealed::Sealed for Voted {}
+impl sealed::Sealed for Locked {}
+
+impl VotingState for NotVoted {
+    const NAME: &'static str = "NotVoted";
+}
+
+impl VotingState for Voted {
+    const NAME: &'static str = "Voted";
+}
+
+impl VotingState for Locked {
+    const NAME: &'static str = "Locked";
+}
+
+/// 投票机，状态 S 只存在于类型中，不占运行时空间
+///
+/// 锁定后没有 `vote` 方法，下面的代码无法编译：
+/// ```compile_fail,E0599
+/// use design_pattern::behavioral::typestate::VotingMachine;
+///
+/// let machine = VotingMachine::new("alice").vote().lock();
+/// machine.vote();
+/// ```
+///
+/// 已投票的机器也不能再投票：
+/// ```compile_fail,E0599
+/// use design_pattern::behavioral::typestate::VotingMachine;
+///
+/// let machine = VotingMachine::new("alice").vote();
+/// machine.vote();
+/// ```
+///
+/// 未投票的机器不能直接锁定：
+/// ```compile_fail,E0599
+/// use design_pattern::behavioral::typestate::VotingMachine;
+///
+/// let machine = VotingMachine::new("alice");
+/// machine.lock();
+/// ```
+///
+/// 迁移会消耗旧的机器，不能继续使用迁移前的状态：
+/// ```compile_fail,E0382
+/// use design_pattern::behavioral::typestate::VotingMachine;
+///
+/// let machine = VotingMachine::new("alice");
+/// let _voted = machine.vote();
+/// machine.status();
+/// ```
+pub struct VotingMachine<S: VotingState> {
+    voter: String,
+    _state: PhantomData<S>,
+}
+
+impl<S: VotingState> VotingMachine<S> {
+    /// 迁移到状态 T，投票人信息原样带过去
+    fn transition<T: VotingState>(self) -> VotingMachine<T> {
+        VotingMachine {
+            voter: self.voter,
+            _state: PhantomData,
+        }
+    }
+
+    /// 投票人
+    pub fn voter(&self) -> &str {
+        &self.voter
+    }
+
+    /// 当前状态名称
+    pub fn state_name(&self) -> &'static str {
+        S::NAME
+    }
+
+    /// 打印当前状态
+    pub fn status(&self) {
+        println!("Current state: {}", S::NAME);
+    }
+}
+
+impl VotingMachine<NotVoted> {
+    /// 创建投票机，初始为未投票状态
+    pub fn new(voter: &str) -> Self {
+        VotingMachine {
+            voter: voter.to_string(),
+            _state: PhantomData,
+        }
+    }
+
+    /// 投票，转为 Voted 状态
+    pub fn vote(self) -> VotingMachine<Voted> {
+        println!("{} voted successfully!", self.voter);
+        self.transition()
+    }
+}
+
+impl VotingMachine<Voted> {
+    /// 锁定投票，转为 Locked 状态
+    pub fn lock(self) -> VotingMachine<Locked> {
+        println!("Voting is locked, {} cannot vote anymore.", self.voter);
+        self.transition()
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+pub fn main() {
+    let machine = VotingMachine::new("alice");
+    machine.status();
+
+    let machine = machine.vote();
+    machine.status();
+
+    let machine = machine.lock();
+    machine.status();
+    assert_eq!(std::mem::size_of::<VotingMachine<Locked>>(), std::mem::size_of::<String>());
+
+    // 下面这行无法编译：VotingMachine<Locked> 没有 vote 方法
+    // machine.vote();
+}
@@ -1,74 +1,103 @@
 //! Visitor Pattern in Rust - 扩展 VotingMachine 的行为而不修改结构
 //! 
-// 前置状态 trait 和结构体（与你现有代码一致）
-trait VotingState: Send + Sync {
-    fn vote(self: Box<Self>) -> Box<dyn VotingState>;
-    fn status(&self);
-    fn accept(&self, visitor: &mut dyn StateVisitor);
-}
+//! 投票机、状态对象和迁移规则都来自 behavioral::state，这里只增加访问者和 accept。
+//! 状态对象只有一条接受访问者的路径：Visitable::accept，VotingState 以它为父 trait，
+//! 有返回值的访问者通过 visit 适配。
+use crate::behavioral::state::{VoteOperation, VoteStatus, VotingState};
 
-#[derive(Debug)]
-struct NotVoted;
+pub use crate::behavioral::state::{Locked, NotVoted, Voted, VotingMachine};
 
-#[derive(Debug)]
-struct Voted;
+// ======================
+// 生成 accept 的宏
+// ======================
 
-#[derive(Debug)]
-struct Locked;
+/// 为每个状态实现 Visitable：accept 调用访问者对应的 visit 方法。
+/// 参数可以是状态本身，也可以是能解引用成状态的包装类型
+/// ```
+/// use std::ops::Deref;
+/// use design_pattern::behavioral::visitor::{visit, LabelVisitor, NotVoted};
+/// use design_pattern::visitable;
+///
+/// // 带倒计时的未投票状态，访问时当作 NotVoted
+/// struct Countdown {
+///     state: NotVoted,
+///     seconds_left: u64,
+/// }
+///
+/// impl Deref for Countdown {
+///     type Target = NotVoted;
+///     fn deref(&self) -> &NotVoted {
+///         &self.state
+///     }
+/// }
+///
+/// visitable!(Countdown => visit_not_voted);
+///
+/// let countdown = Countdown { state: NotVoted, seconds_left: 30 };
+/// assert_eq!(countdown.seconds_left, 30);
+/// assert_eq!(visit(&countdown, &mut LabelVisitor), "Cast your vote");
+/// ```
+#[macro_export]
+macro_rules! visitable {
+    ($($state:ty => $visit:ident),* $(,)?) => {
+        $(
+            impl $crate::behavioral::visitor::Visitable for $state {
+                fn accept(&self, visitor: &mut dyn $crate::behavioral::visitor::StateVisitor<Output = ()>) {
+                    visitor.$visit(self)
+                }
+            }
+        )*
+    };
+}
 
-impl VotingState for NotVoted {
-    fn vote(self: Box<Self>) -> Box<dyn VotingState> {
-        println!("You voted successfully!");
-        Box::new(Voted)
-    }
+visitable!(NotVoted => visit_not_voted, Voted => visit_voted, Locked => visit_locked);
 
-    fn status(&self) {
-        println!("Current state: NotVoted");
-    }
+// ======================
+// 定义 Visitor trait
+// ======================
 
-    fn accept(&self, visitor: &mut dyn StateVisitor) {
-        visitor.visit_not_voted(self);
-    }
+/// 状态访问者，Output 为每次访问得到的结果，只需要打印或修改自身时为 ()
+pub trait StateVisitor {
+    type Output;
+
+    fn visit_not_voted(&mut self, state: &NotVoted) -> Self::Output;
+    fn visit_voted(&mut self, state: &Voted) -> Self::Output;
+    fn visit_locked(&mut self, state: &Locked) -> Self::Output;
 }
 
-impl VotingState for Voted {
-    fn vote(self: Box<Self>) -> Box<dyn VotingState> {
-        println!("You have already voted.");
-        Box::new(Locked)
-    }
+/// 可以被 StateVisitor 访问的类型，通常用 visitable! 宏实现。
+/// accept 只接受 Output = () 的访问者，这样 Box<dyn VotingState> 也能调用；有返回值的访问者用 visit
+pub trait Visitable {
+    fn accept(&self, visitor: &mut dyn StateVisitor<Output = ()>);
+}
 
-    fn status(&self) {
-        println!("Current state: Voted");
-    }
+/// 让 state 接受任意访问者并返回访问结果
+pub fn visit<T: Visitable + ?Sized, V: StateVisitor>(state: &T, visitor: &mut V) -> V::Output {
+    let mut collect = Collect { visitor, output: None };
+    state.accept(&mut collect);
+    collect.output.expect("every state calls exactly one visit method")
+}
 
-    fn accept(&self, visitor: &mut dyn StateVisitor) {
-        visitor.visit_voted(self);
-    }
+/// 把有返回值的访问者包装成 Output = () 的访问者，结果保存在 output 中
+struct Collect<'a, V: StateVisitor> {
+    visitor: &'a mut V,
+    output: Option<V::Output>,
 }
 
-impl VotingState for Locked {
-    fn vote(self: Box<Self>) -> Box<dyn VotingState> {
-        println!("Voting is locked, you cannot vote anymore.");
-        self
-    }
+impl<V: StateVisitor> StateVisitor for Collect<'_, V> {
+    type Output = ();
 
-    fn status(&self) {
-        println!("Current state: Locked");
+    fn visit_not_voted(&mut self, state: &NotVoted) {
+        self.output = Some(self.visitor.visit_not_voted(state));
     }
 
-    fn accept(&self, visitor: &mut dyn StateVisitor) {
-        visitor.visit_locked(self);
+    fn visit_voted(&mut self, state: &Voted) {
+        self.output = Some(self.visitor.visit_voted(state));
     }
-}
-
-// ======================
-// 定义 Visitor trait
-// ======================
 
-trait StateVisitor {
-    fn visit_not_voted(&mut self, state: &NotVoted);
-    fn visit_voted(&mut self, state: &Voted);
-    fn visit_locked(&mut self, state: &Locked);
+    fn visit_locked(&mut self, state: &Locked) {
+        self.output = Some(self.visitor.visit_locked(state));
+    }
 }
 
 // ======================
@@ -79,6 +108,8 @@ trait StateVisitor {
 struct LoggingVisitor;
 
 impl StateVisitor for LoggingVisitor {
+    type Output = ();
+
     fn visit_not_voted(&mut self, _: &NotVoted) {
         println!("[Log] Current state is NotVoted");
     }
@@ -96,6 +127,8 @@ impl StateVisitor for LoggingVisitor {
 struct AuditVisitor;
 
 impl StateVisitor for AuditVisitor {
+    type Output = ();
+
     fn visit_not_voted(&mut self, _: &NotVoted) {
         println!("[Audit] NotVoted: Ready to vote");
     }
@@ -109,34 +142,80 @@ impl StateVisitor for AuditVisitor {
     }
 }
 
-// ======================
-// 修改 VotingMachine 支持 Visitor
-// ======================
+// 示例 3：界面标签，返回按钮上显示的文字
+pub struct LabelVisitor;
+
+impl StateVisitor for LabelVisitor {
+    type Output = &'static str;
+
+    fn visit_not_voted(&mut self, _: &NotVoted) -> &'static str {
+        "Cast your vote"
+    }
+
+    fn visit_voted(&mut self, _: &Voted) -> &'static str {
+        "Thanks for voting"
+    }
 
-pub struct VotingMachine {
-    state: Box<dyn VotingState>,
+    fn visit_locked(&mut self, _: &Locked) -> &'static str {
+        "Voting closed"
+    }
 }
 
-impl VotingMachine {
-    pub fn new() -> Self {
-        VotingMachine {
-            state: Box::new(NotVoted),
-        }
+// 示例 4：序列化，返回 JSON 字符串
+pub struct JsonVisitor;
+
+impl StateVisitor for JsonVisitor {
+    type Output = String;
+
+    fn visit_not_voted(&mut self, _: &NotVoted) -> String {
+        r#"{"state":"NotVoted","can_vote":true}"#.to_string()
+    }
+
+    fn visit_voted(&mut self, _: &Voted) -> String {
+        r#"{"state":"Voted","can_vote":false}"#.to_string()
+    }
+
+    fn visit_locked(&mut self, _: &Locked) -> String {
+        r#"{"state":"Locked","can_vote":false}"#.to_string()
+    }
+}
+
+// 示例 5：校验某个操作在当前状态下是否允许，由被访问的状态对象回答
+pub struct ValidationVisitor {
+    pub operation: VoteOperation,
+    pub has_voted: bool,
+}
+
+impl ValidationVisitor {
+    fn check(&self, state: &dyn VotingState) -> Result<VoteStatus, String> {
+        state.apply(self.operation, self.has_voted).map(|next| next.kind())
+    }
+}
+
+impl StateVisitor for ValidationVisitor {
+    type Output = Result<VoteStatus, String>;
+
+    fn visit_not_voted(&mut self, state: &NotVoted) -> Self::Output {
+        self.check(state)
     }
 
-    pub fn vote(&mut self) {
-        let old_state = std::mem::replace(&mut self.state, Box::new(NotVoted));
-        let new_state = old_state.vote();
-        self.state = new_state;
+    fn visit_voted(&mut self, state: &Voted) -> Self::Output {
+        self.check(state)
     }
 
-    pub fn status(&self) {
-        self.state.status();
+    fn visit_locked(&mut self, state: &Locked) -> Self::Output {
+        self.check(state)
     }
+}
+
+// ======================
+// 修改 VotingMachine 支持 Visitor
+// ======================
 
-    // 新增方法：接受访问者
-    pub fn accept(&self, visitor: &mut dyn StateVisitor) {
-        self.state.accept(visitor);
+impl VotingMachine {
+    // 新增方法：接受访问者，返回访问者的结果
+    pub fn accept<V: StateVisitor>(&self, visitor: &mut V) -> V::Output {
+        visit(self.current(), visitor)
     }
 }
 
@@ -144,7 +223,7 @@ impl VotingMachine {
 // 使用示例
 // ======================
 
-fn main() {
+pub fn main() {
     let mut machine = VotingMachine::new();
 
     machine.status(); // Current state: NotVoted
@@ -156,8 +235,45 @@ fn main() {
     machine.accept(&mut LoggingVisitor);   // [Log] Current state is Voted
     machine.accept(&mut AuditVisitor);     // [Audit] Voted: Already casted
 
-    machine.vote();
+    machine.vote();   // You have already voted.
+    machine.close_poll().unwrap();
     machine.status(); // Current state: Locked
     machine.accept(&mut LoggingVisitor);   // [Log] Current state is Locked
     machine.accept(&mut AuditVisitor);     // [Audit] Locked: Vote finalized
-}
\ No newline at end of file
+
+    // 有返回值的访问者
+    println!("{}", machine.accept(&mut LabelVisitor));   // Voting closed
+    println!("{}", machine.accept(&mut JsonVisitor));    // {"state":"Locked","can_vote":false}
+    let mut reopen = ValidationVisitor { operation: VoteOperation::ReopenPoll, has_voted: true };
+    assert_eq!(machine.accept(&mut reopen), Ok(VoteStatus::Voted));
+
+    // 单个状态也可以直接接受访问者
+    assert_eq!(visit(&NotVoted, &mut LabelVisitor), "Cast your vote");
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visitors_follow_the_machine_state() {
+        let mut machine = VotingMachine::new();
+        assert_eq!(machine.accept(&mut LabelVisitor), "Cast your vote");
+        machine.vote();
+        assert_eq!(machine.accept(&mut JsonVisitor), r#"{"state":"Voted","can_vote":false}"#);
+        machine.close_poll().unwrap();
+        assert_eq!(machine.accept(&mut LabelVisitor), "Voting closed");
+    }
+
+    #[test]
+    fn validation_asks_the_visited_state() {
+        let mut machine = VotingMachine::new();
+        let mut vote = ValidationVisitor { operation: VoteOperation::Vote, has_voted: false };
+        assert_eq!(machine.accept(&mut vote), Ok(VoteStatus::Voted));
+        machine.vote();
+        assert_eq!(machine.accept(&mut vote), Err("You have already voted.".to_string()));
+        machine.close_poll().unwrap();
+        let mut reopen = ValidationVisitor { operation: VoteOperation::ReopenPoll, has_voted: true };
+        assert_eq!(machine.accept(&mut reopen), Ok(VoteStatus::Voted));
+        assert_eq!(visit(&NotVoted, &mut LabelVisitor), "Cast your vote");
+    }
+}
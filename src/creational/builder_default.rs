@@ -28,6 +28,25 @@ impl Default for Config {
     }
 }
 
+// 读取配置项
+impl Config {
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    pub fn enable_logging(&self) -> bool {
+        self.enable_logging
+    }
+
+    pub fn max_connections(&self) -> usize {
+        self.max_connections
+    }
+}
+
 // Builder 结构体，包含和 Config 相同的字段
 #[derive(Default)]
 pub struct ConfigBuilder {
@@ -71,7 +90,7 @@ impl ConfigBuilder {
     }
 }
 
-fn main() {
+pub fn main() {
     // 使用 Builder 构造 Config，默认值 + 自定义部分字段
     let config = ConfigBuilder::default()
         .timeout(Duration::from_secs(10))
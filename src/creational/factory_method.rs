@@ -1,5 +1,5 @@
 //!  工厂方法模式
-//! ```
+//! ```text
 //! 工厂方法模式（Factory Method Pattern） 是一种创建型设计模式，它通过定义一个用于创建对象的接口（trait 或函数），
 //! 但让子类决定实例化哪一个类。这种模式将对象的创建延迟到实现该接口的具体类型中。
 //! 例：将以“不同类型的按钮”为例，展示如何通过工厂方法创建不同的 UI 控件。
@@ -68,7 +68,7 @@ impl ButtonFactory for WebButtonFactory {
     }
 }
 //-------------------------------
-fn main() {
+pub fn main() {
     //根据平台选择工厂
     let os = "windows";
     let factory: Box<dyn ButtonFactory> = match os {
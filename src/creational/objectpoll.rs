@@ -13,6 +13,10 @@ impl Connection {
         Connection { id }
     }
 
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
     pub fn connect(&self) {
         println!("Connection {} is connecting...", self.id);
     }
@@ -45,9 +49,14 @@ impl ConnectionPool {
     pub fn release_connection(&self, conn: Connection) {
         self.connections.lock().unwrap().push(conn);
     }
+
+    // 空闲连接数
+    pub fn available(&self) -> usize {
+        self.connections.lock().unwrap().len()
+    }
 }
 
-fn main() {
+pub fn main() {
     // 创建包含 3 个连接的连接池（线程安全）
     let pool = ConnectionPool::new(3);
 
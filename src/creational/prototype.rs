@@ -1,8 +1,9 @@
+//! #111
+//! 通过克隆原型对象创建新对象 可用 Clone trait 实现.
+//! 原型模式（Prototype Pattern） 是一种创建型设计模式，它通过克隆一个已有的对象来创建新对象，
+//! 而不是通过构造函数显式创建。这种模式适用于对象的创建成本较高、需要复制已有状态等场景.
+
 use std::{any::Any, fmt};
-/// #111
-/// 通过克隆原型对象创建新对象 可用 Clone trait 实现.
-/// 原型模式（Prototype Pattern） 是一种创建型设计模式，它通过克隆一个已有的对象来创建新对象，
-/// 而不是通过构造函数显式创建。这种模式适用于对象的创建成本较高、需要复制已有状态等场景.
 
 /// 定义一个结构体，表示图形
 #[derive(Clone, Debug)] // 自动生成 clone() 方法，这是原型模式的核心。
@@ -45,7 +46,7 @@ impl Prototype for Circle {
     }
 }
 //-----------------------main方法--------------------------------
-fn main() {
+pub fn main() {
     // 创建一个原型对象
     let original_circle = Circle {
         x: 10,
@@ -1,11 +1,11 @@
 use std::sync::Once;
-/// ------------------------------------------------------------------
-/// 线程安全的单例实现
-/// 线程安全性说明
-/// Once 是标准库中的机制，保证初始化代码只执行一次，即使在多线程环境下也能安全运行。
-/// 使用 unsafe 是因为我们需要手动管理静态可变状态，但通过 Once 保证了访问的安全性。
-/// 返回的是 'static 生命周期的引用，表示该实例存活于整个程序运行期间。
-/// ------------------------------------------------------------------
+// ------------------------------------------------------------------
+// 线程安全的单例实现
+// 线程安全性说明
+// Once 是标准库中的机制，保证初始化代码只执行一次，即使在多线程环境下也能安全运行。
+// 使用 unsafe 是因为我们需要手动管理静态可变状态，但通过 Once 保证了访问的安全性。
+// 返回的是 'static 生命周期的引用，表示该实例存活于整个程序运行期间。
+// ------------------------------------------------------------------
 
 // 单例结构体
 pub struct Singleton {
@@ -26,7 +26,7 @@ impl Singleton {
                 });
             });
 
-            SINGLETON_INSTANCE.as_ref().unwrap()
+            (*std::ptr::addr_of!(SINGLETON_INSTANCE)).as_ref().unwrap()
         }
     }
 
@@ -36,7 +36,7 @@ impl Singleton {
     }
 }
 
-fn main() {
+pub fn main() {
     // 获取单例实例并调用方法
     let instance = Singleton::get_instance();
     instance.do_something();
@@ -16,7 +16,7 @@ impl Singleton2 {
                 }));
             });
 
-            SINGLETON_INSTANCE.as_ref().unwrap()
+            (*std::ptr::addr_of!(SINGLETON_INSTANCE)).as_ref().unwrap()
         }
     }
 }
@@ -1,6 +1,6 @@
 ///如果你正在构建一个模块化系统，推荐将“单例”逻辑封装在模块中，并通过函数暴露接口，而不是直接暴露结构体
 /// 
-mod config {
+pub mod config {
     use std::sync::{Once, Mutex};
     use std::collections::HashMap;
 
@@ -12,7 +12,7 @@ mod config {
             INIT.call_once(|| {
                 CONFIG_MAP = Some(Mutex::new(HashMap::new()));
             });
-            CONFIG_MAP.as_ref().unwrap()
+            (*std::ptr::addr_of!(CONFIG_MAP)).as_ref().unwrap()
         }
     }
 
@@ -25,4 +25,9 @@ mod config {
         let map = get_config_map().lock().unwrap();
         map.get(key).cloned()
     }
-}
\ No newline at end of file
+}
+pub fn main() {
+    config::set("mode", "production");
+    println!("mode = {:?}", config::get("mode"));
+    println!("missing = {:?}", config::get("missing"));
+}
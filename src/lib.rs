@@ -0,0 +1,7 @@
+//! 设计模式示例
+//! 各个模式的实现放在库中，src/main.rs 只负责演示；doc 测试和单元测试都针对这里导出的模块运行。
+
+pub mod behavioral;
+pub mod creational;
+pub mod specific;
+pub mod structural;
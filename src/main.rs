@@ -1,40 +1,4 @@
-mod behavioral;
-mod creational;
-mod specific;
-mod structural;
-
-use crate::behavioral::memento::{Editor, Memento};
-use crate::behavioral::iterator::MyIterator;
-use crate::behavioral::state::{VotingMachine};
-use crate::creational::factory_method::{Button, ButtonFactory, WindowsButtonFactory, WebButtonFactory};
-use crate::creational::prototype::{Circle, Prototype};
-use crate::behavioral::command::{Command, Light, LightOnCommand, RemoteControl};
-use crate::behavioral::observer::{Subject, Observer, ConcreteObserver};
-use crate::structural::decorator::{Component, ConcreteComponent, ConcreteDecoratorA};
-use crate::specific::panic_result::{divide, safe_divide};
-use crate::creational::builder_default::{Config, ConfigBuilder};
-use crate::specific::phantom_data::MyBox2;
-use crate::specific::drop::MyResource;
-use crate::specific::interior_mutability::{MyData, SharedCounter, ReadWriteData};
-use std::sync::Arc;
-use std::sync::Mutex;
-use std::thread::{self, JoinHandle};
-use std::time::Duration;
-
-// use bridge::{Circle, DirectXRenderer, OpenGLRenderer, Renderer};
-
-use crate::specific::deref_coercion::MyBox;
-use crate::specific::deref_coercion::say_hello;
-use crate::specific::new_type::{UserId};
-use crate::creational::objectpoll::{Connection, ConnectionPool};
-use crate::creational::singleton2::Singleton2;
-use crate::creational::singleton::Singleton;
-use crate::structural::adapter::{LegacyRectangle, RectangleAdapter, Shape};
-use crate::structural::flyweight::FlyweightFactory;
-// use crate::proxy::{Proxy, RealSubject, Subject};
-use crate::behavioral::responsibility_chain::{ConcreteHandlerA, ConcreteHandlerB, Handler, Request};
-use crate::behavioral::strategy::{ConcreteStrategyA, ConcreteStrategyB, Context};
-use crate::behavioral::template_method::{AbstractClass, ConcreteClassA, ConcreteClassB};
+use design_pattern::behavioral::memento::Editor;
 
 fn main() {
     //备忘录模式---------------------------------------------------
@@ -54,12 +18,16 @@ fn main() {
     println!("恢复到之前的状态: {}", editor.get_content());
 
     //迭代器模式---------------------------------------------------
+    // use design_pattern::behavioral::iterator::MyIterator;
     // let numbers = [1, 2, 3, 4, 5];
     // let mut iter = MyIterator::new(&numbers);
     // while let Some(number) = iter.next() {
     //      println!("Number: {}", number);
     // }
     //状态模式---------------------------------------------------
+    // use design_pattern::behavioral::state::VotingMachine;
+    // use std::sync::Arc;
+    // use std::time::Duration;
     // 创建投票机器
     // let mut machine = VotingMachine::new();
 
@@ -74,7 +42,18 @@ fn main() {
 
     // machine.status();
 
+    // // 管理员关闭投票后锁定；凭口令重新开放
+    // let clock = Arc::new(design_pattern::behavioral::clock::ManualClock::new(Duration::ZERO));
+    // let poll = design_pattern::behavioral::state::Poll::new(clock.clone()).with_admin_token("secret");
+    // let mut machine = VotingMachine::with_poll(poll);
+    // machine.open_poll(Some(Duration::from_secs(60))).unwrap();
+    // clock.advance(Duration::from_secs(61));
+    // machine.vote(); // 截止时间已过，自动锁定
+    // machine.reopen_poll("secret", None).unwrap();
+    // machine.vote();
+
     //工厂方法模式-------------------------------------------------------
+    // use design_pattern::creational::factory_method::{ButtonFactory, WebButtonFactory, WindowsButtonFactory};
     //根据平台选择工厂
     // let os = "windows";
     // let factory: Box<dyn ButtonFactory> = match os {
@@ -90,6 +69,7 @@ fn main() {
     // button.on_click();
 
     //原型模式----------------------------------------------------------
+    // use design_pattern::creational::prototype::{self, Prototype};
     // 创建一个原型对象
     // let original_circle = prototype::Circle {
     //     x: 10,
@@ -114,6 +94,8 @@ fn main() {
     // }
 
     //命令模式----------------------------------------------------------
+    // use design_pattern::behavioral::command::{Light, LightOnCommand, RemoteControl};
+    // use std::sync::{Arc, Mutex};
     //创建被调用对象
     //创建灯对象
     // let light = Arc::new(Mutex::new(Light::new()));
@@ -134,6 +116,7 @@ fn main() {
     // remote_control.undo_button();
 
     //观察者模式--------------------------------------------------------
+    // use design_pattern::behavioral::observer::{self, ConcreteObserver};
        // 创建主题
     // let mut subject = observer::Subject::new();
     // // 创建观察者
@@ -148,11 +131,13 @@ fn main() {
 
 
     //装饰器模式--------------------------------------------------------
+    // use design_pattern::structural::decorator::{Component, ConcreteComponent, ConcreteDecoratorA};
     // let component = ConcreteComponent;
     // let decorated_component = ConcreteDecoratorA::new(component);
     // decorated_component.operation();
 
     //panic 和 result ------------------------------------------------
+    // use design_pattern::specific::panic_result::{divide, safe_divide};
     // let result = divide(10, 0); // 这会 panic!
     // println!("Result: {}", result);
 
@@ -161,6 +146,8 @@ fn main() {
     //     Err(e) => eprintln!("Error: {}", e),
     // }
     // Builder 模式 --------------------------------------------------
+    // use design_pattern::creational::builder_default::ConfigBuilder;
+    // use std::time::Duration;
     // 使用 Builder 构造 Config，默认值 + 自定义部分字段
     // let config = ConfigBuilder::default()
     //     .timeout(Duration::from_secs(10))
@@ -175,6 +162,8 @@ fn main() {
     //Send	如果一个类型 T: Send，那么它可以安全地在线程间转移所有权（即可以通过 channel 发送到另一个线程）
     //Sync	如果一个类型 T: Sync，那么它可以被多个线程同时安全地共享引用（即 &T 可以跨线程传递）
     //注：移动是语法行为，而Send是语义承诺。Rust 要求只有实现了 Send 的类型才能被安全地 move 到另一个线程中，以保障线程安全。
+    // use std::sync::Arc;
+    // use std::thread;
     //场景1：Send示例 - 跨线程传递数据
     // let data = vec![1, 2, 3];
     // thread::spawn(move || {
@@ -197,12 +186,14 @@ fn main() {
     
 
     //一个简单的“智能指针”封装裸指针，并用 PhantomData 标记它“像”拥有 T 类型的数据
+    // use design_pattern::specific::phantom_data::MyBox2;
     // let val = 42;
     // let boxed = MyBox2::new(&val);
 
     // println!("Value: {}", boxed.get()); // 输出 42
     
     //Drop----------------------------------------------------------
+    // use design_pattern::specific::drop::MyResource;
     // {
     //     let _resource1 = MyResource { name: "Resource 1".to_string() };
     //     let _resource2 = MyResource { name: "Resource 2".to_string() };
@@ -212,6 +203,9 @@ fn main() {
     // println!("Exited outer scope. Resources have been dropped.");
 
     //Interior Mutability（内部可变性）是 Rust 独有的设计模式----------------
+    // use design_pattern::specific::interior_mutability::{MyData, ReadWriteData, SharedCounter};
+    // use std::sync::{Arc, Mutex};
+    // use std::thread;
     // RefCell 示例
     // let data = MyData::new(5);
     // data.increment();
@@ -250,6 +244,7 @@ fn main() {
     //     println!("Read 2: {}", read2); // world
     // }
     //使用 Deref coercion（解引用强制转换） 的完整示例代码，演示如何通过实现 Deref trait 来实现智能指针的透明访问：
+    // use design_pattern::specific::deref_coercion::{say_hello, MyBox};
     // let x = 5;
     // let y = MyBox::new(x);
 
@@ -262,11 +257,13 @@ fn main() {
 
 
     //NewType 模式 --------------------------------------------------
+    // use design_pattern::specific::new_type::UserId;
     // 使用元组结构体包装基础类型以增加类型安全性
     // let user_id = UserId::new(42);
     // println!("User ID: {}", user_id.get());
 
     //桥接模式 --------------------------------------------------
+    // use design_pattern::structural::bridge::{Circle, DirectXRenderer, OpenGLRenderer, Renderer};
     //  创建OpenGL渲染器、创建DirectX渲染器
     // let opengl_renderer = Box::new(OpenGLRenderer);
     // let directx_renderer : Box<dyn Renderer + Send + Sync> = Box::new(DirectXRenderer);
@@ -280,6 +277,9 @@ fn main() {
     // circle2.draw();
 
     //object poll -------------------------------------------------
+    // use design_pattern::creational::objectpoll::ConnectionPool;
+    // use std::sync::Arc;
+    // use std::thread;
     // 创建包含 3 个连接的连接池（线程安全）
     // let pool = ConnectionPool::new(3);
 
@@ -306,12 +306,14 @@ fn main() {
     // }
 
     // 单例模式 --------------------------------------------------
+    // use design_pattern::creational::singleton2::Singleton2;
     // let instance2 = Singleton2::get_instance();
     // {
     //     let locked = instance2.lock().unwrap();
     //     println!("Value: {}", locked.value);
     // }
     // 单例模式 --------------------------------------------------
+    // use design_pattern::creational::singleton::Singleton;
     // let instance = Singleton::get_instance();
     // instance.do_something();
 
@@ -322,6 +324,7 @@ fn main() {
     //     std::ptr::eq(instance, another_instance)
     // );
     //适配器模式 --------------------------------------------------
+    // use design_pattern::structural::adapter::{LegacyRectangle, RectangleAdapter, Shape};
     // let legacy_rect = LegacyRectangle {
     //     x: 10,
     //     y: 20,
@@ -339,6 +342,7 @@ fn main() {
     //     shape.draw();
     // }
     // //享元模式--------------------------------------------------
+    // use design_pattern::structural::flyweight::FlyweightFactory;
     // let mut factory = FlyweightFactory { flyweights: vec![] };
 
     // //获取享元对象
@@ -347,6 +351,7 @@ fn main() {
     // factory.handle("state3");
 
     // //创建具体子类对象
+    // use design_pattern::behavioral::template_method::{AbstractClass, ConcreteClassA, ConcreteClassB};
     // let class_a = ConcreteClassA;
     // let class_b = ConcreteClassB;
 
@@ -355,10 +360,12 @@ fn main() {
     // class_b.template_method();
 
     // //代理模式 --------------------------------------------------
+    // use design_pattern::structural::proxy::{Proxy, RealSubject, Subject};
     // let proxy = Proxy { real_subject: RealSubject };
     // proxy.request();
 
     // //策略模式 --------------------------------------------------
+    // use design_pattern::behavioral::strategy::{ConcreteStrategyA, ConcreteStrategyB, Context};
     // let strategy_a = Box::new(ConcreteStrategyA);
     // let strategy_b = Box::new(ConcreteStrategyB);
 
@@ -369,6 +376,7 @@ fn main() {
     // context.execute_strategy();
 
     // //职责链模式 --------------------------------------------------
+    // use design_pattern::behavioral::responsibility_chain::{ConcreteHandlerA, ConcreteHandlerB, Handler, Request};
     // let mut handler_a = ConcreteHandlerA { next_handler: None };
     // let mut handler_b = ConcreteHandlerB { next_handler: None };
     // //1
@@ -381,4 +389,226 @@ fn main() {
 
     // let request_c = Request { content: String::from("Request C") };
     // handler_a.handle_request(&request_c);
+
+    // //责任链追踪 --------------------------------------------------
+    // //记录每个请求经过的处理者、各自的决定和耗时，并汇总每个处理者的统计
+    // use design_pattern::behavioral::chain_trace::ChainTracer;
+    // let mut tracer = ChainTracer::new(100);
+    // let trace = tracer.dispatch(&handler_a, &request_c);
+    // print!("{}", trace);
+    // print!("{}", tracer.dump());
+
+    // //审批流程 --------------------------------------------------
+    // //组长 1000 以内、经理 10000 以内，超过额度逐级升级，暂缓一小时后自动升级
+    // use design_pattern::behavioral::approval::{ApprovalDecision, ApprovalWorkflow, Approver, PurchaseRequest};
+    // let mut team_lead = Approver::new("team lead", 1_000);
+    // let manager = Approver::new("manager", 10_000).with_policy(|_| ApprovalDecision::Defer);
+    // team_lead.set_next(Box::new(manager));
+    // let clock = Arc::new(design_pattern::behavioral::clock::ManualClock::new(Duration::ZERO));
+    // let mut workflow = ApprovalWorkflow::new(Box::new(team_lead), clock.clone(), Duration::from_secs(3600));
+    // let request = PurchaseRequest { id: 1, requester: "alice".to_string(), amount: 8_000, description: "servers".to_string() };
+    // println!("{:?}", workflow.submit(request));
+    // clock.advance(Duration::from_secs(3600));
+    // println!("{:?}", workflow.process_timeouts());
+    // println!("{:?}", workflow.audit_log().entries());
+
+    // //HTTP 服务器 --------------------------------------------------
+    // //日志、鉴权、路由、静态文件都是责任链上的处理者，链上没人处理的请求返回 404
+    // use design_pattern::behavioral::http_server::{HttpHandler, HttpResponse, HttpServer, LoggingHandler, Router, ServerOptions, StaticFiles};
+    // let mut logging = LoggingHandler::new().with_writer(std::io::stdout());
+    // let mut router = Router::new().route("GET", "/", |_| HttpResponse::text(200, "index\n"));
+    // router.set_next(Box::new(StaticFiles::new("/static/", "./static")));
+    // logging.set_next(Box::new(router));
+    // let server = HttpServer::start("127.0.0.1:8080", Box::new(logging), ServerOptions::default()).unwrap();
+    // println!("listening on http://{}", server.local_addr());
+    // thread::sleep(Duration::from_secs(60));
+    // server.shutdown();
+
+    // //声明式状态机 --------------------------------------------------
+    // //投票机的迁移表版本：未定义的迁移返回错误而不是原地不动
+    // use design_pattern::behavioral::state_machine::{voting_machine, VoteEvent};
+    // let mut machine = voting_machine();
+    // println!("{:?}", machine.fire(VoteEvent::Vote));
+    // println!("{:?}", machine.fire(VoteEvent::Vote));
+    // println!("{:?}", machine.fire(VoteEvent::ClosePoll));
+
+    // //状态机导出为图 --------------------------------------------------
+    // //生成 Graphviz DOT 和 Mermaid stateDiagram，当前状态高亮
+    // use design_pattern::behavioral::state_diagram::{to_dot, to_mermaid};
+    // let machine = voting_machine();
+    // println!("{}", to_dot(&machine, "VotingMachine"));
+    // println!("{}", to_mermaid(&machine));
+
+    // //Typestate 投票机 --------------------------------------------------
+    // //状态在类型中，vote() 消耗 self 并返回下一个状态的类型，锁定后再 vote 无法编译
+    // use design_pattern::behavioral::typestate::VotingMachine as TypestateVotingMachine;
+    // let machine = TypestateVotingMachine::new("alice").vote().lock();
+    // machine.status();
+    // // machine.vote(); // 编译错误：VotingMachine<Locked> 没有 vote 方法
+
+    // //分层状态机 --------------------------------------------------
+    // //Playing/Paused 嵌套在 PoweredOn 中，关机由父状态处理，开机后通过深历史回到关机前的子状态
+    // use design_pattern::behavioral::hierarchical_state::{media_player, PlayerEvent};
+    // let mut player = media_player();
+    // for event in [PlayerEvent::Power, PlayerEvent::Play, PlayerEvent::Pause, PlayerEvent::Power, PlayerEvent::Power] {
+    //     player.dispatch(&event).unwrap();
+    //     println!("{:?} -> {:?}", event, player.configuration());
+    // }
+
+    // //迁移日志与回放 --------------------------------------------------
+    // //记录每次迁移的事件、前后状态、时间戳和守卫结果，可以导出 JSON lines，也可以回放到任意一条记录
+    // use design_pattern::behavioral::transition_log::RecordingMachine;
+    // let mut machine = RecordingMachine::new(voting_machine(), Arc::new(design_pattern::behavioral::clock::SystemClock));
+    // let _ = machine.fire(VoteEvent::Vote);
+    // let _ = machine.fire(VoteEvent::ClosePoll);
+    // print!("{}", machine.log().to_json_lines());
+    // let replayed = machine.log().replay(voting_machine, 1).unwrap();
+    // println!("state after 1 record: {:?}", replayed.state());
+
+    // //多人选举与计票策略 --------------------------------------------------
+    // //每个投票人只能投一张排序选票，投票关闭后用即时决选计票，输出逐轮报告
+    // use design_pattern::behavioral::election::{Ballot, BallotKind, Election, InstantRunoff};
+    // let poll = design_pattern::behavioral::state::Poll::new(Arc::new(design_pattern::behavioral::clock::SystemClock));
+    // let mut election = Election::new("club president", BallotKind::Ranked, &["Alice", "Bob"], poll).unwrap();
+    // let voter = election.register("v-001").unwrap();
+    // let alice = election.candidate("Alice").unwrap();
+    // election.cast(&voter, Ballot::Ranked(vec![alice])).unwrap();
+    // election.close().unwrap();
+    // print!("{}", election.tally(&InstantRunoff).unwrap());
+
+    // //状态机模型检查 --------------------------------------------------
+    // //穷举可达的状态和上下文，报告不可达状态、死状态、未定义迁移和不变式违例，并给出最短反例
+    // use design_pattern::behavioral::model_check::ModelChecker;
+    // use design_pattern::behavioral::state_machine::{voting_machine, VoteEvent, VoteState};
+    // let checker = ModelChecker::new(&[VoteEvent::Vote, VoteEvent::ClosePoll, VoteEvent::ReopenPoll, VoteEvent::DeadlinePassed], 6)
+    //     .invariant("voted state implies a recorded vote", |state, voted: &bool| state != VoteState::Voted || *voted);
+    // print!("{}", checker.check(&mut voting_machine()));
+
+    // //带返回值的访问者 --------------------------------------------------
+    // //StateVisitor 的关联类型 Output 决定访问结果：界面标签、JSON 序列化、校验结果等
+    // use design_pattern::behavioral::visitor::{JsonVisitor, LabelVisitor, VotingMachine as VisitableVotingMachine};
+    // let machine = VisitableVotingMachine::new();
+    // println!("{}", machine.accept(&mut LabelVisitor));
+    // println!("{}", machine.accept(&mut JsonVisitor));
+
+    // //表达式树上的访问者 --------------------------------------------------
+    // //求值、格式化、常量折叠、自由变量和类型检查都是访问者，Expr 只提供 accept
+    // use std::collections::HashMap;
+    // use design_pattern::behavioral::expression::{BinaryOp, ConstantFolder, Expr};
+    // let expr = Expr::binary(BinaryOp::Mul, Expr::var("x"), Expr::binary(BinaryOp::Add, Expr::num(1.0), Expr::num(2.0)));
+    // println!("{} => {}", expr, expr.accept(&mut ConstantFolder::new(&HashMap::new())));
+
+    // //图形访问者 --------------------------------------------------
+    // //原型模式的 Circle 和适配器中的 LegacyRectangle 转换成统一的 Shape，面积、包围盒、SVG 等都是访问者
+    // use design_pattern::behavioral::geometry::{to_svg, Measure, Shape as GeometryShape};
+    // let shapes = vec![GeometryShape::from(&Circle { x: 10, y: 20, radius: 5 }), GeometryShape::from(&LegacyRectangle { x: 0, y: 0, width: 4, height: 3 })];
+    // for shape in &shapes {
+    //     println!("{} area={:.2}", shape, shape.accept(&mut Measure).area);
+    // }
+    // print!("{}", to_svg(&shapes, "none"));
+
+    // //投票机集群统计 --------------------------------------------------
+    // //多个线程各自统计一部分投票机，按机器编号合并：各状态机器数、停留时间和异常
+    // use design_pattern::behavioral::fleet::{visit_fleet, FleetStats};
+    // use design_pattern::behavioral::visitor::VotingMachine as VisitableVotingMachine;
+    // let machines: Vec<VisitableVotingMachine> = (0..100).map(|_| VisitableVotingMachine::new()).collect();
+    // let stats = visit_fleet(&machines, 4, || FleetStats::new(Arc::new(design_pattern::behavioral::clock::SystemClock), Duration::from_secs(3600)));
+    // print!("{}", stats);
+
+    // //按名称选择策略 --------------------------------------------------
+    // //策略登记在注册表中，根据命令行参数 --strategy=a 选择；名称写错时列出可用的策略
+    // use design_pattern::behavioral::strategy::StrategyRegistry;
+    // let registry: StrategyRegistry = StrategyRegistry::new()
+    //     .register("a", || Box::new(ConcreteStrategyA))
+    //     .register("b", || Box::new(ConcreteStrategyB));
+    // let args: Vec<String> = std::env::args().collect();
+    // match registry.select_from_args(&args, "--strategy", "a") {
+    //     Ok(strategy) => strategy.execute(()),
+    //     Err(e) => println!("{}", e),
+    // }
+
+    // //可替换的压缩策略 --------------------------------------------------
+    // //压缩结果带有策略编号，解压时自动选择对应的策略
+    // use design_pattern::behavioral::compression::{codec_by_name, CompressionContext};
+    // let context = CompressionContext::new(codec_by_name("huffman").unwrap());
+    // let frame = context.compress(b"abracadabra").unwrap();
+    // println!("{} -> {} bytes, {:?}", 11, frame.len(), context.decompress(&frame));
+
+    // //重试与退避策略 --------------------------------------------------
+    // //重试次数取自 Config 的 retries，错误由调用方分为可重试和致命两类
+    // use design_pattern::behavioral::retry::{ErrorClass, ExponentialBackoff, Retrier, SeededRng};
+    // let config = ConfigBuilder::default().retries(3).build();
+    // let strategy = ExponentialBackoff::from_config(&config, Duration::from_millis(50), Duration::from_secs(1));
+    // let mut retrier = Retrier::new(Box::new(strategy), Arc::new(design_pattern::behavioral::clock::SystemClock), Box::new(SeededRng::from_time()));
+    // let report = retrier.run(|_: &String| ErrorClass::Retryable, |n| if n < 3 { Err(format!("attempt {} failed", n)) } else { Ok(n) });
+    // print!("{}", report);
+
+    // //多个连接池的负载均衡 --------------------------------------------------
+    // //借出的连接离开作用域时归还到原来的连接池，运行时切换策略不影响已借出的连接
+    // use design_pattern::behavioral::load_balancer::{Backend, LoadBalancer, RoundRobin, LeastOutstanding};
+    // let balancer = LoadBalancer::new(
+    //     vec![Backend::new("primary", 3, ConnectionPool::new(2)), Backend::new("replica", 1, ConnectionPool::new(2))],
+    //     Arc::new(RoundRobin::default()),
+    //     Arc::new(design_pattern::behavioral::clock::SystemClock),
+    // );
+    // let lease = balancer.acquire(None).unwrap();
+    // balancer.set_strategy(Arc::new(LeastOutstanding));
+    // println!("{} via {}", lease.backend(), balancer.acquire(None).unwrap().backend());
+
+    // //可替换淘汰策略的缓存 --------------------------------------------------
+    // //策略名可以来自配置；simulate 用同一段访问日志比较各策略的命中率
+    // use design_pattern::behavioral::cache::{parse_trace, policy_by_name, simulate, simulation_report, Cache};
+    // let mut cache = Cache::new(100, policy_by_name("arc").unwrap());
+    // cache.put("/index.html".to_string(), "<html>...</html>");
+    // println!("{:?} {}", cache.get(&"/index.html".to_string()), cache.stats());
+    // let trace = parse_trace(&std::fs::read_to_string("access.log").unwrap_or_default());
+    // print!("{}", simulation_report(&simulate(&trace, 100)));
+
+    // //静态分发的策略环境 --------------------------------------------------
+    // //策略在编译期已知时不需要 Box，也不经过虚表；需要时再转成 Context
+    // use design_pattern::behavioral::strategy::StaticContext;
+    // let context = StaticContext::new(ConcreteStrategyA);
+    // context.execute(());
+    // let dynamic: Context = context.into();
+    // dynamic.execute_strategy();
+
+    // //可失败的模板方法 --------------------------------------------------
+    // //步骤返回 Result，第一个失败的步骤结束流程，错误中带有步骤名称和各步骤耗时
+    // use design_pattern::behavioral::template_method::FallibleTemplate;
+    // match job.run() {
+    //     Ok(report) => print!("{}", report),
+    //     Err(e) => println!("{}\n{}", e, e.report),
+    // }
+
+    // //ETL 任务 --------------------------------------------------
+    // //具体任务只实现 extract、transform、load；出错的行写入死信文件，中断后从检查点继续
+    // use design_pattern::behavioral::etl::{EtlJob, EtlOptions};
+    // let options = EtlOptions::new()
+    //     .dead_letter(std::path::Path::new("orders.dead.jsonl"))
+    //     .checkpoint(std::path::Path::new("orders.checkpoint"), 100);
+    // match job.run(&options) {
+    //     Ok(report) => println!("{}", report),
+    //     Err(e) => println!("job stopped, rerun to resume: {}", e),
+    // }
+
+    // //固定步长的游戏循环 --------------------------------------------------
+    // //回放录像时帧率可以不同，模拟结果只取决于 dt 和输入，最终哈希与录像一致
+    // use design_pattern::behavioral::game_loop::{Game, LoopConfig, Recording, TextRenderer};
+    // let recording: Recording<Command> = Recording::load(std::path::Path::new("match.rec")).unwrap();
+    // let config = LoopConfig::new(Arc::new(design_pattern::behavioral::clock::SystemClock), recording.dt).max_ticks(recording.ticks);
+    // let mut renderer = TextRenderer::new(80, 24, 1);
+    // let summary = game.run(&config, &mut recording.replay(), &mut renderer);
+    // println!("{}\n{:?}", renderer.last_frame().unwrap_or(""), recording.verify(&summary));
+
+    // //报表模板 --------------------------------------------------
+    // //报表结构固定，换一个渲染器就得到 Markdown、HTML 或 CSV
+    // use design_pattern::behavioral::template_method::{Column, MarkdownRenderer, NumberFormat, Report, ReportRenderer, Section, Table};
+    // let report = Report::new("Inventory").section(
+    //     Section::new("Stock").table(
+    //         Table::new(vec![Column::text("Item"), Column::number("Count", NumberFormat::Fixed { decimals: 0, thousands: true })])
+    //             .row(vec!["bolts".into(), 12000.0.into()])
+    //             .totals("Total"),
+    //     ),
+    // );
+    // print!("{}", MarkdownRenderer.render(&report));
 }
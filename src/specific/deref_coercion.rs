@@ -30,7 +30,7 @@ pub fn say_hello(name: &str) {
     println!("Hello, {}!", name);
 }
 
-fn main() {
+pub fn main() {
     let x = 5;
     let y = MyBox::new(x);
 
@@ -71,10 +71,8 @@ impl ReadWriteData {
     }
 
     pub fn write_data(&self, new_val: &str) {
-        let mut guard = self.data.write();
-        match guard {
-            Ok(ref mut g) => **g = new_val.to_string(),
-            Err(_) => (),
+        if let Ok(mut guard) = self.data.write() {
+            *guard = new_val.to_string();
         }
     }
 }
@@ -14,7 +14,7 @@ pub fn safe_divide(a: i32, b: i32) -> Result<i32, String> {
 }
 
 
-fn main() {
+pub fn main() {
     let result = divide(10, 0); // 这会 panic!
     println!("Result: {}", result);
 
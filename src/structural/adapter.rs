@@ -35,7 +35,7 @@ impl Shape for RectangleAdapter {
 }
 //-------------------------------------------------------------------
 // 使用示例
-fn main() {
+pub fn main() {
     let legacy_rect = LegacyRectangle {
         x: 10,
         y: 20,
@@ -44,7 +44,7 @@ fn main() {
     };
 
     let adapter = RectangleAdapter {
-        legacy_rect: legacy_rect,
+        legacy_rect,
     };
 
     let shapes: Vec<&dyn Shape> = vec![&adapter];
@@ -43,7 +43,7 @@ impl<T: Component> Component for ConcreteDecoratorA<T> {
     }
 }
 //--------------------------------------------------------------------------------------------------
-fn main() {
+pub fn main() {
     let component = ConcreteComponent;
     let decorated_component = ConcreteDecoratorA::new(component);
     decorated_component.operation();
@@ -24,7 +24,7 @@ pub struct FlyweightFactory {
 
 impl FlyweightFactory {
     ///处理数据
-    pub(crate) fn handle(&mut self, s: &str) ->Option<&ConcreteFlyweight> {
+    pub fn handle(&mut self, s: &str) ->Option<&ConcreteFlyweight> {
         //不可变借用
         if let Some(i)
             = self.flyweights.iter().position(|x| x.intrinsic_state == s ) {
@@ -54,7 +54,7 @@ impl FlyweightFactory {
      */
     pub fn push_flyweight(&mut self, intrinsic_state: String) {
         let flyweight2 = ConcreteFlyweight {
-            intrinsic_state: intrinsic_state,
+            intrinsic_state,
         };
         self.flyweights.push(flyweight2);//可变借用
     }